@@ -6,7 +6,7 @@ use std::{
 
 use hpi_parser::{ast::*, Span};
 
-use crate::{ast::*, Diagnostic, DiagnosticLevel, ErrorKind};
+use crate::{ast::*, builtins, Diagnostic, DiagnosticLevel, ErrorKind};
 
 #[derive(Default, Debug)]
 pub struct Analyzer<'src> {
@@ -34,12 +34,19 @@ struct Function<'src> {
     pub params: Spanned<'src, Vec<Parameter<'src>>>,
     pub return_type: Spanned<'src, Type>,
     pub used: bool,
+    /// The index of the function in [`AnalyzedProgram::functions`], `None` for the main functions.
+    pub index: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
 enum ParamTypes {
     VarArgs(Vec<Type>, Type),
     Normal(Vec<Type>),
+    /// Like `Normal`, but the parameters of the second list may be omitted from the end.
+    Optional(Vec<Type>, Vec<Type>),
+    /// Validates the argument types and returns the result type of the call.
+    /// Used for builtins which are generic over their arguments.
+    Generic(fn(&[Type]) -> Result<Type, String>),
 }
 
 #[derive(Debug, Clone)]
@@ -153,7 +160,16 @@ impl<'src> Analyzer<'src> {
             .collect();
 
         // add all function signatures first
+        // the main functions are not part of `AnalyzedProgram::functions`, so they have no index
+        let mut next_index = 0;
         for func in &program.functions {
+            let index = match func.name.inner {
+                "Bewerbung" | "Einschreibung" | "Studium" => None,
+                _ => {
+                    next_index += 1;
+                    Some(next_index - 1)
+                }
+            };
             // check for duplicate function names
             if let Some(prev_def) = self.functions.get(func.name.inner) {
                 let prev_def_span = prev_def.ident.span;
@@ -181,6 +197,7 @@ impl<'src> Analyzer<'src> {
                     params: func.params.clone(),
                     return_type: func.return_type.clone(),
                     used: false,
+                    index,
                 },
             );
         }
@@ -368,24 +385,228 @@ impl<'src> Analyzer<'src> {
                     BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::Any),
                 );
             }
+            ("Zergliedere_JSON_Streng", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Zergliedere_JSON_Streng",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::Any),
+                );
+            }
             ("Gliedere_JSON", "Textverarbeitung") => {
                 self.builtin_functions.insert(
                     "Gliedere_JSON",
                     BuiltinFunction::new(ParamTypes::Normal(vec![Type::Unknown]), Type::String(0)),
                 );
             }
+            ("Gliedere_JSON_Schön", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Gliedere_JSON_Schön",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::Unknown]), Type::String(0)),
+                );
+            }
+            ("Gliedere_JSON_Sortiert", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Gliedere_JSON_Sortiert",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::Unknown, Type::String(0)]), Type::String(0)),
+                );
+            }
             ("Formatiere", "Textverarbeitung") => {
                 self.builtin_functions.insert(
                     "Formatiere",
                     BuiltinFunction::new(ParamTypes::VarArgs(vec![Type::String(0)], Type::Unknown), Type::String(0)),
                 );
             }
+            ("ErsetzeAlleRegex", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "ErsetzeAlleRegex",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::String(0), Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("PasstAuf", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "PasstAuf",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::String(0)]), Type::Bool(0)),
+                );
+            }
+            ("FindeMuster", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "FindeMuster",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::String(0)]), Type::List(Box::new(Type::String(0)), 0)),
+                );
+            }
+            ("AlsWahrheitswert", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "AlsWahrheitswert",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::Bool(0)),
+                );
+            }
+            ("KehreZeichenketteUm", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "KehreZeichenketteUm",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("Grossbuchstaben", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Grossbuchstaben",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("Kleinbuchstaben", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Kleinbuchstaben",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("Trennen", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Trennen",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::String(0)]), Type::List(Box::new(Type::String(0)), 0)),
+                );
+            }
+            ("Ersetzen", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Ersetzen",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::String(0), Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("Zuschneiden", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Zuschneiden",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("Zuschneiden_Links", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Zuschneiden_Links",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("Zuschneiden_Rechts", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Zuschneiden_Rechts",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("Zeichen_Bei", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Zeichen_Bei",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::Int(0)]), Type::Char(0)),
+                );
+            }
+            ("Ist_Zahl", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Ist_Zahl",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::Bool(0)),
+                );
+            }
+            ("Ist_Fließkommazahl", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Ist_Fließkommazahl",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::Bool(0)),
+                );
+            }
+            ("Teilzeichenkette", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Teilzeichenkette",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::Int(0), Type::Int(0)]), Type::String(0)),
+                );
+            }
+            ("Index_Von", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Index_Von",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::String(0)]), Type::Int(0)),
+                );
+            }
+            ("Beginnt_Mit", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Beginnt_Mit",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::String(0)]), Type::Bool(0)),
+                );
+            }
+            ("Endet_Mit", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Endet_Mit",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::String(0)]), Type::Bool(0)),
+                );
+            }
+            ("Wiederhole", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Wiederhole",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::Int(0)]), Type::String(0)),
+                );
+            }
+            ("Zeichen_Code", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Zeichen_Code",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::Char(0)]), Type::Int(0)),
+                );
+            }
+            ("Von_Code", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Von_Code",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::Int(0)]), Type::Char(0)),
+                );
+            }
+            ("Verbinden", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "Verbinden",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::List(Box::new(Type::String(0)), 0), Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("ZerlegeZeilen", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "ZerlegeZeilen",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::List(Box::new(Type::String(0)), 0)),
+                );
+            }
+            ("ListeAlsText", "Textverarbeitung") => {
+                self.builtin_functions.insert(
+                    "ListeAlsText",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::liste_als_text), Type::String(0)),
+                );
+            }
             ("Drucke", "Drucker") => {
                 self.builtin_functions.insert(
                     "Drucke",
                     BuiltinFunction::new(ParamTypes::VarArgs(vec![], Type::Unknown), Type::Nichts),
                 );
             }
+            ("Schreibe", "Drucker") => {
+                self.builtin_functions.insert(
+                    "Schreibe",
+                    BuiltinFunction::new(ParamTypes::VarArgs(vec![], Type::Unknown), Type::Nichts),
+                );
+            }
+            ("Fehlerdrucke", "Drucker") => {
+                self.builtin_functions.insert(
+                    "Fehlerdrucke",
+                    BuiltinFunction::new(ParamTypes::VarArgs(vec![], Type::Unknown), Type::Nichts),
+                );
+            }
+            ("DruckeDebug", "Drucker") => {
+                self.builtin_functions.insert(
+                    "DruckeDebug",
+                    BuiltinFunction::new(ParamTypes::VarArgs(vec![Type::Int(0)], Type::Unknown), Type::Nichts),
+                );
+            }
+            ("DruckeKanal", "Drucker") => {
+                self.builtin_functions.insert(
+                    "DruckeKanal",
+                    BuiltinFunction::new(ParamTypes::VarArgs(vec![Type::String(0)], Type::Unknown), Type::Nichts),
+                );
+            }
+            ("Leere_Ausgabe", "Drucker") => {
+                self.builtin_functions.insert(
+                    "Leere_Ausgabe",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![]), Type::Nichts),
+                );
+            }
+            ("DruckeTabelle", "Drucker") => {
+                self.builtin_functions.insert(
+                    "DruckeTabelle",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::drucke_tabelle), Type::Nichts),
+                );
+            }
             ("Geld", "Hasso") => {
                 self.builtin_functions.insert(
                     "Geld",
@@ -394,16 +615,7 @@ impl<'src> Analyzer<'src> {
             }
 
             ("Zeit", "Uhr") => {
-                let timestamp_type = Type::Object(vec![
-                        ObjectTypeField { key: "Jahr".to_string(), type_: Box::new(Type::Int(0)) },
-                        ObjectTypeField { key: "Monat".to_string(), type_: Box::new(Type::Int(0)) },
-                        ObjectTypeField { key: "Kalendar_Tag".to_string(), type_: Box::new(Type::Int(0)) },
-                        ObjectTypeField { key: "Wochentag".to_string(), type_: Box::new(Type::Int(0)) },
-                        ObjectTypeField { key: "Stunde".to_string(), type_: Box::new(Type::Int(0)) },
-                        ObjectTypeField { key: "Minute".to_string(), type_: Box::new(Type::Int(0)) },
-                        ObjectTypeField { key: "Sekunde".to_string(), type_: Box::new(Type::Int(0)) },
-
-                ], 0);
+                let timestamp_type = builtins::zeitstempel_type();
 
                 self.types.insert("Zeitstempel", Spanned { span: node.value_name.span, inner: timestamp_type.clone() });
 
@@ -412,10 +624,22 @@ impl<'src> Analyzer<'src> {
                     BuiltinFunction::new(ParamTypes::Normal(vec![]), timestamp_type),
                 );
             }
+            ("Zeit_Formatieren", "Uhr") => {
+                self.builtin_functions.insert(
+                    "Zeit_Formatieren",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::zeit_formatieren), Type::String(0)),
+                );
+            }
+            ("Zeit_Millisekunden", "Uhr") => {
+                self.builtin_functions.insert(
+                    "Zeit_Millisekunden",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![]), Type::Int(0)),
+                );
+            }
             ("Http", "Netzwerk") => {
                 self.builtin_functions.insert(
                     "Http",
-                    BuiltinFunction::new(ParamTypes::Normal(vec![
+                    BuiltinFunction::new(ParamTypes::Optional(vec![
                                             Type::String(0), // method
                                             Type::String(0), // url
                                             Type::String(0), // body
@@ -424,15 +648,220 @@ impl<'src> Analyzer<'src> {
                                                 ObjectTypeField{ key: "Wert".to_string(), type_: Box::new(Type::String(0)) },
                                             ], 0)), 0), // headers
                                             Type::String(1), // body dest
+                    ], vec![
+                                            Type::AnyObject(1), // response headers dest
                     ]), Type::Int(0)),
                 );
             }
             ("Aufgeben", "libSAP") => {
                 self.builtin_functions.insert("Aufgeben", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Int(0)]), Type::Never));
             },
+            ("Versuche", "libSAP") => {
+                self.types.insert("Versuchsergebnis", Spanned { span: node.value_name.span, inner: builtins::versuchsergebnis_type() });
+                self.builtin_functions.insert("Versuche", BuiltinFunction::new(ParamTypes::Generic(builtins::versuche), Type::Unknown));
+            },
+            ("AlsListe", "libSAP") => {
+                self.types.insert("Eintrag", Spanned { span: node.value_name.span, inner: builtins::eintrag_type() });
+                self.builtin_functions.insert("AlsListe", BuiltinFunction::new(ParamTypes::Generic(builtins::als_liste), Type::Unknown));
+            },
+            ("GrößeVon", "libSAP") => {
+                self.builtin_functions.insert("GrößeVon", BuiltinFunction::new(ParamTypes::Generic(builtins::groesse_von), Type::Int(0)));
+            },
+            ("Länge", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Länge",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::laenge), Type::Int(0)),
+                );
+            }
+            ("IstLeer", "libSAP") => {
+                self.builtin_functions.insert("IstLeer", BuiltinFunction::new(ParamTypes::Generic(builtins::ist_leer), Type::Bool(0)));
+            },
+            ("Typ", "libSAP") => {
+                self.builtin_functions.insert("Typ", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Unknown]), Type::String(0)));
+            },
+            ("MerkeDir", "libSAP") => {
+                self.builtin_functions.insert("MerkeDir", BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0), Type::Unknown]), Type::Nichts));
+            },
+            ("HatSchon", "libSAP") => {
+                self.builtin_functions.insert("HatSchon", BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::Bool(0)));
+            },
+            ("HoleGemerkt", "libSAP") => {
+                self.builtin_functions.insert("HoleGemerkt", BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::Any));
+            },
+            ("Rotiere", "libSAP") => {
+                self.builtin_functions.insert("Rotiere", BuiltinFunction::new(ParamTypes::Generic(builtins::rotiere), Type::Unknown));
+            },
+            ("Teilliste", "libSAP") => {
+                self.builtin_functions.insert("Teilliste", BuiltinFunction::new(ParamTypes::Generic(builtins::teilliste), Type::Unknown));
+            },
+            ("Abbilden", "libSAP") => {
+                self.builtin_functions.insert("Abbilden", BuiltinFunction::new(ParamTypes::Generic(builtins::abbilden), Type::Unknown));
+            },
+            ("Filtern", "libSAP") => {
+                self.builtin_functions.insert("Filtern", BuiltinFunction::new(ParamTypes::Generic(builtins::filtern), Type::Unknown));
+            },
+            ("Reduziere", "libSAP") => {
+                self.builtin_functions.insert("Reduziere", BuiltinFunction::new(ParamTypes::Generic(builtins::reduziere), Type::Unknown));
+            },
+            ("MaximumNach", "libSAP") => {
+                self.builtin_functions.insert(
+                    "MaximumNach",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::maximum_nach), Type::Unknown),
+                );
+            }
+            ("MinimumNach", "libSAP") => {
+                self.builtin_functions.insert(
+                    "MinimumNach",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::minimum_nach), Type::Unknown),
+                );
+            }
+            ("Maximum", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Maximum",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::maximum), Type::Unknown),
+                );
+            }
+            ("Minimum", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Minimum",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::minimum), Type::Unknown),
+                );
+            }
+            ("Lies_Zeile", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Lies_Zeile",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![]), Type::String(0)),
+                );
+            }
+            ("Enthält", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Enthält",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::enthaelt), Type::Unknown),
+                );
+            }
+            ("Hinzufügen", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Hinzufügen",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::hinzufuegen), Type::Unknown),
+                );
+            }
+            ("Entferne_Letztes", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Entferne_Letztes",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::entferne_letztes), Type::Unknown),
+                );
+            }
+            ("Umkehren", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Umkehren",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::umkehren), Type::Unknown),
+                );
+            }
+            ("Kopiere", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Kopiere",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::kopiere), Type::Unknown),
+                );
+            }
+            ("Sortiere", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Sortiere",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::sortiere), Type::Unknown),
+                );
+            }
+            ("Schlüssel", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Schlüssel",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::AnyObject(0)]), Type::List(Box::new(Type::String(0)), 0)),
+                );
+            }
+            ("Objekt_Schlüssel", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Objekt_Schlüssel",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::objekt_schluessel), Type::Unknown),
+                );
+            }
+            ("Feld", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Feld",
+                    BuiltinFunction::new(ParamTypes::Generic(builtins::feld), Type::Unknown),
+                );
+            }
+            ("Hole", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Hole",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::AnyObject(0), Type::String(0)]), Type::Any),
+                );
+            }
+            ("Setze", "libSAP") => {
+                self.builtin_functions.insert(
+                    "Setze",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::AnyObject(0), Type::String(0), Type::Unknown]), Type::Nichts),
+                );
+            }
             ("Umgebungsvariablen", "libSAP") => {
                 self.builtin_functions.insert("Umgebungsvariablen", BuiltinFunction::new(ParamTypes::Normal(vec![]), Type::AnyObject(0)));
             },
+            ("UmgebungsvariableEgalGroß", "libSAP") => {
+                self.builtin_functions.insert(
+                    "UmgebungsvariableEgalGroß",
+                    BuiltinFunction::new(ParamTypes::Normal(vec![Type::String(0)]), Type::String(0)),
+                );
+            }
+            ("Summe", "Mathe") => {
+                self.builtin_functions.insert("Summe", BuiltinFunction::new(ParamTypes::Generic(builtins::summe), Type::Unknown));
+            },
+            ("Durchschnitt", "Mathe") => {
+                self.builtin_functions.insert("Durchschnitt", BuiltinFunction::new(ParamTypes::Generic(builtins::durchschnitt), Type::Unknown));
+            },
+            ("GgT", "Mathe") => {
+                self.builtin_functions.insert("GgT", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Int(0), Type::Int(0)]), Type::Int(0)));
+            },
+            ("KgV", "Mathe") => {
+                self.builtin_functions.insert("KgV", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Int(0), Type::Int(0)]), Type::Int(0)));
+            },
+            ("IstPrim", "Mathe") => {
+                self.builtin_functions.insert("IstPrim", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Int(0)]), Type::Bool(0)));
+            },
+            ("Primfaktoren", "Mathe") => {
+                self.builtin_functions.insert("Primfaktoren", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Int(0)]), Type::List(Box::new(Type::Int(0)), 0)));
+            },
+            ("Begrenze", "Mathe") => {
+                self.builtin_functions.insert("Begrenze", BuiltinFunction::new(ParamTypes::Generic(builtins::begrenze), Type::Unknown));
+            },
+            ("AbstandZwischen", "Mathe") => {
+                self.builtin_functions.insert("AbstandZwischen", BuiltinFunction::new(ParamTypes::Generic(builtins::abstand_zwischen), Type::Unknown));
+            },
+            ("Betrag", "Mathe") => {
+                self.builtin_functions.insert("Betrag", BuiltinFunction::new(ParamTypes::Generic(builtins::betrag), Type::Unknown));
+            },
+            ("Wurzel", "Mathe") => {
+                self.builtin_functions.insert("Wurzel", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Float(0)]), Type::Float(0)));
+            },
+            ("Sinus", "Mathe") => {
+                self.builtin_functions.insert("Sinus", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Float(0)]), Type::Float(0)));
+            },
+            ("Kosinus", "Mathe") => {
+                self.builtin_functions.insert("Kosinus", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Float(0)]), Type::Float(0)));
+            },
+            ("Runde", "Mathe") => {
+                self.builtin_functions.insert("Runde", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Float(0)]), Type::Float(0)));
+            },
+            ("Abrunden", "Mathe") => {
+                self.builtin_functions.insert("Abrunden", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Float(0)]), Type::Float(0)));
+            },
+            ("Aufrunden", "Mathe") => {
+                self.builtin_functions.insert("Aufrunden", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Float(0)]), Type::Float(0)));
+            },
+            ("Potenz", "Mathe") => {
+                self.builtin_functions.insert("Potenz", BuiltinFunction::new(ParamTypes::Generic(builtins::potenz), Type::Unknown));
+            },
+            ("Modulo", "Mathe") => {
+                self.builtin_functions.insert("Modulo", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Int(0), Type::Int(0)]), Type::Int(0)));
+            },
+            ("Zufall", "Mathe") => {
+                self.builtin_functions.insert("Zufall", BuiltinFunction::new(ParamTypes::Normal(vec![Type::Int(0), Type::Int(0)]), Type::Int(0)));
+            },
             (value, module) => self.error(
                 ErrorKind::Reference,
                 format!("Dieser Antrag `{value}` von `{module}` wurde aufgrund falscher Angaben abgelehnt."),
@@ -814,6 +1243,7 @@ impl<'src> Analyzer<'src> {
         }
 
         let mut stmts = vec![];
+        let mut stmt_spans = vec![];
 
         let mut never_type_span = None;
         let mut warned_unreachable = false;
@@ -835,6 +1265,7 @@ impl<'src> Analyzer<'src> {
                     never_type_span = Some(stmt_span);
                 }
                 stmts.push(stmt);
+                stmt_spans.push(stmt_span);
             }
         }
 
@@ -869,6 +1300,7 @@ impl<'src> Analyzer<'src> {
         AnalyzedBlock {
             result_type,
             stmts,
+            stmt_spans,
             expr,
         }
     }
@@ -1691,6 +2123,29 @@ impl<'src> Analyzer<'src> {
             };
         }
 
+        // functions can be used as values, for instance as arguments of builtins
+        if let Some(func) = self.functions.get_mut(node.inner) {
+            // only mark the function as used if it is referenced from outside of its body
+            if self.curr_func_name != node.inner {
+                func.used = true;
+            }
+
+            let result_type = Type::Function {
+                params: func
+                    .params
+                    .inner
+                    .iter()
+                    .map(|param| param.type_.inner.clone())
+                    .collect(),
+                result_type: Box::new(func.return_type.inner.clone()),
+            };
+
+            return AnalyzedExpression::Ident(AnalyzedIdentExpr {
+                result_type,
+                ident: node.inner,
+            });
+        }
+
         // ignore empty identifiers (cannot be created by users)
         if !node.inner.is_empty() {
             self.error(
@@ -1847,8 +2302,9 @@ impl<'src> Analyzer<'src> {
         // evaluate constant expressions
         match (&expr, node.op) {
             (AnalyzedExpression::Int(num), PrefixOp::Not) => return AnalyzedExpression::Int(!num),
-            (AnalyzedExpression::Int(num), PrefixOp::Neg) => {
-                return AnalyzedExpression::Int(num.wrapping_neg())
+            // overflows are not folded, so that they are reported at runtime
+            (AnalyzedExpression::Int(num), PrefixOp::Neg) if num.checked_neg().is_some() => {
+                return AnalyzedExpression::Int(-num)
             }
             (AnalyzedExpression::Float(num), PrefixOp::Neg) => {
                 return AnalyzedExpression::Float(-num)
@@ -1931,6 +2387,17 @@ impl<'src> Analyzer<'src> {
             (left, right) if left == right && allowed_types.contains(&left) => {
                 Box::new(override_result_type.unwrap_or(left))
             }
+            // lists, objects, and Speicherboxen are compared by their contents
+            (left, right)
+                if left == right
+                    && matches!(node.op, InfixOp::Eq | InfixOp::Neq)
+                    && matches!(
+                        left,
+                        Type::List(_, 0) | Type::Object(_, 0) | Type::AnyObject(0)
+                    ) =>
+            {
+                Box::new(Type::Bool(0))
+            }
             (left, right) if left != right => {
                 self.error(
                     ErrorKind::Type,
@@ -1971,17 +2438,26 @@ impl<'src> Analyzer<'src> {
                 InfixOp::Gte => return AnalyzedExpression::Bool(left >= right),
                 _ => {}
             },
+            // overflows are not folded, so that they are reported at runtime
             (AnalyzedExpression::Int(left), AnalyzedExpression::Int(right)) => match node.op {
-                InfixOp::Plus => return AnalyzedExpression::Int(left.wrapping_add(*right)),
-                InfixOp::Minus => return AnalyzedExpression::Int(left.wrapping_sub(*right)),
-                InfixOp::Mul => return AnalyzedExpression::Int(left.wrapping_mul(*right)),
+                InfixOp::Plus if left.checked_add(*right).is_some() => {
+                    return AnalyzedExpression::Int(left + right)
+                }
+                InfixOp::Minus if left.checked_sub(*right).is_some() => {
+                    return AnalyzedExpression::Int(left - right)
+                }
+                InfixOp::Mul if left.checked_mul(*right).is_some() => {
+                    return AnalyzedExpression::Int(left * right)
+                }
                 InfixOp::Div if *right == 0 => self.error(
                     ErrorKind::Semantic,
                     format!("Kann nicht {left} durch 0 teilen."),
                     vec!["Fragen Sie Timo, weshalb Mathe das nicht erlaubt.".into()],
                     node.span,
                 ),
-                InfixOp::Div => return AnalyzedExpression::Int(left.wrapping_div(*right)),
+                InfixOp::Div if left.checked_div(*right).is_some() => {
+                    return AnalyzedExpression::Int(left / right)
+                }
                 InfixOp::Rem if *right == 0 => self.error(
                     ErrorKind::Semantic,
                     format!(
@@ -1991,12 +2467,14 @@ impl<'src> Analyzer<'src> {
                     node.span,
                 ),
                 InfixOp::Rem => return AnalyzedExpression::Int(left.wrapping_rem(*right)),
+                InfixOp::Pow if *right < 0 => return AnalyzedExpression::Int(0),
                 InfixOp::Pow => {
-                    return AnalyzedExpression::Int(if *right < 0 {
-                        0
-                    } else {
-                        left.wrapping_pow(*right as u32)
-                    })
+                    if let Some(res) = u32::try_from(*right)
+                        .ok()
+                        .and_then(|right| left.checked_pow(right))
+                    {
+                        return AnalyzedExpression::Int(res);
+                    }
                 }
                 InfixOp::Eq => return AnalyzedExpression::Bool(left == right),
                 InfixOp::Neq => return AnalyzedExpression::Bool(left != right),
@@ -2058,6 +2536,7 @@ impl<'src> Analyzer<'src> {
                 lhs,
                 op: node.op,
                 rhs,
+                span: node.span,
             }
             .into(),
         )
@@ -2237,12 +2716,26 @@ impl<'src> Analyzer<'src> {
                         if self.curr_func_name != ident.inner {
                             func.used = true;
                         }
-                        Some((func.return_type.inner.clone(), func.params.clone()))
+                        // imported builtins take precedence over user-defined functions at runtime
+                        let function_index = func
+                            .index
+                            .filter(|_| !self.builtin_functions.contains_key(ident.inner));
+                        Some((
+                            func.return_type.inner.clone(),
+                            func.params.clone(),
+                            function_index,
+                        ))
                     }
                     (_, Some(builtin)) => {
                         self.used_builtins.insert(ident.inner);
                         let builtin = builtin.clone();
 
+                        // like a cast, `Typ` is used to inspect values of the `Unbekannt` type
+                        let create_err_if_expr_contains_any = self.create_err_if_expr_contains_any;
+                        if ident.inner == "Typ" {
+                            self.create_err_if_expr_contains_any = false;
+                        }
+
                         let (result_type, args) = match builtin.param_types {
                             ParamTypes::VarArgs(fixed, inner_type) => {
                                 let mut result_type = builtin.return_type;
@@ -2275,6 +2768,76 @@ impl<'src> Analyzer<'src> {
                                 args.append(&mut args_temp);
                                 (result_type, args)
                             }
+                            ParamTypes::Generic(signature) => {
+                                let mut result_type = None;
+                                let mut args = vec![];
+                                let mut arg_types = vec![];
+
+                                for arg in node.args {
+                                    let arg_span = arg.span();
+                                    let arg = self.expression(arg);
+                                    let arg_type = self.lookup_type(&Spanned {
+                                        span: arg_span,
+                                        inner: arg.result_type(),
+                                    });
+
+                                    match arg_type {
+                                        Type::Never => {
+                                            self.warn_unreachable(node.span, arg_span, true);
+                                            result_type = Some(Type::Never);
+                                        }
+                                        Type::Unknown => {
+                                            result_type.get_or_insert(Type::Unknown);
+                                        }
+                                        _ => {}
+                                    }
+
+                                    arg_types.push(arg_type);
+                                    args.push(arg);
+                                }
+
+                                let result_type = match result_type {
+                                    Some(result_type) => result_type,
+                                    None => match signature(&arg_types) {
+                                        Ok(result_type) => result_type,
+                                        Err(message) => {
+                                            self.error(ErrorKind::Type, message, vec![], node.span);
+                                            Type::Unknown
+                                        }
+                                    },
+                                };
+
+                                (result_type, args)
+                            }
+                            ParamTypes::Optional(required, optional) => {
+                                let max_len = required.len() + optional.len();
+                                if !(required.len()..=max_len).contains(&node.args.len()) {
+                                    self.error(
+                                        ErrorKind::Reference,
+                                        format!(
+                                    "Die Funktion `{}` erwartet zwischen {} und {max_len} Argumente, allerdings wurde{} {} übergeben.",
+                                    ident.inner,
+                                    required.len(),
+                                    if node.args.len() == 1 { "" } else {"n"},
+                                    node.args.len()
+                                ),
+                                        vec![],
+                                        node.span,
+                                    );
+                                    (builtin.return_type, vec![])
+                                } else {
+                                    let mut result_type = builtin.return_type;
+                                    let args = node
+                                        .args
+                                        .into_iter()
+                                        .zip(required.into_iter().chain(optional))
+                                        .map(|(arg, param_type)| {
+                                            self.arg(arg, &param_type, node.span, &mut result_type)
+                                        })
+                                        .collect();
+                                    (result_type, args)
+                                }
+                            }
                             ParamTypes::Normal(param_types) => {
                                 if param_types.len() != node.args.len() {
                                     self.error(
@@ -2304,12 +2867,14 @@ impl<'src> Analyzer<'src> {
                                 }
                             }
                         };
+                        self.create_err_if_expr_contains_any = create_err_if_expr_contains_any;
 
                         return AnalyzedExpression::Call(
                             AnalyzedCallExpr {
                                 result_type,
                                 func: AnalyzedCallBase::Ident(ident.inner),
                                 args,
+                                function_index: None,
                             }
                             .into(),
                         );
@@ -2328,8 +2893,8 @@ impl<'src> Analyzer<'src> {
                         None
                     }
                 };
-                let (result_type, args) = match func {
-                    Some((mut result_type, func_params)) => {
+                let (result_type, args, function_index) = match func {
+                    Some((mut result_type, func_params, function_index)) => {
                         let args = node
                             .args
                             .into_iter()
@@ -2338,7 +2903,7 @@ impl<'src> Analyzer<'src> {
                                 self.arg(arg, &param.type_.inner, node.span, &mut result_type)
                             })
                             .collect();
-                        (result_type, args)
+                        (result_type, args, function_index)
                     }
                     None => {
                         let mut result_type = Type::Unknown;
@@ -2353,7 +2918,7 @@ impl<'src> Analyzer<'src> {
                                 arg
                             })
                             .collect();
-                        (result_type, args)
+                        (result_type, args, None)
                     }
                 };
 
@@ -2362,6 +2927,7 @@ impl<'src> Analyzer<'src> {
                         result_type,
                         func: AnalyzedCallBase::Ident(ident.inner),
                         args,
+                        function_index,
                     }
                     .into(),
                 )
@@ -2384,6 +2950,7 @@ impl<'src> Analyzer<'src> {
                             result_type: *result_type,
                             func: AnalyzedCallBase::Expr(Box::new(func)),
                             args,
+                            function_index: None,
                         }))
                     }
                     other @ Type::Never | other @ Type::Unknown => {
@@ -2391,6 +2958,7 @@ impl<'src> Analyzer<'src> {
                             result_type: other,
                             func: AnalyzedCallBase::Expr(Box::new(func)),
                             args: vec![],
+                            function_index: None,
                         }))
                     }
                     _ => {
@@ -2405,6 +2973,7 @@ impl<'src> Analyzer<'src> {
                             result_type: Type::Unknown,
                             func: AnalyzedCallBase::Expr(Box::new(func)),
                             args: vec![],
+                            function_index: None,
                         }))
                     }
                 }
@@ -2547,14 +3116,14 @@ impl<'src> Analyzer<'src> {
             (AnalyzedExpression::Int(val), Type::Int(0)) => AnalyzedExpression::Int(val),
             (AnalyzedExpression::Int(val), Type::Float(0)) => AnalyzedExpression::Float(val as f64),
             (AnalyzedExpression::Int(val), Type::Bool(0)) => AnalyzedExpression::Bool(val != 0),
-            (AnalyzedExpression::Int(val), Type::Char(0)) => {
-                AnalyzedExpression::Char(val.clamp(0, 127) as u8)
+            (AnalyzedExpression::Int(val @ 0..=127), Type::Char(0)) => {
+                AnalyzedExpression::Char(val as u8)
             }
             (AnalyzedExpression::Float(val), Type::Int(0)) => AnalyzedExpression::Int(val as i64),
             (AnalyzedExpression::Float(val), Type::Float(0)) => AnalyzedExpression::Float(val),
             (AnalyzedExpression::Float(val), Type::Bool(0)) => AnalyzedExpression::Bool(val != 0.0),
-            (AnalyzedExpression::Float(val), Type::Char(0)) => {
-                AnalyzedExpression::Char(val.clamp(0.0, 127.0) as u8)
+            (AnalyzedExpression::Float(val), Type::Char(0)) if (0.0..128.0).contains(&val) => {
+                AnalyzedExpression::Char(val as u8)
             }
             (AnalyzedExpression::Bool(val), Type::Int(0)) => AnalyzedExpression::Int(val as i64),
             (AnalyzedExpression::Bool(val), Type::Float(0)) => {
@@ -2573,6 +3142,7 @@ impl<'src> Analyzer<'src> {
                     result_type,
                     expr,
                     as_type: node.type_.inner,
+                    span: node.span,
                 }
                 .into(),
             ),
@@ -2749,6 +3319,7 @@ impl<'src> Analyzer<'src> {
                     result_type: *inner,
                     expr,
                     index,
+                    span: node.span,
                 }))
             }
             (Type::Never, _) | (_, Type::Never) => {
@@ -2756,6 +3327,7 @@ impl<'src> Analyzer<'src> {
                     result_type: Type::Never,
                     expr,
                     index,
+                    span: node.span,
                 }))
             }
             (expr_type, index_type) => {
@@ -2764,6 +3336,7 @@ impl<'src> Analyzer<'src> {
                     result_type: Type::Unknown,
                     expr,
                     index,
+                    span: node.span,
                 }))
             }
         }
@@ -1,6 +1,9 @@
 use std::collections::{HashMap, HashSet};
 
-use hpi_parser::ast::{AssignOp, InfixOp, ObjectTypeField, PrefixOp, Type};
+use hpi_parser::{
+    ast::{AssignOp, InfixOp, ObjectTypeField, PrefixOp, Type},
+    Span,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AnalyzedProgram<'src> {
@@ -33,6 +36,8 @@ pub struct AnalyzedParameter<'src> {
 pub struct AnalyzedBlock<'src> {
     pub result_type: Type,
     pub stmts: Vec<AnalyzedStatement<'src>>,
+    /// The source locations of `stmts`, at the same indices.
+    pub stmt_spans: Vec<Span<'src>>,
     pub expr: Option<AnalyzedExpression<'src>>,
 }
 
@@ -258,6 +263,8 @@ pub struct AnalyzedInfixExpr<'src> {
     pub lhs: AnalyzedExpression<'src>,
     pub op: InfixOp,
     pub rhs: AnalyzedExpression<'src>,
+    /// The source location, runtime errors raised by this expression are reported here.
+    pub span: Span<'src>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -280,6 +287,9 @@ pub struct AnalyzedCallExpr<'src> {
     pub result_type: Type,
     pub func: AnalyzedCallBase<'src>,
     pub args: Vec<AnalyzedExpression<'src>>,
+    /// The index of the called function in [`AnalyzedProgram::functions`].
+    /// `None` if a builtin, a function value, or one of the main functions is called.
+    pub function_index: Option<usize>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -287,6 +297,8 @@ pub struct AnalyzedCastExpr<'src> {
     pub result_type: Type,
     pub expr: AnalyzedExpression<'src>,
     pub as_type: Type,
+    /// The source location, runtime errors raised by this expression are reported here.
+    pub span: Span<'src>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -301,4 +313,6 @@ pub struct AnalyzedIndexExpr<'src> {
     pub result_type: Type,
     pub expr: AnalyzedExpression<'src>,
     pub index: AnalyzedExpression<'src>,
+    /// The source location, runtime errors raised by this expression are reported here.
+    pub span: Span<'src>,
 }
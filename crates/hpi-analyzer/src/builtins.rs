@@ -0,0 +1,510 @@
+//! Signatures of builtin functions which are generic over their arguments.
+
+use hpi_parser::ast::{ObjectTypeField, Type};
+
+/// Creates the error message for a call with invalid argument types.
+fn signature_error(name: &str, expected: &str, args: &[Type]) -> String {
+    format!(
+        "Die Funktion `{name}` erwartet {expected}, allerdings wurde `({})` übergeben.",
+        args.iter()
+            .map(|typ| typ.to_string())
+            .collect::<Vec<String>>()
+            .join(" / ")
+    )
+}
+
+/// The result of `Versuche`, the value is only present if the call was successful.
+pub(crate) fn versuchsergebnis_type() -> Type {
+    Type::Object(
+        vec![
+            ObjectTypeField {
+                key: "Erfolg".to_string(),
+                type_: Box::new(Type::Bool(0)),
+            },
+            ObjectTypeField {
+                key: "Wert".to_string(),
+                type_: Box::new(Type::Any),
+            },
+            ObjectTypeField {
+                key: "Fehler".to_string(),
+                type_: Box::new(Type::String(0)),
+            },
+        ],
+        0,
+    )
+}
+
+/// `Versuche(funk() ergibt T) ergibt Versuchsergebnis`
+pub(crate) fn versuche(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::Function { params, .. }] if params.is_empty() => Ok(versuchsergebnis_type()),
+        _ => Err(signature_error(
+            "Versuche",
+            "eine Funktion ohne Parameter",
+            args,
+        )),
+    }
+}
+
+/// A point in time as returned by `Zeit`.
+pub(crate) fn zeitstempel_type() -> Type {
+    let fields = [
+        "Jahr",
+        "Monat",
+        "Kalendar_Tag",
+        "Wochentag",
+        "Stunde",
+        "Minute",
+        "Sekunde",
+    ];
+    Type::Object(
+        fields
+            .into_iter()
+            .map(|key| ObjectTypeField {
+                key: key.to_string(),
+                type_: Box::new(Type::Int(0)),
+            })
+            .collect(),
+        0,
+    )
+}
+
+/// `Zeit_Formatieren(Zeitstempel | Zahl / Zeichenkette) ergibt Zeichenkette`, where a `Zahl` is a
+/// number of milliseconds since the Unix epoch like the result of `Zeit_Millisekunden`
+pub(crate) fn zeit_formatieren(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::Int(0), Type::String(0)] => Ok(Type::String(0)),
+        [timestamp, Type::String(0)] if *timestamp == zeitstempel_type() => Ok(Type::String(0)),
+        _ => Err(signature_error(
+            "Zeit_Formatieren",
+            "einen Zeitstempel oder eine Zahl von Millisekunden und ein Format",
+            args,
+        )),
+    }
+}
+
+/// An entry of a Speicherbox or object as returned by `AlsListe`.
+pub(crate) fn eintrag_type() -> Type {
+    Type::Object(
+        vec![
+            ObjectTypeField {
+                key: "Schlüssel".to_string(),
+                type_: Box::new(Type::String(0)),
+            },
+            ObjectTypeField {
+                key: "Wert".to_string(),
+                type_: Box::new(Type::Any),
+            },
+        ],
+        0,
+    )
+}
+
+/// `AlsListe(Zeichenkette | Liste von T | Speicherbox | Objekt) ergibt Liste von ...`
+pub(crate) fn als_liste(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::String(0)] => Ok(Type::List(Box::new(Type::String(0)), 0)),
+        [list @ Type::List(_, 0)] => Ok(list.clone()),
+        [Type::AnyObject(0) | Type::Object(_, 0)] => Ok(Type::List(Box::new(eintrag_type()), 0)),
+        _ => Err(signature_error(
+            "AlsListe",
+            "eine Zeichenkette, eine Liste, eine Speicherbox oder ein Objekt",
+            args,
+        )),
+    }
+}
+
+/// `GrößeVon(Speicherbox | Objekt) ergibt Zahl`
+pub(crate) fn groesse_von(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::AnyObject(0) | Type::Object(_, 0)] => Ok(Type::Int(0)),
+        _ => Err(signature_error(
+            "GrößeVon",
+            "eine Speicherbox oder ein Objekt",
+            args,
+        )),
+    }
+}
+
+/// `Objekt_Schlüssel(Objekt | Speicherbox) ergibt Liste von Zeichenkette`
+pub(crate) fn objekt_schluessel(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::Object(_, 0) | Type::AnyObject(0)] => Ok(Type::List(Box::new(Type::String(0)), 0)),
+        _ => Err(signature_error(
+            "Objekt_Schlüssel",
+            "ein Objekt oder eine Speicherbox",
+            args,
+        )),
+    }
+}
+
+/// `Feld(Objekt | Speicherbox / Zeichenkette) ergibt Any`, the result is usually cast using `als`
+pub(crate) fn feld(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::Object(_, 0) | Type::AnyObject(0), Type::String(0)] => Ok(Type::Any),
+        _ => Err(signature_error(
+            "Feld",
+            "ein Objekt oder eine Speicherbox und einen Schlüssel",
+            args,
+        )),
+    }
+}
+
+/// `Länge(Zeichenkette | Liste von T | Speicherbox) ergibt Zahl`
+pub(crate) fn laenge(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::String(0) | Type::List(_, 0) | Type::AnyObject(0)] => Ok(Type::Int(0)),
+        _ => Err(signature_error(
+            "Länge",
+            "eine Zeichenkette, eine Liste oder eine Speicherbox",
+            args,
+        )),
+    }
+}
+
+/// `Enthält(Zeichenkette / Zeichenkette) ergibt Wahrheitswert` or
+/// `Enthält(Liste von T / T) ergibt Wahrheitswert`
+pub(crate) fn enthaelt(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::String(0), Type::String(0)] => Ok(Type::Bool(0)),
+        [Type::List(inner, 0), element] if **inner == *element || **inner == Type::Unknown => {
+            Ok(Type::Bool(0))
+        }
+        _ => Err(signature_error(
+            "Enthält",
+            "zwei Zeichenketten oder eine Liste von `T` und ein `T`",
+            args,
+        )),
+    }
+}
+
+/// `IstLeer(Zeichenkette | Liste von T | Speicherbox | Objekt) ergibt Wahrheitswert`
+pub(crate) fn ist_leer(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::String(0) | Type::List(_, 0) | Type::AnyObject(0) | Type::Object(_, 0)] => {
+            Ok(Type::Bool(0))
+        }
+        _ => Err(signature_error(
+            "IstLeer",
+            "eine Zeichenkette, eine Liste, eine Speicherbox oder ein Objekt",
+            args,
+        )),
+    }
+}
+
+/// `Begrenze(T Wert / T Minimum / T Maximum) ergibt T` where `T` is `Zahl` or `Fließkommazahl`
+pub(crate) fn begrenze(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [value @ (Type::Int(0) | Type::Float(0)), min, max] if min == value && max == value => {
+            Ok(value.clone())
+        }
+        _ => Err(signature_error(
+            "Begrenze",
+            "drei Zahlen oder drei Fließkommazahlen",
+            args,
+        )),
+    }
+}
+
+/// `DruckeTabelle(Liste von Liste von T) ergibt Nichts`
+pub(crate) fn drucke_tabelle(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(row, 0)] if matches!(**row, Type::List(_, 0)) => Ok(Type::Nichts),
+        _ => Err(signature_error(
+            "DruckeTabelle",
+            "eine Liste von Zeilen, welche selbst Listen sind",
+            args,
+        )),
+    }
+}
+
+/// `Rotiere(Liste von T / Zahl) ergibt Liste von T`
+pub(crate) fn rotiere(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [list @ Type::List(_, 0), Type::Int(0)] => Ok(list.clone()),
+        _ => Err(signature_error("Rotiere", "eine Liste und eine Zahl", args)),
+    }
+}
+
+/// `Teilliste(Liste von T / Zahl / Zahl) ergibt Liste von T`
+pub(crate) fn teilliste(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [list @ Type::List(_, 0), Type::Int(0), Type::Int(0)] => Ok(list.clone()),
+        _ => Err(signature_error(
+            "Teilliste",
+            "eine Liste, einen Anfang und ein Ende",
+            args,
+        )),
+    }
+}
+
+/// `AbstandZwischen(T / T) ergibt T` where `T` is `Zahl` or `Fließkommazahl`
+pub(crate) fn abstand_zwischen(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [left @ (Type::Int(0) | Type::Float(0)), right] if left == right => Ok(left.clone()),
+        _ => Err(signature_error(
+            "AbstandZwischen",
+            "zwei Zahlen oder zwei Fließkommazahlen",
+            args,
+        )),
+    }
+}
+
+/// `Hinzufügen(Liste von T / T) ergibt Nichts`
+pub(crate) fn hinzufuegen(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0), element] if **inner == *element || **inner == Type::Unknown => {
+            Ok(Type::Nichts)
+        }
+        _ => Err(signature_error(
+            "Hinzufügen",
+            "eine Liste von `T` und ein `T`",
+            args,
+        )),
+    }
+}
+
+/// `Entferne_Letztes(Liste von T) ergibt T`
+pub(crate) fn entferne_letztes(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0)] => Ok((**inner).clone()),
+        _ => Err(signature_error("Entferne_Letztes", "eine Liste", args)),
+    }
+}
+
+/// `Umkehren(Zeichenkette | Liste von T) ergibt Zeichenkette | Liste von T`
+pub(crate) fn umkehren(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [value @ (Type::String(0) | Type::List(_, 0))] => Ok(value.clone()),
+        _ => Err(signature_error(
+            "Umkehren",
+            "eine Zeichenkette oder eine Liste",
+            args,
+        )),
+    }
+}
+
+/// `Kopiere(T) ergibt T`
+pub(crate) fn kopiere(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [value] => Ok(value.clone()),
+        _ => Err(signature_error("Kopiere", "einen beliebigen Wert", args)),
+    }
+}
+
+/// `Sortiere(Liste von T) ergibt Liste von T` where `T` is a number, a character, or a string
+pub(crate) fn sortiere(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [list @ Type::List(inner, 0)]
+            if matches!(
+                **inner,
+                Type::Int(0) | Type::Float(0) | Type::Char(0) | Type::String(0) | Type::Unknown
+            ) =>
+        {
+            Ok(list.clone())
+        }
+        _ => Err(signature_error(
+            "Sortiere",
+            "eine Liste von Zahlen, Fließkommazahlen, Zeichen oder Zeichenketten",
+            args,
+        )),
+    }
+}
+
+/// `Maximum(Liste von T) ergibt T` where `T` is a number, a character, or a string.
+/// `Minimum` uses the same signature.
+fn extremum(name: &str, args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0)]
+            if matches!(
+                **inner,
+                Type::Int(0) | Type::Float(0) | Type::Char(0) | Type::String(0) | Type::Unknown
+            ) =>
+        {
+            Ok((**inner).clone())
+        }
+        _ => Err(signature_error(
+            name,
+            "eine Liste von Zahlen, Fließkommazahlen, Zeichen oder Zeichenketten",
+            args,
+        )),
+    }
+}
+
+/// See [`extremum`].
+pub(crate) fn maximum(args: &[Type]) -> Result<Type, String> {
+    extremum("Maximum", args)
+}
+
+/// See [`extremum`].
+pub(crate) fn minimum(args: &[Type]) -> Result<Type, String> {
+    extremum("Minimum", args)
+}
+
+/// `Summe(Liste von T) ergibt T` where `T` is `Zahl` or `Fließkommazahl`
+pub(crate) fn summe(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0)] => match **inner {
+            // an empty list literal sums up to the integer `0`
+            Type::Int(0) | Type::Unknown => Ok(Type::Int(0)),
+            Type::Float(0) => Ok(Type::Float(0)),
+            _ => Err(signature_error(
+                "Summe",
+                "eine Liste von Zahlen oder Fließkommazahlen",
+                args,
+            )),
+        },
+        _ => Err(signature_error(
+            "Summe",
+            "eine Liste von Zahlen oder Fließkommazahlen",
+            args,
+        )),
+    }
+}
+
+/// `Durchschnitt(Liste von T) ergibt Fließkommazahl` where `T` is `Zahl` or `Fließkommazahl`
+pub(crate) fn durchschnitt(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0)]
+            if matches!(**inner, Type::Int(0) | Type::Float(0) | Type::Unknown) =>
+        {
+            Ok(Type::Float(0))
+        }
+        _ => Err(signature_error(
+            "Durchschnitt",
+            "eine Liste von Zahlen oder Fließkommazahlen",
+            args,
+        )),
+    }
+}
+
+/// `Betrag(T) ergibt T` where `T` is `Zahl` or `Fließkommazahl`
+pub(crate) fn betrag(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [value @ (Type::Int(0) | Type::Float(0))] => Ok(value.clone()),
+        _ => Err(signature_error(
+            "Betrag",
+            "eine Zahl oder eine Fließkommazahl",
+            args,
+        )),
+    }
+}
+
+/// `Potenz(T / T) ergibt T` where `T` is `Zahl` or `Fließkommazahl`
+pub(crate) fn potenz(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [base @ (Type::Int(0) | Type::Float(0)), exp] if base == exp => Ok(base.clone()),
+        _ => Err(signature_error(
+            "Potenz",
+            "zwei Zahlen oder zwei Fließkommazahlen",
+            args,
+        )),
+    }
+}
+
+/// `ListeAlsText(Liste von T / Zeichenkette / funk(T) ergibt Zeichenkette) ergibt Zeichenkette`
+pub(crate) fn liste_als_text(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0), Type::String(0), Type::Function {
+            params,
+            result_type,
+        }] if matches!(**result_type, Type::String(0))
+            && matches!(&params[..], [param] if param == &**inner || **inner == Type::Unknown) =>
+        {
+            Ok(Type::String(0))
+        }
+        _ => Err(signature_error(
+            "ListeAlsText",
+            "eine Liste von `T`, ein Trennzeichen und eine Funktion, welche `T` in eine Zeichenkette umwandelt",
+            args,
+        )),
+    }
+}
+
+/// `MaximumNach(Liste von T / funk(T) ergibt S) ergibt T`, where `S` is a number, a character, or
+/// a string.
+/// `MinimumNach` uses the same signature.
+fn extremum_nach(name: &str, args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0), Type::Function {
+            params,
+            result_type,
+        }] if matches!(
+            **result_type,
+            Type::Int(0) | Type::Float(0) | Type::Char(0) | Type::String(0)
+        ) && matches!(&params[..], [param] if param == &**inner || **inner == Type::Unknown) =>
+        {
+            Ok((**inner).clone())
+        }
+        _ => Err(signature_error(
+            name,
+            "eine Liste von `T` und eine Funktion, welche `T` in eine vergleichbare Zahl, ein Zeichen oder eine Zeichenkette umwandelt",
+            args,
+        )),
+    }
+}
+
+/// See [`extremum_nach`].
+pub(crate) fn maximum_nach(args: &[Type]) -> Result<Type, String> {
+    extremum_nach("MaximumNach", args)
+}
+
+/// See [`extremum_nach`].
+pub(crate) fn minimum_nach(args: &[Type]) -> Result<Type, String> {
+    extremum_nach("MinimumNach", args)
+}
+
+/// `Abbilden(Liste von T / funk(T) ergibt S) ergibt Liste von S`
+pub(crate) fn abbilden(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0), Type::Function {
+            params,
+            result_type,
+        }] if matches!(&params[..], [param] if param == &**inner || **inner == Type::Unknown) => {
+            Ok(Type::List(result_type.clone(), 0))
+        }
+        _ => Err(signature_error(
+            "Abbilden",
+            "eine Liste von `T` und eine Funktion, welche `T` als einziges Argument annimmt",
+            args,
+        )),
+    }
+}
+
+/// `Filtern(Liste von T / funk(T) ergibt Wahrheitswert) ergibt Liste von T`
+pub(crate) fn filtern(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [list @ Type::List(inner, 0), Type::Function {
+            params,
+            result_type,
+        }] if matches!(**result_type, Type::Bool(0))
+            && matches!(&params[..], [param] if param == &**inner || **inner == Type::Unknown) =>
+        {
+            Ok(list.clone())
+        }
+        _ => Err(signature_error(
+            "Filtern",
+            "eine Liste von `T` und eine Funktion, welche `T` in einen Wahrheitswert umwandelt",
+            args,
+        )),
+    }
+}
+
+/// `Reduziere(Liste von T / S / funk(S / T) ergibt S) ergibt S`
+pub(crate) fn reduziere(args: &[Type]) -> Result<Type, String> {
+    match args {
+        [Type::List(inner, 0), initial, Type::Function {
+            params,
+            result_type,
+        }] if **result_type == *initial
+            && matches!(&params[..], [accumulator, param] if accumulator == initial && (param == &**inner || **inner == Type::Unknown)) =>
+        {
+            Ok(initial.clone())
+        }
+        _ => Err(signature_error(
+            "Reduziere",
+            "eine Liste von `T`, einen Startwert `S` und eine Funktion, welche `S` und `T` in ein neues `S` umwandelt",
+            args,
+        )),
+    }
+}
@@ -3,6 +3,7 @@ mod macros;
 
 mod analyzer;
 pub mod ast;
+mod builtins;
 mod diagnostic;
 
 use std::iter;
@@ -12,6 +13,7 @@ use ast::AnalyzedProgram;
 pub use diagnostic::*;
 
 pub use hpi_parser::ast::{AssignOp, InfixOp, PrefixOp, Type};
+pub use hpi_parser::Span;
 use hpi_parser::{Lexer, Parser};
 
 /// Analyzes rush source code and returns an analyzed (annotated) AST.
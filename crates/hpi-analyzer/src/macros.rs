@@ -71,10 +71,14 @@ macro_rules! analyzed_tree {
         stmts: [$($stmt:tt),* $(,)?],
         expr: $expr:tt $(,)?
     )) => {
-        AnalyzedBlock {
-            result_type: $result_type,
-            stmts: vec![$(analyzed_tree!($stmt)),*],
-            expr: analyzed_tree!($expr),
+        {
+            let stmts = vec![$(analyzed_tree!($stmt)),*];
+            AnalyzedBlock {
+                result_type: $result_type,
+                stmt_spans: vec![Span::dummy(); stmts.len()],
+                stmts,
+                expr: analyzed_tree!($expr),
+            }
         }
     };
     ((BlockExpr $($rest:tt)*)) => {
@@ -128,6 +132,7 @@ macro_rules! analyzed_tree {
             lhs: analyzed_tree!($lhs),
             op: $op,
             rhs: analyzed_tree!($rhs),
+            span: Span::dummy(),
         }.into())
     };
     ((
@@ -152,6 +157,7 @@ macro_rules! analyzed_tree {
             result_type: $result_type,
             func: $func,
             args: vec![$(analyzed_tree!($arg)),*],
+            function_index: None,
         }.into())
     };
     ((
@@ -163,6 +169,7 @@ macro_rules! analyzed_tree {
             result_type: $result_type,
             expr: analyzed_tree!($expr),
             type_: $type,
+            span: Span::dummy(),
         }.into())
     };
 }
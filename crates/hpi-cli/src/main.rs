@@ -1,11 +1,11 @@
-use std::{collections::HashMap, fs, io, process, str::FromStr, time::Instant};
+use std::{collections::HashMap, fs, io, process, str::FromStr, time::{Duration, Instant}};
 
 use anyhow::{bail, Context};
 use clap::Parser;
 use cli::{Cli, Command};
 
 use hpi_analyzer::{ast::AnalyzedProgram, Diagnostic};
-use hpi_interpreter_tree::{HPIHttpClient, Interpreter};
+use hpi_interpreter_tree::{HPIHttpClient, HttpError, Interpreter};
 use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue},
     Method,
@@ -23,8 +23,10 @@ impl HPIHttpClient for InterpreterHttpClient {
         url: &str,
         body: String,
         headers: HashMap<String, String>,
-    ) -> Result<(u16, String), String> {
+        timeout: Duration,
+    ) -> Result<(u16, String, HashMap<String, String>), HttpError> {
         let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
             .build()
             .map_err(|err| err.to_string())?;
 
@@ -44,11 +46,22 @@ impl HPIHttpClient for InterpreterHttpClient {
             .body(body)
             .headers(header_map)
             .send()
-            .map_err(|err| err.to_string())?;
-        Ok((
-            res.status().as_u16(),
-            res.text().map_err(|err| err.to_string())?,
-        ))
+            .map_err(|err| match err.is_timeout() {
+                true => HttpError::Timeout,
+                false => HttpError::Other(err.to_string()),
+            })?;
+        let status = res.status().as_u16();
+        let headers = res
+            .headers()
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        Ok((status, res.text().map_err(|err| err.to_string())?, headers))
     }
 }
 
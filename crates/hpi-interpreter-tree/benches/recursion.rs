@@ -0,0 +1,54 @@
+//! Measures the call overhead of the interpreter using a recursive Fibonacci function.
+//! Run using `cargo bench -p hpi-interpreter-tree`.
+
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, Instant},
+};
+
+use hpi_interpreter_tree::{HPIHttpClient, HttpError};
+
+const FIB: &str = r#"
+beantrage Drucke von Drucker;
+
+funk Fib(Zahl N) ergibt Zahl {
+    falls N < 2 { N } sonst { Fib(N - 1) + Fib(N - 2) }
+}
+
+funk Bewerbung() ergibt Zeichenkette { "Bitte nehmt mich!" }
+
+funk Einschreibung(Zahl Matrikelnummer) ergibt Nichts {
+    setze Zahl _Nummer auf Matrikelnummer;
+}
+
+funk Studium() ergibt Nichts {
+    Drucke(Fib(22));
+}
+"#;
+
+struct NoHttpClient;
+
+impl HPIHttpClient for NoHttpClient {
+    fn request(
+        &self,
+        _method: String,
+        _url: &str,
+        _body: String,
+        _headers: HashMap<String, String>,
+        _timeout: Duration,
+    ) -> Result<(u16, String, HashMap<String, String>), HttpError> {
+        Err(HttpError::Other("Kein Netzwerk verfügbar".to_string()))
+    }
+}
+
+fn main() {
+    const RUNS: u32 = 5;
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        hpi_interpreter_tree::run(FIB, "fib.hpi", io::sink(), NoHttpClient, HashMap::new())
+            .expect("the benchmark program is valid");
+    }
+    println!("fib(22): {:?} per run", start.elapsed() / RUNS);
+}
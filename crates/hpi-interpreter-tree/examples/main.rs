@@ -1,6 +1,11 @@
-use std::{collections::HashMap, env, fs, io, process, str::FromStr, time::Instant};
+use std::{
+    collections::HashMap,
+    env, fs, io, process,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
-use hpi_interpreter_tree::{HPIHttpClient, RunError};
+use hpi_interpreter_tree::{HPIHttpClient, HttpError, RunError};
 use reqwest::{
     header::{HeaderMap, HeaderName, HeaderValue},
     Method,
@@ -15,8 +20,10 @@ impl HPIHttpClient for Client {
         url: &str,
         body: String,
         headers: HashMap<String, String>,
-    ) -> Result<(u16, String), String> {
+        timeout: Duration,
+    ) -> Result<(u16, String, HashMap<String, String>), HttpError> {
         let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
             .build()
             .map_err(|err| err.to_string())?;
 
@@ -36,11 +43,22 @@ impl HPIHttpClient for Client {
             .body(body)
             .headers(header_map)
             .send()
-            .map_err(|err| err.to_string())?;
-        Ok((
-            res.status().as_u16(),
-            res.text().map_err(|err| err.to_string())?,
-        ))
+            .map_err(|err| match err.is_timeout() {
+                true => HttpError::Timeout,
+                false => HttpError::Other(err.to_string()),
+            })?;
+        let status = res.status().as_u16();
+        let headers = res
+            .headers()
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        Ok((status, res.text().map_err(|err| err.to_string())?, headers))
     }
 }
 
@@ -55,8 +73,14 @@ fn main() {
             println!("{:?}", start.elapsed());
             process::exit(code as i32);
         }
-        Err(RunError::Runtime(err)) => {
-            eprintln!("{err}");
+        Err(RunError::Runtime(err, span)) => {
+            match span {
+                Some(span) => eprintln!(
+                    "{}:{}:{}: {err}",
+                    span.start.path, span.start.line, span.start.column
+                ),
+                None => eprintln!("{err}"),
+            }
             process::exit(1);
         }
         Err(RunError::Analyzer(diagnostics)) => {
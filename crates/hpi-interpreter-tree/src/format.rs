@@ -1,6 +1,6 @@
 use std::str::Chars;
 
-use crate::value::{InterruptKind, Value};
+use crate::value::{DisplayOptions, InterruptKind, Value};
 
 pub struct Formatter<'src> {
     input_reader: Chars<'src>,
@@ -8,16 +8,18 @@ pub struct Formatter<'src> {
     inut_args_curr_pos: usize,
     curr_char: Option<char>,
     output: String,
+    display_options: DisplayOptions,
 }
 
 impl<'src> Formatter<'src> {
-    pub fn new(input: &'src str, args: Vec<Value>) -> Self {
+    pub fn new(input: &'src str, args: Vec<Value>, display_options: DisplayOptions) -> Self {
         let mut fmt = Self {
             input_reader: input.chars(),
             input_args: args.to_vec(),
             inut_args_curr_pos: 0,
             curr_char: None,
             output: String::new(),
+            display_options,
         };
 
         fmt.next();
@@ -43,22 +45,36 @@ impl<'src> Formatter<'src> {
         self.curr_char = self.input_reader.next()
     }
 
-    fn escape(&mut self, padding: usize) -> Result<(), InterruptKind> {
+    fn escape(&mut self, spec: Spec) -> Result<(), InterruptKind> {
         match self.curr_char {
             Some(char) => {
-                let curr = self.input_args.get(self.inut_args_curr_pos);
-                self.inut_args_curr_pos+=1;
-
-                match (char, curr, padding) {
-                    ('d', Some(Value::Int(inner)), _) => { self.output.push_str(format!("{:0width$}", inner, width=padding).as_str())  }
-                    ('f', Some(Value::Float(inner)), _) => { self.output.push_str(format!("{:0width$}", inner, width=padding).as_str())  }
-                    ('t', Some(Value::Bool(inner)), 0) => { self.output.push_str(inner.to_string().as_str()) }
-                    ('s', Some(Value::String(inner)), 0) => { self.output.push_str(inner) }
-                    ('v', Some(other), 0) => { self.output.push_str(other.to_string().as_str()) }
-                    (specifier, Some(value), _) => {
-                        return Err(InterruptKind::Error(format!("Formatierungsfehler: Unzulässige Kombination aus Formatierungsanweisung `{specifier}`, Pufferung `{padding}` und Eingabewert mit dem Datentyp `{}`", value.as_type()).into()));
+                let curr = match &spec.name {
+                    Some(name) => Some(self.named_arg(name)?),
+                    None => {
+                        let curr = self.input_args.get(self.inut_args_curr_pos).cloned();
+                        self.inut_args_curr_pos+=1;
+                        curr
                     }
-                    (specifier, None, _) => {
+                };
+
+                let width = spec.width;
+                match (char, curr.as_ref()) {
+                    // numbers are padded with zeros unless they are aligned to the left
+                    ('d', Some(Value::Int(inner))) if spec.left_align => { self.output.push_str(format!("{:<width$}", inner).as_str()) }
+                    ('d', Some(Value::Int(inner))) => { self.output.push_str(format!("{:0width$}", inner).as_str()) }
+                    ('x' | 'X' | 'b' | 'o', Some(Value::Int(inner))) => { self.push_in_base(char, *inner, &spec) }
+                    ('f', Some(Value::Float(inner))) if spec.left_align => { self.output.push_str(format!("{:<width$}", inner).as_str()) }
+                    ('f', Some(Value::Float(inner))) => { self.output.push_str(format!("{:0width$}", inner).as_str()) }
+                    ('t' | 's' | 'v', Some(_)) if spec.zero_pad => {
+                        return Err(InterruptKind::Error(format!("Formatierungsfehler: Die Formatierungsanweisung `{char}` kann nicht mit Nullen aufgefüllt werden, dies ist nur für Zahlen möglich.").into()));
+                    }
+                    ('t', Some(Value::Bool(inner))) => { self.push_aligned(&inner.to_string(), &spec) }
+                    ('s', Some(Value::String(inner))) => { self.push_aligned(inner, &spec) }
+                    ('v', Some(other)) => { self.push_aligned(&other.display(self.display_options), &spec) }
+                    (specifier, Some(value)) => {
+                        return Err(InterruptKind::Error(format!("Formatierungsfehler: Unzulässige Kombination aus Formatierungsanweisung `{specifier}`, Pufferung `{width}` und Eingabewert mit dem Datentyp `{}`", value.as_type()).into()));
+                    }
+                    (specifier, None) => {
                         return Err(InterruptKind::Error(format!("Formatierungsfehler: Erwartete Eingabewert für Formatierungsanweisung `{specifier}`, allerdings endet hier die Eingabe.").into()));
                     }
                 }
@@ -71,28 +87,125 @@ impl<'src> Formatter<'src> {
         Ok(())
     }
 
+    /// Looks up the value of a named placeholder, e.g. `%(Name)s`, in the Speicherbox or object
+    /// which is passed as the first argument.
+    fn named_arg(&self, name: &str) -> Result<Value, InterruptKind> {
+        let value = match self.input_args.first() {
+            Some(Value::Speicherbox(members) | Value::Objekt(members)) => {
+                members.borrow().get(name).cloned()
+            }
+            Some(other) => {
+                return Err(InterruptKind::Error(format!("Formatierungsfehler: Der benannte Platzhalter `{name}` benötigt eine Speicherbox oder ein Objekt als erstes Argument, allerdings wurde ein Wert mit dem Datentyp `{}` übergeben.", other.as_type()).into()));
+            }
+            None => {
+                return Err(InterruptKind::Error(format!("Formatierungsfehler: Der benannte Platzhalter `{name}` benötigt eine Speicherbox oder ein Objekt als erstes Argument, allerdings wurde kein Argument übergeben.").into()));
+            }
+        };
+
+        value.ok_or_else(|| InterruptKind::Error(format!("Formatierungsfehler: Der benannte Platzhalter `{name}` ist nicht im ersten Argument enthalten.").into()))
+    }
+
+    /// Writes an integer in hexadecimal (`x`, `X`), binary (`b`), or octal (`o`).
+    /// Negative numbers are written as their absolute value prefixed with `-` (e.g. `-ff`) instead
+    /// of using the two's complement, just like `%d` does.
+    fn push_in_base(&mut self, specifier: char, value: i64, spec: &Spec) {
+        let magnitude = value.unsigned_abs();
+        let digits = match specifier {
+            'x' => format!("{magnitude:x}"),
+            'X' => format!("{magnitude:X}"),
+            'b' => format!("{magnitude:b}"),
+            _ => format!("{magnitude:o}"),
+        };
+        let sign = if value < 0 { "-" } else { "" };
+
+        if spec.left_align {
+            self.push_aligned(&format!("{sign}{digits}"), spec)
+        } else {
+            // the zeros are inserted between the sign and the digits
+            let width = spec.width.saturating_sub(sign.len());
+            self.output
+                .push_str(format!("{sign}{digits:0>width$}").as_str())
+        }
+    }
+
+    /// Pads `text` with spaces to the width of the directive, the width counts characters.
+    fn push_aligned(&mut self, text: &str, spec: &Spec) {
+        let width = spec.width;
+        if spec.left_align {
+            self.output.push_str(format!("{:<width$}", text).as_str())
+        } else {
+            self.output.push_str(format!("{:>width$}", text).as_str())
+        }
+    }
+
+    /// Parses the name, the flags, and the width of a directive, e.g. `(Name)-5` in `%(Name)-5s`.
     fn start_escape(&mut self) -> Result<(), InterruptKind> {
         self.next();
 
-        match self.curr_char {
-            Some(char) => {
-                match char {
-                    '0' ..= '9' => {
-                        let mut padding = String::new();
-
-                        while let Some(char @ '0'..='9') = self.curr_char {
-                            padding.push(char);
-                            self.next();
-                        }
+        // `%%` is a literal percent sign, braces are never special
+        if self.curr_char == Some('%') {
+            self.next();
+            self.output.push('%');
+            return Ok(());
+        }
 
-                        self.escape(padding.parse().unwrap())?;
-                    }
-                    _=> self.escape(0)?
+        let mut spec = Spec::default();
+        if self.curr_char == Some('(') {
+            self.next();
+            let mut name = String::new();
+            loop {
+                match self.curr_char {
+                    Some(')') => break,
+                    Some(char) => name.push(char),
+                    None => return Err(InterruptKind::Error(format!("Formatierungsfehler: Erwartete `)` nach dem Namen des Platzhalters `{name}`, allerdings endet hier die Eingabe.").into())),
                 }
-            },
-            None => return Err(InterruptKind::Error("Formatierungsfehler: Erwartete Formatierungsanweisung, allerdings endet hier die Eingabe.".into())),
+                self.next();
+            }
+            self.next();
+            spec.name = Some(name);
+        }
+        if self.curr_char == Some('-') {
+            spec.left_align = true;
+            self.next();
+        }
+        if self.curr_char == Some('0') {
+            spec.zero_pad = true;
         }
 
-        Ok(())
+        let mut width = String::new();
+        while let Some(char @ '0'..='9') = self.curr_char {
+            width.push(char);
+            self.next();
+        }
+
+        if spec.left_align && spec.zero_pad {
+            return Err(InterruptKind::Error("Formatierungsfehler: Linksbündig ausgerichtete Werte können nicht mit Nullen aufgefüllt werden.".into()));
+        }
+        // `std::fmt` panics for widths which do not fit into a `u16`
+        if !width.is_empty() {
+            spec.width = width.parse::<u16>().map(usize::from).map_err(|_| {
+                InterruptKind::Error(
+                    format!("Formatierungsfehler: Die Breite `{width}` ist zu groß.").into(),
+                )
+            })?;
+        }
+
+        self.escape(spec)
     }
 }
+
+/// The name, the flags, and the width of a directive.
+/// For example, `%-5s` aligns a string to the left of 5 characters and `%05d` pads a number with
+/// zeros to 5 digits.
+/// Numbers (`%d`, `%f`, `%x`, `%X`, `%b`, and `%o`) are always padded with zeros unless they are
+/// aligned to the left, other values are padded with spaces.
+/// If a name is given, e.g. `%(Name)s`, the value is not taken from the next argument, but looked
+/// up in the Speicherbox or object which is passed as the first argument.
+#[derive(Debug, Default)]
+struct Spec {
+    name: Option<String>,
+    left_align: bool,
+    zero_pad: bool,
+    /// The minimum number of characters which are written.
+    width: usize,
+}
@@ -0,0 +1,92 @@
+//! Support for HTTP clients which perform their requests using `async` code.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake},
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+use crate::{HPIHttpClient, HttpError};
+
+/// Like [`HPIHttpClient`], but the request is performed by a future.
+///
+/// The interpreter itself is synchronous, so clients implementing this trait are passed to it
+/// using [`BlockingHttpClient`].
+pub trait AsyncHPIHttpClient {
+    fn request(
+        &self,
+        method: String,
+        url: &str,
+        body: String,
+        headers: HashMap<String, String>,
+    ) -> impl Future<Output = Result<(u16, String, HashMap<String, String>), HttpError>>;
+}
+
+/// Passes an [`AsyncHPIHttpClient`] to the interpreter.
+///
+/// Each `Http` call blocks the thread of the interpreter until the future of the request has
+/// completed, the future is polled using a minimal executor which parks the thread in between.
+/// If the future does not complete within the timeout, it is dropped and the request fails with
+/// [`HttpError::Timeout`], so clients do not have to implement the timeout themselves.
+/// When embedding the interpreter into an async server, it should therefore run on a thread
+/// which may block, e.g. using `tokio::task::spawn_blocking`, so that no task of the server is
+/// stalled.
+/// The executor provides no reactor, so futures which rely on one, such as the requests of
+/// async `reqwest`, should be spawned onto the runtime of the server.
+/// The returned handle (e.g. a `tokio::task::JoinHandle`) can then be awaited by the client,
+/// which lets the runtime perform the request while only the interpreter's thread waits.
+#[derive(Debug, Clone, Default)]
+pub struct BlockingHttpClient<Client>(pub Client);
+
+impl<Client: AsyncHPIHttpClient> HPIHttpClient for BlockingHttpClient<Client> {
+    fn request(
+        &self,
+        method: String,
+        url: &str,
+        body: String,
+        headers: HashMap<String, String>,
+        timeout: Duration,
+    ) -> Result<(u16, String, HashMap<String, String>), HttpError> {
+        block_on(self.0.request(method, url, body, headers), timeout)
+            .unwrap_or(Err(HttpError::Timeout))
+    }
+}
+
+/// Wakes the thread which is blocked on a future.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls the future on the current thread until it completes, returns `None` if this takes
+/// longer than `timeout`.
+fn block_on<F: Future>(future: F, timeout: Duration) -> Option<F::Output> {
+    // timeouts which are too large to be represented as an instant, e.g. `Duration::MAX`, never
+    // expire
+    let deadline = Instant::now().checked_add(timeout);
+    let mut future = pin!(future);
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return Some(output);
+        }
+        // spurious wake-ups only cause an additional poll
+        let Some(deadline) = deadline else {
+            thread::park();
+            continue;
+        };
+        match deadline.checked_duration_since(Instant::now()) {
+            Some(remaining) if !remaining.is_zero() => thread::park_timeout(remaining),
+            _ => return None,
+        }
+    }
+}
@@ -1,29 +1,176 @@
 use std::{
-    borrow::Cow, cell::RefCell, collections::HashMap, io::Write, rc::Rc, thread, time::Duration,
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Write as _},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    rc::Rc,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use chrono::{Datelike, Timelike};
-use hpi_analyzer::{ast::*, AssignOp, InfixOp, PrefixOp, Type};
+use chrono::{
+    format::{Item, StrftimeItems},
+    Datelike, NaiveDate, NaiveDateTime, Timelike,
+};
+use hpi_analyzer::{ast::*, AssignOp, InfixOp, PrefixOp, Span, Type};
+use indexmap::IndexMap;
 
 use crate::{
     format::Formatter,
-    json,
-    value::{InterruptKind, Value},
+    json::{self, DuplicateKeys, KeyOrder},
+    math::{self, ggt},
+    regex::Regex,
+    value::{DisplayOptions, FloatNotation, InterruptKind, PointerNotation, Value},
 };
 
-pub(crate) type Error = Cow<'static, str>;
+mod builder;
+mod determinism;
+mod error;
+mod statistics;
+mod step;
+
+pub use builder::InterpreterBuilder;
+pub use determinism::Determinismus;
+use determinism::Random;
+pub use error::{IndexTarget, RuntimeError};
+pub use statistics::Statistics;
+pub use step::{BreakpointOutcome, StepOutcome, Stepper};
+
+pub(crate) type Error = RuntimeError;
 type ExprResult = Result<Value, InterruptKind>;
 type StmtResult = Result<(), InterruptKind>;
 type Scope<'src> = HashMap<&'src str, Rc<RefCell<Value>>>;
 
 pub trait HPIHttpClient {
+    /// Performs a request and returns the status code, the body, and the headers of the response.
+    /// The header names may use any casing, as they are lowercased by the `Http` builtin.
+    /// If the response does not arrive within `timeout`, the request should be aborted using
+    /// [`HttpError::Timeout`].
     fn request(
         &self,
         method: String,
         url: &str,
         body: String,
         headers: HashMap<String, String>,
-    ) -> Result<(u16, String), String>;
+        timeout: Duration,
+    ) -> Result<(u16, String, HashMap<String, String>), HttpError>;
+}
+
+/// The reasons why a request of an [`HPIHttpClient`] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpError {
+    /// The response did not arrive within the timeout.
+    Timeout,
+    /// Any other error, the message is shown to the user.
+    Other(String),
+}
+
+impl From<String> for HttpError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+/// The writers which receive the output of `DruckeKanal`, indexed by channel name.
+#[derive(Default)]
+struct Channels<'src>(HashMap<String, Box<dyn Write + 'src>>);
+
+impl Debug for Channels<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// The writer which receives the output of `Fehlerdrucke`, see
+/// [`InterpreterBuilder::with_error_output`].
+#[derive(Default)]
+struct ErrorOutput<'src>(Option<Box<dyn Write + 'src>>);
+
+impl Debug for ErrorOutput<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(<writer>)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+/// The strings which `Drucke` writes between its arguments and after the last one, see
+/// [`InterpreterBuilder::with_drucke_separator`] and
+/// [`InterpreterBuilder::with_drucke_terminator`].
+#[derive(Debug)]
+struct DruckeFormat {
+    separator: String,
+    terminator: String,
+}
+
+impl Default for DruckeFormat {
+    fn default() -> Self {
+        Self {
+            separator: " ".to_string(),
+            terminator: "\n".to_string(),
+        }
+    }
+}
+
+/// The source of the lines read by `Lies_Zeile`.
+struct Input<'src>(Box<dyn BufRead + 'src>);
+
+impl Default for Input<'_> {
+    fn default() -> Self {
+        Self(Box::new(BufReader::new(io::stdin())))
+    }
+}
+
+impl Debug for Input<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<input>")
+    }
+}
+
+/// Receives the arguments of `Drucke` instead of the output, see
+/// [`InterpreterBuilder::with_drucke_callback`].
+#[derive(Default)]
+struct DruckeCallback<'src>(Option<Box<dyn FnMut(Vec<Value>) + 'src>>);
+
+impl Debug for DruckeCallback<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(<callback>)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+/// The default for [`InterpreterBuilder::with_http_timeout`].
+const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default for [`InterpreterBuilder::with_max_call_depth`].
+/// Each nested call uses up to about 15 KiB of stack in debug builds and 4 KiB in optimized
+/// builds, so this fits into the 8 MiB main thread of debug builds and 2 MiB threads otherwise.
+const DEFAULT_MAX_CALL_DEPTH: usize = 300;
+
+/// The maximum length in bytes of a string created by `Wiederhole`.
+const MAX_REPEATED_LEN: usize = 1 << 26;
+
+/// Describes how a program terminated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// `Studium` returned normally.
+    Completed,
+    /// The program was terminated using `Aufgeben` with the contained exit code.
+    Exited(i64),
+}
+
+impl RunOutcome {
+    /// The exit code of the program, which is 0 if the program completed normally.
+    pub fn code(self) -> i64 {
+        match self {
+            RunOutcome::Completed => 0,
+            RunOutcome::Exited(code) => code,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -32,11 +179,54 @@ where
     Output: Write,
     HttpClient: HPIHttpClient,
 {
-    output: Output,
+    /// Flushed when the program terminates, by `Leere_Ausgabe`, before `Lies_Zeile` reads, before
+    /// `Schlummere` sleeps, and before other writers receive output.
+    output: BufWriter<Output>,
     environment_variables: HashMap<String, String>,
     http_client: HttpClient,
+    http_timeout: Duration,
     scopes: Vec<Scope<'src>>,
     functions: HashMap<&'src str, Rc<AnalyzedFunctionDefinition<'src>>>,
+    /// The builtins imported using `beantrage`, they take precedence over user-defined functions.
+    used_builtins: HashSet<&'src str>,
+    /// The user-defined functions in the order of [`AnalyzedProgram::functions`], so that calls
+    /// resolved by the analyzer (see [`AnalyzedCallExpr::function_index`]) do not need to hash the
+    /// function name.
+    function_table: Vec<Rc<AnalyzedFunctionDefinition<'src>>>,
+    /// If set, loops are not slowed down artificially (see [`Interpreter::into_stepper`]).
+    cooperative: bool,
+    channels: Channels<'src>,
+    /// If set, `DruckeKanal` fails for channels without a registered writer.
+    strict_channels: bool,
+    /// If unset, `Fehlerdrucke` writes to the default output.
+    error_output: ErrorOutput<'src>,
+    display_options: DisplayOptions,
+    random: Random,
+    /// If set, this is passed to `Einschreibung` instead of a random Matrikelnummer.
+    matrikelnummer: Option<u32>,
+    /// If set, `Zeit` returns this time instead of the local time.
+    fixed_time: Option<NaiveDateTime>,
+    statistics: Rc<Statistics>,
+    /// The results remembered using `MerkeDir`, keys are namespaced by the calling function.
+    memo: HashMap<(&'src str, String), Value>,
+    /// `DruckeDebug` only prints if its level is at most this verbosity.
+    verbosity: u8,
+    /// The maximum nesting depth of values serialized by `Gliedere_JSON`.
+    json_max_depth: usize,
+    drucke_callback: DruckeCallback<'src>,
+    drucke_format: DruckeFormat,
+    /// The names of the user-defined functions which are currently being executed, the innermost
+    /// one last.
+    call_stack: Vec<&'src str>,
+    max_call_depth: usize,
+    /// The call stack at the point where the current runtime error was raised.
+    error_trace: Option<Vec<&'src str>>,
+    /// The location where the current runtime error was raised.
+    error_span: Option<Span<'src>>,
+    step_limit: Option<u64>,
+    /// The number of statements and loop iterations which have been executed so far.
+    steps: u64,
+    input: Input<'src>,
 }
 
 impl<'src, Output, HttpClient> Interpreter<'src, Output, HttpClient>
@@ -44,23 +234,132 @@ where
     Output: Write,
     HttpClient: HPIHttpClient,
 {
+    /// Creates an interpreter with the default options, use [`InterpreterBuilder`] to configure
+    /// them.
     pub fn new(
         output: Output,
         http_client: HttpClient,
         environment_variables: HashMap<String, String>,
     ) -> Self {
-        Self {
-            http_client,
-            output,
-            scopes: vec![],
-            functions: HashMap::new(),
-            environment_variables,
+        InterpreterBuilder::new(output, http_client)
+            .with_environment_variables(environment_variables)
+            .build()
+    }
+
+    pub fn run(self, tree: AnalyzedProgram<'src>) -> Result<i64, Error> {
+        self.run_to_outcome(tree).map(RunOutcome::code)
+    }
+
+    /// Like [`Interpreter::run`], but also reports how the program terminated.
+    pub fn run_to_outcome(self, tree: AnalyzedProgram<'src>) -> Result<RunOutcome, Error> {
+        self.run_located(tree).map_err(|(err, _)| err)
+    }
+
+    /// Like [`Interpreter::run_to_outcome`], but runtime errors are returned alongside the
+    /// location of the expression or statement which raised them.
+    /// The location is the innermost index, cast, or operator expression if the error was raised
+    /// by one, otherwise the innermost statement.
+    /// It is `None` if the error was raised outside of any statement, e.g. by the result of
+    /// `Bewerbung`.
+    pub fn run_located(
+        mut self,
+        tree: AnalyzedProgram<'src>,
+    ) -> Result<RunOutcome, (Error, Option<Box<Span<'src>>>)> {
+        let res = self.run_program(tree);
+        self.flush_output();
+        res
+    }
+
+    fn run_program(
+        &mut self,
+        tree: AnalyzedProgram<'src>,
+    ) -> Result<RunOutcome, (Error, Option<Box<Span<'src>>>)> {
+        self.init(tree).map_err(|err| (err, None))?;
+
+        // ignore interruptions (e.g. break, return)
+        match self.call_func(
+            &AnalyzedCallBase::Ident("Bewerbung"),
+            vec![],
+            &Type::String(0),
+        ) {
+            Err(InterruptKind::Error(msg)) => return Err(self.fail(msg)),
+            Err(InterruptKind::Exit(code)) => return Ok(RunOutcome::Exited(code)),
+            Ok(value) => check_bewerbung(value).map_err(|err| (err, None))?,
+            Err(_) => {}
+        };
+
+        let matrikelnummer = self.matrikelnummer();
+
+        match self.call_func(
+            &AnalyzedCallBase::Ident("Einschreibung"),
+            vec![Value::Int(matrikelnummer)],
+            &Type::Nichts,
+        ) {
+            Err(InterruptKind::Error(msg)) => return Err(self.fail(msg)),
+            Err(InterruptKind::Exit(code)) => return Ok(RunOutcome::Exited(code)),
+            Ok(_) | Err(_) => {}
+        };
+
+        match self.call_func(
+            &AnalyzedCallBase::Ident("Studium"),
+            vec![Value::Int(matrikelnummer)],
+            &Type::Nichts,
+        ) {
+            Err(InterruptKind::Error(msg)) => Err(self.fail(msg)),
+            Err(InterruptKind::Exit(code)) => Ok(RunOutcome::Exited(code)),
+            Ok(_) | Err(_) => Ok(RunOutcome::Completed),
         }
     }
 
-    pub fn run(mut self, tree: AnalyzedProgram<'src>) -> Result<i64, Error> {
-        for func in tree.functions.into_iter().filter(|f| f.used) {
-            self.functions.insert(func.name, func.into());
+    /// Writes the buffered output to the underlying writer.
+    pub(super) fn flush_output(&mut self) {
+        self.output.flush().expect("if this fails, we're screwed");
+    }
+
+    /// Prepares an error which terminates the program for being returned by
+    /// [`Interpreter::run_located`].
+    fn fail(&mut self, msg: Error) -> (Error, Option<Box<Span<'src>>>) {
+        (
+            self.with_traceback(msg),
+            self.error_span.take().map(Box::new),
+        )
+    }
+
+    /// Appends the functions which were being executed when the error was raised to its message.
+    /// Errors raised directly in `Bewerbung`, `Einschreibung`, or `Studium` are left unchanged.
+    fn with_traceback(&mut self, msg: Error) -> Error {
+        match self.error_trace.take() {
+            Some(trace) if trace.len() > 1 => RuntimeError::WithTraceback {
+                error: Box::new(msg),
+                trace: trace.into_iter().map(str::to_string).collect(),
+            },
+            _ => msg,
+        }
+    }
+
+    /// Prepares the program for cooperative execution.
+    /// Instead of running to completion, the returned [`Stepper`] executes the program in
+    /// small slices using [`Stepper::run_steps`].
+    /// In this mode, loops are not artificially slowed down, so that the host can decide when
+    /// to resume execution (e.g. on the next tick of a browser event loop).
+    pub fn into_stepper<'tree>(
+        mut self,
+        tree: &'tree AnalyzedProgram<'src>,
+    ) -> Stepper<'tree, 'src, Output, HttpClient> {
+        self.cooperative = true;
+        let init = self.init(tree.clone());
+        Stepper::new(self, tree, init)
+    }
+
+    /// Registers all functions and evaluates the global variables of the program.
+    fn init(&mut self, tree: AnalyzedProgram<'src>) -> Result<(), Error> {
+        self.used_builtins = tree.used_builtins;
+        for func in tree.functions {
+            let func = Rc::new(func);
+            if func.used {
+                self.functions.insert(func.name, Rc::clone(&func));
+            }
+            self.function_table.push(func);
         }
 
         let mut global_scope = HashMap::new();
@@ -73,12 +372,15 @@ where
                     AnalyzedExpression::Bool(bool) => Value::Bool(bool).wrapped(),
                     AnalyzedExpression::Char(num) => Value::Char(num).wrapped(),
                     AnalyzedExpression::String(str) => Value::String(str.to_string()).wrapped(),
-                    AnalyzedExpression::List(inner) => Value::List(Rc::new(RefCell::new(
-                        self.visit_list_expr_helper(&inner.values)
-                            .expect("the analyzer guarantees that this cannot happen"),
-                    )))
-                    .wrapped(),
-                    _ => unreachable!("the analyzer guarantees constant globals"),
+                    AnalyzedExpression::List(inner) => {
+                        match self.visit_list_expr_helper(&inner.values) {
+                            Ok(values) => Value::List(Rc::new(RefCell::new(values))).wrapped(),
+                            Err(_) => {
+                                analyzer_guaranteed!("the analyzer guarantees constant globals")
+                            }
+                        }
+                    }
+                    _ => analyzer_guaranteed!("the analyzer guarantees constant globals"),
                 },
             );
         }
@@ -123,46 +425,7 @@ where
             .into(),
         );
 
-        // ignore interruptions (e.g. break, return)
-        match self.call_func(&AnalyzedCallBase::Ident("Bewerbung"), vec![]) {
-            Err(InterruptKind::Error(msg)) => return Err(msg),
-            Err(InterruptKind::Exit(code)) => return Ok(code),
-            Ok(value) => {
-                if let Value::String(inner) = value {
-                    if inner.is_empty() {
-                        return Err("Ihre Bewerbung hat das HPI leider nicht überzeugt.\n Ist Ihr Bewerbungsschreiben vielleicht leer?".into());
-                    }
-                } else {
-                    unreachable!("the analyzer prevents this")
-                }
-            }
-            Err(_) => {}
-        };
-
-        let mut rand_buffer: [u8; 4] = [0; 4];
-        getrandom::getrandom(&mut rand_buffer).unwrap();
-        let matrikelnummer: u32 = (rand_buffer[0] as u32)
-            | (rand_buffer[1] as u32) << 8
-            | (rand_buffer[2] as u32) << 16
-            | (rand_buffer[3] as u32) << 24;
-
-        match self.call_func(
-            &AnalyzedCallBase::Ident("Einschreibung"),
-            vec![Value::Int(matrikelnummer as i64)],
-        ) {
-            Err(InterruptKind::Error(msg)) => return Err(msg),
-            Err(InterruptKind::Exit(code)) => return Ok(code),
-            Ok(_) | Err(_) => {}
-        };
-
-        match self.call_func(
-            &AnalyzedCallBase::Ident("Studium"),
-            vec![Value::Int(matrikelnummer as i64)],
-        ) {
-            Err(InterruptKind::Error(msg)) => Err(msg),
-            Err(InterruptKind::Exit(code)) => Ok(code),
-            Ok(_) | Err(_) => Ok(0),
-        }
+        Ok(())
     }
 
     //////////////////////////////////
@@ -182,13 +445,13 @@ where
         ))))
     }
 
-    fn get_var(&mut self, name: &'src str) -> Rc<RefCell<Value>> {
+    fn get_var(&mut self, name: &'src str) -> Result<Rc<RefCell<Value>>, InterruptKind> {
         for scope in self.scopes.iter().rev() {
             if let Some(var) = scope.get(name) {
-                return Rc::clone(var);
+                return Ok(Rc::clone(var));
             }
         }
-        unreachable!("the analyzer guarantees valid variable references: {name}")
+        analyzer_guaranteed!("the analyzer guarantees valid variable references: {name}")
     }
 
     fn scoped<T>(&mut self, scope: Scope<'src>, callback: impl FnOnce(&mut Self) -> T) -> T {
@@ -198,53 +461,531 @@ where
         res
     }
 
+    /// `result_type` is the type of the call as determined by the analyzer.
     fn call_func(
         &mut self,
         func_name: &AnalyzedCallBase<'src>,
-        mut args: Vec<Value>,
+        args: Vec<Value>,
+        result_type: &Type,
     ) -> ExprResult {
         match func_name {
-            AnalyzedCallBase::Ident("Aufgeben") => {
-                Err(InterruptKind::Exit(args.swap_remove(0).unwrap_int()))
+            AnalyzedCallBase::Ident(name) => match self.lookup_function(name) {
+                Some(func) => self.call_user_func(func, args),
+                None => self.call_builtin(name, args, result_type),
+            },
+            AnalyzedCallBase::Expr(expr) => {
+                let base = self.visit_expression(expr)?;
+                self.call_value(&base, args)
             }
-            AnalyzedCallBase::Ident("Drucke") => {
+        }
+    }
+
+    /// Calls the builtin function with the given name.
+    /// This is separate from [`Interpreter::call_func`] because the many builtins require a large
+    /// stack frame, which would otherwise be part of every recursive call of a user-defined
+    /// function.
+    /// Some builtins depend on `result_type`, e.g. the sum of an empty list has no element type
+    /// at runtime.
+    #[inline(never)]
+    fn call_builtin(
+        &mut self,
+        name: &'src str,
+        mut args: Vec<Value>,
+        result_type: &Type,
+    ) -> ExprResult {
+        match name {
+            "Aufgeben" => {
+                Err(InterruptKind::Exit(args.swap_remove(0).unwrap_int()?))
+            }
+            "Drucke" => {
+                if let Some(callback) = &mut self.drucke_callback.0 {
+                    callback(args);
+                    return Ok(Value::Unit);
+                }
+
                 self.output
                     .write_all(
-                        (args
-                            .iter()
-                            .map(|val| val.to_string())
-                            .collect::<Vec<String>>()
-                            .join(" ")
-                            + "\n")
+                        (display_args(&args, self.display_options, &self.drucke_format.separator)
+                            + &self.drucke_format.terminator)
                             .as_bytes(),
                     )
                     .expect("if this fails, we're screwed");
 
                 Ok(Value::Unit)
             }
-            AnalyzedCallBase::Ident("Zergliedere_JSON") => {
+            // like `Drucke`, but without the terminator
+            "Schreibe" => {
+                let text = display_args(&args, self.display_options, &self.drucke_format.separator);
+                self.output
+                    .write_all(text.as_bytes())
+                    .expect("if this fails, we're screwed");
+
+                Ok(Value::Unit)
+            }
+            "DruckeDebug" => {
+                let level = args[0].clone().unwrap_int()?;
+                if level <= self.verbosity as i64 {
+                    let line = display_args(&args[1..], self.display_options, " ") + "\n";
+                    self.output
+                        .write_all(line.as_bytes())
+                        .expect("if this fails, we're screwed");
+                }
+
+                Ok(Value::Unit)
+            }
+            "DruckeKanal" => {
+                let Value::String(channel) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+
+                let line = display_args(&args[1..], self.display_options, " ") + "\n";
+                match self.channels.0.get_mut(channel) {
+                    // preserves the order of the lines written to the default output
+                    Some(writer) => self
+                        .output
+                        .flush()
+                        .and_then(|()| writer.write_all(line.as_bytes())),
+                    None if self.strict_channels => {
+                        return Err(InterruptKind::Error(
+                            format!("Unbekannter Ausgabekanal `{channel}`.").into(),
+                        ))
+                    }
+                    None => self.output.write_all(line.as_bytes()),
+                }
+                .expect("if this fails, we're screwed");
+
+                Ok(Value::Unit)
+            }
+            "Leere_Ausgabe" => {
+                self.flush_output();
+                Ok(Value::Unit)
+            }
+            "Fehlerdrucke" => {
+                let line = display_args(&args, self.display_options, " ") + "\n";
+                match &mut self.error_output.0 {
+                    Some(writer) => self
+                        .output
+                        .flush()
+                        .and_then(|()| writer.write_all(line.as_bytes())),
+                    None => self.output.write_all(line.as_bytes()),
+                }
+                .expect("if this fails, we're screwed");
+
+                Ok(Value::Unit)
+            }
+            // at the end of the input, a runtime error is raised so that it can be distinguished
+            // from an empty line
+            "Lies_Zeile" => {
+                // prompts are usually printed without a trailing newline
+                self.flush_output();
+                let mut line = String::new();
+                let read = self.input.0.read_line(&mut line).map_err(|err| {
+                    InterruptKind::Error(
+                        format!("`Lies_Zeile` konnte nicht lesen: {err}").into(),
+                    )
+                })?;
+                if read == 0 {
+                    return Err(InterruptKind::Error(
+                        "`Lies_Zeile` hat das Ende der Eingabe erreicht.".into(),
+                    ));
+                }
+
+                let line = line
+                    .strip_suffix('\n')
+                    .map(|line| line.strip_suffix('\r').unwrap_or(line))
+                    .unwrap_or(&line);
+                Ok(Value::String(line.to_string()))
+            }
+            "Zergliedere_JSON" | "Zergliedere_JSON_Streng" => {
                 let Value::String(string_input) = args[0].clone() else {
-                    unreachable!("the analyzer prevents this")
+                    analyzer_guaranteed!("the analyzer prevents this")
+                };
+                let duplicate_keys = match name {
+                    "Zergliedere_JSON_Streng" => DuplicateKeys::Reject,
+                    _ => DuplicateKeys::KeepLast,
                 };
 
-                json::deserialize(&string_input)
+                json::deserialize(&string_input, duplicate_keys)
             }
-            AnalyzedCallBase::Ident("Gliedere_JSON") => {
-                let res = json::serialize(args[0].clone())?;
+            "Gliedere_JSON" => {
+                let res = json::serialize(
+                    args[0].clone(),
+                    KeyOrder::default(),
+                    self.json_max_depth,
+                    None,
+                )?;
                 Ok(Value::String(res))
             }
-            AnalyzedCallBase::Ident("Formatiere") => {
+            "Gliedere_JSON_Schön" => {
+                let res = json::serialize(
+                    args[0].clone(),
+                    KeyOrder::default(),
+                    self.json_max_depth,
+                    Some(2),
+                )?;
+                Ok(Value::String(res))
+            }
+            "Gliedere_JSON_Sortiert" => {
+                let Value::String(key_order) = &args[1] else {
+                    analyzer_guaranteed!("the analyzer prevents this")
+                };
+                let key_order = key_order
+                    .parse()
+                    .map_err(|err: String| InterruptKind::Error(err.into()))?;
+
+                let res = json::serialize(args[0].clone(), key_order, self.json_max_depth, None)?;
+                Ok(Value::String(res))
+            }
+            "DruckeTabelle" => {
+                let rows: Vec<Vec<String>> = args[0]
+                    .clone()
+                    .unwrap_list()?
+                    .borrow()
+                    .iter()
+                    .map(|row| {
+                        Ok(row
+                            .clone()
+                            .unwrap_list()?
+                            .borrow()
+                            .iter()
+                            .map(|cell| cell.display(self.display_options))
+                            .collect())
+                    })
+                    .collect::<Result<_, InterruptKind>>()?;
+
+                self.output
+                    .write_all(table(&rows).as_bytes())
+                    .expect("if this fails, we're screwed");
+
+                Ok(Value::Unit)
+            }
+            "Formatiere" => {
                 let Value::String(inner) = &args[0] else {
-                    unreachable!("the analyzer prevents this");
+                    analyzer_guaranteed!("the analyzer prevents this");
                 };
-                let fmt = Formatter::new(inner, args[1..].to_vec());
+                let fmt = Formatter::new(inner, args[1..].to_vec(), self.display_options);
                 let res = fmt.format()?;
                 Ok(Value::String(res))
             }
-            AnalyzedCallBase::Ident("Zeit") => {
-                let now = chrono::offset::Local::now();
+            "ErsetzeAlleRegex" => {
+                let [Value::String(text), Value::String(pattern), Value::String(replacement)] =
+                    &args[..]
+                else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                let res = compile_regex("ErsetzeAlleRegex", pattern)?
+                    .replace_all(text, replacement)
+                    .map_err(|err| regex_error("ErsetzeAlleRegex", pattern, err))?;
+                Ok(Value::String(res))
+            }
+            "PasstAuf" => {
+                let [Value::String(text), Value::String(pattern)] = &args[..] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                Ok(Value::Bool(
+                    compile_regex("PasstAuf", pattern)?.is_match(text),
+                ))
+            }
+            "FindeMuster" => {
+                let [Value::String(text), Value::String(pattern)] = &args[..] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                // the whole match is followed by the groups, unmatched groups are empty
+                let groups = compile_regex("FindeMuster", pattern)?
+                    .captures(text)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|group| Value::String(group.unwrap_or_default()))
+                    .collect();
+                Ok(Value::List(Rc::new(RefCell::new(groups))))
+            }
+            "AlsWahrheitswert" => {
+                let Value::String(input) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                match input.trim().to_lowercase().as_str() {
+                    "wahr" | "ja" | "true" | "1" => Ok(Value::Bool(true)),
+                    "falsch" | "nein" | "false" | "0" => Ok(Value::Bool(false)),
+                    _ => Err(InterruptKind::Error(
+                        format!("Die Zeichenkette `{input}` kann nicht in einen Wahrheitswert umgewandelt werden.").into(),
+                    )),
+                }
+            }
+            "KehreZeichenketteUm" => {
+                let Value::String(input) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                Ok(Value::String(reverse(input)))
+            }
+            // `to_uppercase` also handles characters without a single uppercase form, e.g. `ß`
+            name @ ("Grossbuchstaben" | "Kleinbuchstaben") => {
+                let Value::String(input) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                Ok(Value::String(match name {
+                    "Grossbuchstaben" => input.to_uppercase(),
+                    _ => input.to_lowercase(),
+                }))
+            }
+            "Trennen" => {
+                let (Value::String(input), Value::String(separator)) = (&args[0], &args[1]) else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                if separator.is_empty() {
+                    return Err(InterruptKind::Error(
+                        "`Trennen` benötigt ein nicht-leeres Trennzeichen.".into(),
+                    ));
+                }
+
+                let pieces = input
+                    .split(separator.as_str())
+                    .map(|piece| Value::String(piece.to_string()))
+                    .collect();
+                Ok(Value::List(Rc::new(RefCell::new(pieces))))
+            }
+            "Ersetzen" => {
+                let (Value::String(input), Value::String(pattern), Value::String(replacement)) =
+                    (&args[0], &args[1], &args[2])
+                else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                if pattern.is_empty() {
+                    return Err(InterruptKind::Error(
+                        "`Ersetzen` benötigt eine nicht-leere zu ersetzende Zeichenkette.".into(),
+                    ));
+                }
+
+                Ok(Value::String(input.replace(pattern.as_str(), replacement)))
+            }
+            // whitespace is anything with the Unicode `White_Space` property, e.g. non-breaking spaces
+            name @ ("Zuschneiden" | "Zuschneiden_Links" | "Zuschneiden_Rechts") => {
+                let Value::String(input) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                let trimmed = match name {
+                    "Zuschneiden" => input.trim(),
+                    "Zuschneiden_Links" => input.trim_start(),
+                    _ => input.trim_end(),
+                };
+                Ok(Value::String(trimmed.to_string()))
+            }
+            // indices count Unicode scalar values, not bytes
+            "Zeichen_Bei" => {
+                let (Value::String(input), Value::Int(idx)) = (&args[0], &args[1]) else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                if *idx < 0 {
+                    return Err(InterruptKind::Error(RuntimeError::NegativeIndex(*idx)));
+                }
+                match input.chars().nth(*idx as usize) {
+                    // a `Zeichen` can only hold ASCII characters
+                    Some(character) if character.is_ascii() => Ok(Value::Char(character as u8)),
+                    Some(character) => Err(InterruptKind::Error(
+                        format!(
+                            "`Zeichen_Bei`: Das Zeichen `{character}` an Index `{idx}` ist kein ASCII-Zeichen und kann nicht als `Zeichen` dargestellt werden."
+                        )
+                        .into(),
+                    )),
+                    None => Err(InterruptKind::Error(RuntimeError::IndexOutOfBounds {
+                        index: *idx,
+                        len: input.chars().count(),
+                        target: IndexTarget::String,
+                    })),
+                }
+            }
+            // these accept exactly the strings which can be cast using `als`
+            "Ist_Zahl" => {
+                let Value::String(input) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                Ok(Value::Bool(normalize_number(input).parse::<i64>().is_ok()))
+            }
+            "Ist_Fließkommazahl" => {
+                let Value::String(input) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                Ok(Value::Bool(normalize_number(input).parse::<f64>().is_ok()))
+            }
+            // like `Zeichen_Bei`, the bounds count Unicode scalar values
+            "Teilzeichenkette" => {
+                let (Value::String(input), Value::Int(start), Value::Int(end)) =
+                    (&args[0], &args[1], &args[2])
+                else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                if start > end {
+                    return Err(InterruptKind::Error(
+                        format!("`Teilzeichenkette`: Der Anfang `{start}` liegt hinter dem Ende `{end}`.").into(),
+                    ));
+                }
+                let len = input.chars().count();
+                if *start < 0 || *end as usize > len {
+                    return Err(InterruptKind::Error(
+                        format!(
+                            "`Teilzeichenkette`: Der Bereich von `{start}` bis `{end}` liegt außerhalb der Zeichenkette der Länge `{len}`."
+                        )
+                        .into(),
+                    ));
+                }
+
+                Ok(Value::String(
+                    input
+                        .chars()
+                        .skip(*start as usize)
+                        .take((end - start) as usize)
+                        .collect(),
+                ))
+            }
+            "Index_Von" => {
+                let (Value::String(input), Value::String(needle)) = (&args[0], &args[1]) else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+
+                // the index counts characters, just like the one of `Zeichen_Bei`
+                let index = input
+                    .find(needle.as_str())
+                    .map_or(-1, |byte| input[..byte].chars().count() as i64);
+                Ok(Value::Int(index))
+            }
+            "Beginnt_Mit" | "Endet_Mit" => {
+                let (Value::String(input), Value::String(pattern)) = (&args[0], &args[1]) else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+
+                Ok(Value::Bool(match name {
+                    "Beginnt_Mit" => input.starts_with(pattern.as_str()),
+                    _ => input.ends_with(pattern.as_str()),
+                }))
+            }
+            "Wiederhole" => {
+                let (Value::String(input), Value::Int(count)) = (&args[0], &args[1]) else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                if *count < 0 {
+                    return Err(InterruptKind::Error(
+                        format!("`Wiederhole`: Die Anzahl `{count}` darf nicht negativ sein.").into(),
+                    ));
+                }
+                // prevents programs from exhausting the memory of the host
+                match usize::try_from(*count)
+                    .ok()
+                    .and_then(|count| input.len().checked_mul(count))
+                {
+                    Some(len) if len <= MAX_REPEATED_LEN => {}
+                    _ => {
+                        return Err(InterruptKind::Error(
+                            format!(
+                                "`Wiederhole`: Das Ergebnis wäre länger als die erlaubten `{MAX_REPEATED_LEN}` Bytes."
+                            )
+                            .into(),
+                        ))
+                    }
+                }
+
+                Ok(Value::String(input.repeat(*count as usize)))
+            }
+            // a `Zeichen` is an ASCII character, so the codes range from `0` to `127`
+            "Zeichen_Code" => {
+                let Value::Char(code) = args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                Ok(Value::Int(code as i64))
+            }
+            "Von_Code" => {
+                let code = args[0].clone().unwrap_int()?;
+                match u8::try_from(code) {
+                    Ok(code) if code.is_ascii() => Ok(Value::Char(code)),
+                    _ => Err(InterruptKind::Error(
+                        format!("`Von_Code`: Der Code `{code}` liegt außerhalb des ASCII-Bereichs von `0` bis `127`.").into(),
+                    )),
+                }
+            }
+            "Verbinden" => {
+                let Value::String(separator) = &args[1] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                let pieces = args[0]
+                    .clone()
+                    .unwrap_list()?
+                    .borrow()
+                    .iter()
+                    .map(|piece| match piece {
+                        Value::String(piece) => Ok(piece.clone()),
+                        other => Err(InterruptKind::Error(
+                            format!(
+                                "`Verbinden` kann nur Zeichenketten verbinden, allerdings wurde ein Wert vom Typ `{}` übergeben.",
+                                other.as_type()
+                            )
+                            .into(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Value::String(pieces.join(separator)))
+            }
+            "ZerlegeZeilen" => {
+                let Value::String(input) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                // `lines` accepts both `\n` and `\r\n` and ignores a trailing line break
+                let lines = input
+                    .lines()
+                    .map(|line| Value::String(line.to_string()))
+                    .collect();
+                Ok(Value::List(Rc::new(RefCell::new(lines))))
+            }
+            "ListeAlsText" => {
+                let Value::String(separator) = &args[1] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                let values = args[0].clone().unwrap_list()?.borrow().clone();
+
+                let mut parts = Vec::with_capacity(values.len());
+                for value in values {
+                    match self.call_value(&args[2], vec![value])? {
+                        Value::String(part) => parts.push(part),
+                        _ => analyzer_guaranteed!("the analyzer prevents this"),
+                    }
+                }
+                Ok(Value::String(parts.join(separator)))
+            }
+            "Abbilden" => {
+                // the list is copied, so the function may modify it while it is being mapped
+                let values = args[0].clone().unwrap_list()?.borrow().clone();
+
+                let mut mapped = Vec::with_capacity(values.len());
+                for value in values {
+                    mapped.push(self.call_value(&args[1], vec![value])?);
+                }
+                Ok(Value::List(Rc::new(RefCell::new(mapped))))
+            }
+            "Filtern" => {
+                let values = args[0].clone().unwrap_list()?.borrow().clone();
+
+                let mut kept = vec![];
+                for value in values {
+                    match self.call_value(&args[1], vec![value.clone()])? {
+                        Value::Bool(true) => kept.push(value),
+                        Value::Bool(false) => {}
+                        _ => analyzer_guaranteed!("the analyzer prevents this"),
+                    }
+                }
+                Ok(Value::List(Rc::new(RefCell::new(kept))))
+            }
+            "Reduziere" => {
+                let values = args[0].clone().unwrap_list()?.borrow().clone();
+
+                let mut accumulator = args[1].clone();
+                for value in values {
+                    accumulator = self.call_value(&args[2], vec![accumulator, value])?;
+                }
+                Ok(accumulator)
+            }
+            "Zeit" => {
+                let now = self
+                    .fixed_time
+                    .unwrap_or_else(|| chrono::offset::Local::now().naive_local());
 
-                let members = HashMap::from([
+                let members = IndexMap::from([
                     ("Jahr".to_string(), Value::Int(now.year() as i64)),
                     ("Monat".to_string(), Value::Int(now.month() as i64)),
                     ("Kalendar_Tag".to_string(), Value::Int(now.day() as i64)),
@@ -256,7 +997,60 @@ where
 
                 Ok(Value::Objekt(Rc::new(RefCell::new(members))))
             }
-            AnalyzedCallBase::Ident("Http") => {
+            // like `Zeit_Millisekunden`, numbers are interpreted as milliseconds in UTC
+            "Zeit_Formatieren" => {
+                let Value::String(pattern) = &args[1] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+
+                let time = match &args[0] {
+                    Value::Int(millis) => NaiveDateTime::from_timestamp_millis(*millis),
+                    Value::Objekt(members) => {
+                        let members = members.borrow();
+                        let field = |key: &str| members[key].clone().unwrap_int();
+                        let (year, month, day) =
+                            (field("Jahr")?, field("Monat")?, field("Kalendar_Tag")?);
+                        let (hour, minute, second) =
+                            (field("Stunde")?, field("Minute")?, field("Sekunde")?);
+                        let date = i32::try_from(year).ok().and_then(|year| {
+                            NaiveDate::from_ymd_opt(
+                                year,
+                                u32::try_from(month).ok()?,
+                                u32::try_from(day).ok()?,
+                            )
+                        });
+                        date.and_then(|date| {
+                            date.and_hms_opt(
+                                u32::try_from(hour).ok()?,
+                                u32::try_from(minute).ok()?,
+                                u32::try_from(second).ok()?,
+                            )
+                        })
+                    }
+                    _ => analyzer_guaranteed!("the analyzer prevents this"),
+                };
+                let Some(time) = time else {
+                    return Err(InterruptKind::Error(
+                        format!("`Zeit_Formatieren`: Der Zeitpunkt `{}` ist ungültig.", args[0])
+                            .into(),
+                    ));
+                };
+
+                // chrono only reports invalid specifiers when the formatted time is displayed,
+                // specifiers of the time zone fail there as well because the time has none
+                let items: Vec<Item> = StrftimeItems::new(pattern).collect();
+                let mut formatted = String::new();
+                if items.contains(&Item::Error)
+                    || write!(formatted, "{}", time.format_with_items(items.iter())).is_err()
+                {
+                    return Err(InterruptKind::Error(
+                        format!("`Zeit_Formatieren`: Das Format `{pattern}` ist ungültig.").into(),
+                    ));
+                }
+
+                Ok(Value::String(formatted))
+            }
+            "Http" => {
                 // BuiltinFunction::new(ParamTypes::Normal(vec![
                 //                         Type::String(0), // method
                 //                         Type::String(0), // url
@@ -264,19 +1058,19 @@ where
                 //                         Type::List(Box::new(Type::String(0)), 0); // headers
 
                 let Value::String(method) = args[0].clone() else {
-                    unreachable!("the analyzer prevents this");
+                    analyzer_guaranteed!("the analyzer prevents this");
                 };
 
                 let Value::String(url) = args[1].clone() else {
-                    unreachable!("the analyzer prevents this");
+                    analyzer_guaranteed!("the analyzer prevents this");
                 };
 
                 let Value::String(body) = args[2].clone() else {
-                    unreachable!("the analyzer prevents this");
+                    analyzer_guaranteed!("the analyzer prevents this");
                 };
 
                 let Value::List(list_inner) = args[3].clone() else {
-                    unreachable!("the analyzer prevents this");
+                    analyzer_guaranteed!("the analyzer prevents this");
                 };
 
                 let headers = list_inner
@@ -284,95 +1078,699 @@ where
                     .iter()
                     .map(|element| {
                         let Value::Objekt(members) = element else {
-                            unreachable!("the analyzer prevents this");
+                            analyzer_guaranteed!("the analyzer prevents this");
                         };
 
-                        let Value::String(key) = members.borrow().get("Schlüssel").unwrap().clone()
+                        let Some(Value::String(key)) = members.borrow().get("Schlüssel").cloned()
                         else {
-                            unreachable!("the analyzer prevents this");
+                            analyzer_guaranteed!("the analyzer prevents this");
                         };
 
-                        let Value::String(value) = members.borrow().get("Wert").unwrap().clone()
+                        let Some(Value::String(value)) = members.borrow().get("Wert").cloned()
                         else {
-                            unreachable!("the analyzer prevents this");
+                            analyzer_guaranteed!("the analyzer prevents this");
                         };
 
-                        (key, value)
+                        Ok((key, value))
                     })
-                    .collect::<HashMap<_, _>>();
+                    .collect::<Result<HashMap<_, _>, InterruptKind>>()?;
 
+                // the analyzer prevents this, but the tree could have been modified afterwards
                 let Value::Ptr(body_ptr) = args[4].clone() else {
-                    unreachable!("the analyzer prevents this");
+                    return Err(InterruptKind::Error(
+                        format!(
+                            "`Http` erwartet als fünftes Argument einen Zeiger, in welchen die Antwort geschrieben wird, allerdings wurde ein Wert vom Typ `{}` übergeben.",
+                            args[4].as_type()
+                        )
+                        .into(),
+                    ));
                 };
 
-                let res = self
+                let (status, body, headers) = self
                     .http_client
-                    .request(method, url.as_str(), body, headers)
-                    .map_err(|err| InterruptKind::Error(err.into()))?;
+                    .request(method, url.as_str(), body, headers, self.http_timeout)
+                    .map_err(|err| match err {
+                        HttpError::Timeout => InterruptKind::Error(
+                            format!(
+                                "Zeitüberschreitung bei HTTP-Anfrage: Die Antwort auf `{url}` ist nicht innerhalb von {} Millisekunden eingetroffen.",
+                                self.http_timeout.as_millis()
+                            )
+                            .into(),
+                        ),
+                        HttpError::Other(message) => InterruptKind::Error(message.into()),
+                    })?;
+
+                *body_ptr.borrow_mut() = Value::String(body);
+
+                // header names are case-insensitive, so they are lowercased and sorted to be
+                // independent of the client
+                if let Some(Value::Ptr(headers_ptr)) = args.get(5) {
+                    let mut headers: Vec<(String, Value)> = headers
+                        .into_iter()
+                        .map(|(key, value)| (key.to_lowercase(), Value::String(value)))
+                        .collect();
+                    headers.sort_by(|left, right| left.0.cmp(&right.0));
+                    *headers_ptr.borrow_mut() =
+                        Value::Speicherbox(Rc::new(RefCell::new(headers.into_iter().collect())));
+                }
+
+                Ok(Value::Int(status as i64))
+            }
+            // this is the wall-clock time, so it may go backwards, e.g. if the system clock is
+            // adjusted
+            "Zeit_Millisekunden" => {
+                if let Some(fixed_time) = self.fixed_time {
+                    return Ok(Value::Int(fixed_time.timestamp_millis()));
+                }
 
-                *body_ptr.borrow_mut() = Value::String(res.1);
+                // `SystemTime` is not available in the browser, chrono uses the clock of JS instead
+                #[cfg(target_arch = "wasm32")]
+                let millis = chrono::Utc::now().timestamp_millis();
+                #[cfg(not(target_arch = "wasm32"))]
+                let millis = match SystemTime::now().duration_since(UNIX_EPOCH) {
+                    Ok(elapsed) => elapsed.as_millis() as i64,
+                    Err(err) => -(err.duration().as_millis() as i64),
+                };
 
-                Ok(Value::Int(res.0 as i64))
+                Ok(Value::Int(millis))
             }
-            AnalyzedCallBase::Ident("Schlummere") => {
+            "Schlummere" => {
                 #[cfg(target_arch = "wasm32")]
                 {
                     return Err(InterruptKind::Error("Im Web wird nicht geschlafen!".into()));
                 }
 
                 if let Value::Float(duration) = args[0] {
+                    // the output so far is displayed while sleeping
+                    self.flush_output();
                     thread::sleep(Duration::from_secs_f64(duration));
                 } else {
-                    unreachable!("the analyzer prevents this")
+                    analyzer_guaranteed!("the analyzer prevents this")
                 }
 
                 Ok(Value::Unit)
             }
-            AnalyzedCallBase::Ident("Geld") => Ok(Value::String(String::from(
+            "Geld" => Ok(Value::String(String::from(
                 "Nun sind Sie reich, sie wurden gesponst!",
             ))),
-            AnalyzedCallBase::Ident("Umgebungsvariablen") => {
+            "Umgebungsvariablen" => {
                 let inner = self
                     .environment_variables
                     .iter()
                     .map(|(key, value)| (key.clone(), Value::String(value.clone())))
                     .collect();
-                Ok(Value::Speicherbox(inner))
+                Ok(Value::Speicherbox(Rc::new(RefCell::new(inner))))
             }
-            AnalyzedCallBase::Ident(func_name) => {
-                let func = Rc::clone(&self.functions[func_name]);
+            "Enthält" => match &args[..] {
+                [Value::String(haystack), Value::String(needle)] => {
+                    Ok(Value::Bool(haystack.contains(needle.as_str())))
+                }
+                [Value::List(values), element] => {
+                    Ok(Value::Bool(values.borrow().contains(element)))
+                }
+                _ => analyzer_guaranteed!("the analyzer prevents this"),
+            },
+            // lists have reference semantics, so the changes are visible through every copy
+            "Hinzufügen" => {
+                let value = args.swap_remove(1);
+                args[0].clone().unwrap_list()?.borrow_mut().push(value);
+                Ok(Value::Unit)
+            }
+            "Entferne_Letztes" => args[0].clone().unwrap_list()?.borrow_mut().pop().ok_or_else(|| {
+                InterruptKind::Error("`Entferne_Letztes` kann nicht aus einer leeren Liste entfernen.".into())
+            }),
+            // strings are reversed by characters, so multi-byte characters stay intact
+            "Umkehren" => match &args[0] {
+                Value::String(input) => Ok(Value::String(input.chars().rev().collect())),
+                Value::List(values) => {
+                    let reversed = values.borrow().iter().rev().cloned().collect();
+                    Ok(Value::List(Rc::new(RefCell::new(reversed))))
+                }
+                _ => analyzer_guaranteed!("the analyzer prevents this"),
+            },
+            "Kopiere" => Ok(args[0].deep_clone()),
+            "Sortiere" => {
+                let mut values = args[0].clone().unwrap_list()?.borrow().clone();
+                if values
+                    .iter()
+                    .any(|value| matches!(value, Value::Float(num) if num.is_nan()))
+                {
+                    return Err(InterruptKind::Error(
+                        "`Sortiere` kann keine Liste sortieren, welche `NaN` enthält.".into(),
+                    ));
+                }
 
-                let mut scope = HashMap::new();
-                for (param, arg) in func.params.iter().zip(args) {
-                    scope.insert(param.name, arg.wrapped());
+                // reports values which are not comparable before sorting, as `sort_by` cannot fail
+                for pair in values.windows(2) {
+                    pair[0].compare(&pair[1])?;
                 }
+                values.sort_by(|left, right| {
+                    left.partial_cmp(right)
+                        .expect("only `NaN` is not comparable, which is rejected above")
+                });
+                Ok(Value::List(Rc::new(RefCell::new(values))))
+            }
+            // `Objekt_Schlüssel` also accepts objects, e.g. to enumerate their fields generically
+            "Schlüssel" | "Objekt_Schlüssel" => {
+                let (Value::Speicherbox(members) | Value::Objekt(members)) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                let mut keys: Vec<String> = members.borrow().keys().cloned().collect();
+                keys.sort();
+                let keys = keys.into_iter().map(Value::String).collect();
+                Ok(Value::List(Rc::new(RefCell::new(keys))))
+            }
+            "Hole" => {
+                let (Value::Speicherbox(members), Value::String(key)) = (&args[0], &args[1]) else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                members.borrow().get(key).cloned().ok_or_else(|| {
+                    InterruptKind::Error(
+                        format!("`Hole`: Die Speicherbox enthält keinen Schlüssel `{key}`.").into(),
+                    )
+                })
+            }
+            "Feld" => {
+                let (Value::Speicherbox(members) | Value::Objekt(members), Value::String(key)) =
+                    (&args[0], &args[1])
+                else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                members.borrow().get(key).cloned().ok_or_else(|| {
+                    InterruptKind::Error(
+                        format!("`Feld`: Das Objekt enthält kein Feld `{key}`.").into(),
+                    )
+                })
+            }
+            "Setze" => {
+                let value = args.swap_remove(2);
+                let (Value::Speicherbox(members), Value::String(key)) = (&args[0], &args[1]) else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                members.borrow_mut().insert(key.clone(), value);
+                Ok(Value::Unit)
+            }
+            "UmgebungsvariableEgalGroß" => {
+                let Value::String(name) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+
+                // if several variables only differ in case, an exact match is preferred,
+                // otherwise the lexicographically smallest name wins
+                let value = match self.environment_variables.get(name) {
+                    Some(value) => Some(value),
+                    None => self
+                        .environment_variables
+                        .iter()
+                        .filter(|(key, _)| key.to_lowercase() == name.to_lowercase())
+                        .min_by(|left, right| left.0.cmp(right.0))
+                        .map(|(_, value)| value),
+                };
+                Ok(Value::String(value.cloned().unwrap_or_default()))
+            }
+            "Versuche" => {
+                let (success, value, error) = match self.call_value(&args[0], vec![]) {
+                    Ok(value) => (true, value, String::new()),
+                    Err(InterruptKind::Error(msg)) => {
+                        self.error_trace = None;
+                        self.error_span = None;
+                        (false, Value::Unit, msg.to_string())
+                    }
+                    Err(interrupt) => return Err(interrupt),
+                };
 
-                self.scoped(scope, |self_| match self_.visit_block(&func.block, false) {
-                    Ok(val) => Ok(val),
-                    Err(interrupt) => Ok(interrupt.into_value()?),
+                let members = IndexMap::from([
+                    ("Erfolg".to_string(), Value::Bool(success)),
+                    ("Wert".to_string(), value),
+                    ("Fehler".to_string(), Value::String(error)),
+                ]);
+
+                Ok(Value::Objekt(Rc::new(RefCell::new(members))))
+            }
+            "Summe" => {
+                let values = args[0].clone().unwrap_list()?;
+                let values = values.borrow();
+                if let (Some(Value::Float(_)), _) | (None, Type::Float(0)) =
+                    (values.first(), result_type)
+                {
+                    let floats = values
+                        .iter()
+                        .map(|value| value.clone().unwrap_float())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let sum = math::sum_floats(floats);
+                    return Ok(Value::Float(sum));
+                }
+
+                // accumulate in `i128` so that only the final result has to fit into a `Zahl`
+                let sum: i128 = values
+                    .iter()
+                    .map(|value| Ok(value.clone().unwrap_int()? as i128))
+                    .sum::<Result<_, InterruptKind>>()?;
+
+                i64::try_from(sum).map(Value::Int).map_err(|_| {
+                    InterruptKind::Error(RuntimeError::Overflow {
+                        operation: "Summe",
+                        result: Some(sum),
+                    })
                 })
             }
-            AnalyzedCallBase::Expr(expr) => {
-                let base = self.visit_expression(expr)?;
+            "Durchschnitt" => {
+                let values = args[0].clone().unwrap_list()?;
+                let values = values.borrow();
+                let sum = match values.first() {
+                    Some(Value::Float(_)) => math::sum_floats(
+                        values
+                            .iter()
+                            .map(|value| value.clone().unwrap_float())
+                            .collect::<Result<Vec<_>, _>>()?,
+                    ),
+                    Some(_) => {
+                        values
+                            .iter()
+                            .map(|value| Ok(value.clone().unwrap_int()? as i128))
+                            .sum::<Result<i128, InterruptKind>>()? as f64
+                    }
+                    None => {
+                        return Err(InterruptKind::Error(
+                            "`Durchschnitt` kann nicht auf eine leere Liste angewendet werden.".into(),
+                        ))
+                    }
+                };
+                Ok(Value::Float(sum / values.len() as f64))
+            }
+            "GrößeVon" => match &args[0] {
+                Value::Speicherbox(members) => Ok(Value::Int(members.borrow().len() as i64)),
+                Value::Objekt(members) => Ok(Value::Int(members.borrow().len() as i64)),
+                other => Err(InterruptKind::Error(
+                    format!(
+                        "`GrößeVon` kann die Größe eines Wertes vom Typ `{}` nicht bestimmen.",
+                        other.as_type()
+                    )
+                    .into(),
+                )),
+            },
+            // strings are measured in Unicode scalar values, not in bytes
+            "Länge" => match &args[0] {
+                Value::String(string) => Ok(Value::Int(string.chars().count() as i64)),
+                Value::List(list) => Ok(Value::Int(list.borrow().len() as i64)),
+                Value::Speicherbox(members) => Ok(Value::Int(members.borrow().len() as i64)),
+                other => Err(InterruptKind::Error(
+                    format!(
+                        "`Länge` kann die Länge eines Wertes vom Typ `{}` nicht bestimmen.",
+                        other.as_type()
+                    )
+                    .into(),
+                )),
+            },
+            "IstLeer" => match &args[0] {
+                Value::String(string) => Ok(Value::Bool(string.is_empty())),
+                Value::List(list) => Ok(Value::Bool(list.borrow().is_empty())),
+                Value::Speicherbox(members) => Ok(Value::Bool(members.borrow().is_empty())),
+                Value::Objekt(members) => Ok(Value::Bool(members.borrow().is_empty())),
+                other => Err(InterruptKind::Error(
+                    format!(
+                        "`IstLeer` kann nicht prüfen, ob ein Wert vom Typ `{}` leer ist.",
+                        other.as_type()
+                    )
+                    .into(),
+                )),
+            },
+            // returns the outermost type as written in annotations, i.e. `Zahl`, `Fließkommazahl`,
+            // `Wahrheitswert`, `Zeichen`, `Zeichenkette`, `Liste`, `Objekt`, `Speicherbox`,
+            // `Funktion`, or `Nichts`, prefixed by `Zeiger auf ` once per indirection
+            "Typ" => {
+                let typ = args[0].as_type();
+                let indirections = typ.ptr_count().unwrap_or(0);
+                let name = match typ {
+                    Type::Int(_) => "Zahl",
+                    Type::Float(_) => "Fließkommazahl",
+                    Type::Bool(_) => "Wahrheitswert",
+                    Type::Char(_) => "Zeichen",
+                    Type::String(_) => "Zeichenkette",
+                    Type::List(..) => "Liste",
+                    Type::Object(..) => "Objekt",
+                    Type::AnyObject(_) => "Speicherbox",
+                    Type::Function { .. } => "Funktion",
+                    Type::Nichts => "Nichts",
+                    _ => analyzer_guaranteed!("values always have one of the above types"),
+                };
+                Ok(Value::String(format!(
+                    "{}{name}",
+                    "Zeiger auf ".repeat(indirections)
+                )))
+            }
+            // both results are never negative and `GgT(0 / 0)` is 0
+            "GgT" => {
+                let (left, right) = (args[0].clone().unwrap_int()?, args[1].clone().unwrap_int()?);
+                let res = ggt(left.unsigned_abs(), right.unsigned_abs());
+                i64::try_from(res).map(Value::Int).map_err(|_| {
+                    InterruptKind::Error(RuntimeError::Overflow {
+                        operation: "GgT",
+                        result: Some(res.into()),
+                    })
+                })
+            }
+            "KgV" => {
+                let (left, right) = (args[0].clone().unwrap_int()?, args[1].clone().unwrap_int()?);
+                let (left, right) = (left.unsigned_abs(), right.unsigned_abs());
+                if left == 0 || right == 0 {
+                    return Ok(Value::Int(0));
+                }
+
+                // both factors are at most 2^63, so their product fits into an `i128`
+                let res = (left / ggt(left, right)) as i128 * right as i128;
+                i64::try_from(res).map(Value::Int).map_err(|_| {
+                    InterruptKind::Error(RuntimeError::Overflow {
+                        operation: "KgV",
+                        result: Some(res),
+                    })
+                })
+            }
+            "IstPrim" => {
+                let num = args[0].clone().unwrap_int()?;
+                Ok(Value::Bool(num > 0 && math::is_prime(num as u64)))
+            }
+            // numbers below 2 have no prime factors
+            "Primfaktoren" => {
+                let num = args[0].clone().unwrap_int()?;
+                let factors = match num {
+                    ..=1 => vec![],
+                    num => math::prime_factors(num as u64)
+                        .into_iter()
+                        .map(|factor| Value::Int(factor as i64))
+                        .collect(),
+                };
+                Ok(Value::List(Rc::new(RefCell::new(factors))))
+            }
+            "Begrenze" => match &args[..] {
+                [Value::Int(value), Value::Int(min), Value::Int(max)] if min <= max => {
+                    Ok(Value::Int(*value.clamp(min, max)))
+                }
+                [Value::Float(value), Value::Float(min), Value::Float(max)] if min <= max => {
+                    Ok(Value::Float(value.clamp(*min, *max)))
+                }
+                [_, min, max] => Err(InterruptKind::Error(
+                    format!("`Begrenze` erwartet ein Minimum, welches nicht größer als das Maximum ist, allerdings ist das Minimum `{min}` und das Maximum `{max}`.").into(),
+                )),
+                _ => analyzer_guaranteed!("the analyzer prevents this"),
+            },
+            "MerkeDir" => {
+                let Value::String(key) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                let key = self.memo_key(key);
+                self.memo.insert(key, args[1].clone());
+                Ok(Value::Unit)
+            }
+            "HatSchon" => {
+                let Value::String(key) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                Ok(Value::Bool(self.memo.contains_key(&self.memo_key(key))))
+            }
+            "HoleGemerkt" => {
+                let Value::String(key) = &args[0] else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                self.memo.get(&self.memo_key(key)).cloned().ok_or_else(|| {
+                    InterruptKind::Error(
+                        format!("Unter dem Schlüssel `{key}` wurde nichts gemerkt.").into(),
+                    )
+                })
+            }
+            // positive offsets move the elements towards the end of the list
+            "Rotiere" => {
+                let mut values = args[0].clone().unwrap_list()?.borrow().clone();
+                let offset = args[1].clone().unwrap_int()?;
+                if !values.is_empty() {
+                    let offset = offset.rem_euclid(values.len() as i64) as usize;
+                    values.rotate_right(offset);
+                }
+                Ok(Value::List(Rc::new(RefCell::new(values))))
+            }
+            "Teilliste" => {
+                let (Value::List(values), Value::Int(start), Value::Int(end)) =
+                    (&args[0], &args[1], &args[2])
+                else {
+                    analyzer_guaranteed!("the analyzer prevents this");
+                };
+                if *start < 0 {
+                    return Err(InterruptKind::Error(RuntimeError::NegativeIndex(*start)));
+                }
+                if start > end {
+                    return Err(InterruptKind::Error(
+                        format!("Illegale Indizierung: Der Anfang `{start}` liegt hinter dem Ende `{end}`").into(),
+                    ));
+                }
+                let values = values.borrow();
+                match values.get(*start as usize..*end as usize) {
+                    // the slice is copied, so the new list does not alias the original one
+                    Some(slice) => Ok(Value::List(Rc::new(RefCell::new(slice.to_vec())))),
+                    None => Err(InterruptKind::Error(RuntimeError::IndexOutOfBounds {
+                        index: *end,
+                        len: values.len(),
+                        target: IndexTarget::List,
+                    })),
+                }
+            }
+            "AbstandZwischen" => match &args[..] {
+                [Value::Int(left), Value::Int(right)] => {
+                    let distance = left.abs_diff(*right);
+                    i64::try_from(distance).map(Value::Int).map_err(|_| {
+                        InterruptKind::Error(RuntimeError::Overflow {
+                            operation: "AbstandZwischen",
+                            result: Some(distance.into()),
+                        })
+                    })
+                }
+                [Value::Float(left), Value::Float(right)] => Ok(Value::Float((left - right).abs())),
+                _ => analyzer_guaranteed!("the analyzer prevents this"),
+            },
+            "Betrag" => match &args[..] {
+                [Value::Int(value)] => value.checked_abs().map(Value::Int).ok_or_else(|| {
+                    InterruptKind::Error(RuntimeError::Overflow {
+                        operation: "Betrag",
+                        result: Some(value.unsigned_abs().into()),
+                    })
+                }),
+                [Value::Float(value)] => Ok(Value::Float(value.abs())),
+                _ => analyzer_guaranteed!("the analyzer prevents this"),
+            },
+            "Wurzel" => {
+                let value = args[0].clone().unwrap_float()?;
+                if value < 0.0 {
+                    return Err(InterruptKind::Error(
+                        format!("`Wurzel` kann nicht aus der negativen Zahl `{value}` gezogen werden.").into(),
+                    ));
+                }
+                Ok(Value::Float(value.sqrt()))
+            }
+            "Sinus" => Ok(Value::Float(args[0].clone().unwrap_float()?.sin())),
+            "Kosinus" => Ok(Value::Float(args[0].clone().unwrap_float()?.cos())),
+            // halfway cases are rounded away from zero
+            "Runde" => Ok(Value::Float(args[0].clone().unwrap_float()?.round())),
+            "Abrunden" => Ok(Value::Float(args[0].clone().unwrap_float()?.floor())),
+            "Aufrunden" => Ok(Value::Float(args[0].clone().unwrap_float()?.ceil())),
+            // these behave exactly like the `**` and `%` operators
+            "Potenz" => Ok(args[0].clone().pow(args[1].clone())?),
+            "Modulo" => Ok((args[0].clone() % args[1].clone())?),
+            // the lower bound is inclusive, the upper bound is exclusive
+            "Zufall" => {
+                let (low, high) = (args[0].clone().unwrap_int()?, args[1].clone().unwrap_int()?);
+                if low >= high {
+                    return Err(InterruptKind::Error(
+                        format!("`Zufall` erwartet eine untere Grenze, welche kleiner als die obere Grenze ist, allerdings ist die untere Grenze `{low}` und die obere Grenze `{high}`.").into(),
+                    ));
+                }
+                let offset = self.random.below(high.abs_diff(low));
+                Ok(Value::Int(low.wrapping_add(offset as i64)))
+            }
+            name @ ("MaximumNach" | "MinimumNach") => {
+                let wanted = match name {
+                    "MaximumNach" => Ordering::Greater,
+                    _ => Ordering::Less,
+                };
+                let values = args[0].clone().unwrap_list()?.borrow().clone();
+
+                // only a strictly better key replaces the current result, so ties keep the first
+                let mut best: Option<(Value, Value)> = None;
+                for value in values {
+                    let key = self.call_value(&args[1], vec![value.clone()])?;
+                    let better = match &best {
+                        Some((best_key, _)) => key.compare(best_key)? == Some(wanted),
+                        None => true,
+                    };
+                    if better {
+                        best = Some((key, value));
+                    }
+                }
+
+                best.map(|(_, value)| value).ok_or_else(|| {
+                    InterruptKind::Error(
+                        format!("`{name}` kann nicht auf eine leere Liste angewendet werden.")
+                            .into(),
+                    )
+                })
+            }
+            // like `MaximumNach` and `MinimumNach`, ties keep the first element
+            name @ ("Maximum" | "Minimum") => {
+                let wanted = match name {
+                    "Maximum" => Ordering::Greater,
+                    _ => Ordering::Less,
+                };
+                let values = args[0].clone().unwrap_list()?;
+                let values = values.borrow();
+                if values
+                    .iter()
+                    .any(|value| matches!(value, Value::Float(num) if num.is_nan()))
+                {
+                    return Err(InterruptKind::Error(
+                        format!("`{name}` kann nicht auf eine Liste angewendet werden, welche `NaN` enthält.").into(),
+                    ));
+                }
 
-                match base {
-                    Value::BuiltinFunction(base, func) => Ok(func(&base, args)),
-                    _ => unreachable!("analyzer prevents this"),
+                let mut best: Option<&Value> = None;
+                for value in values.iter() {
+                    let better = match best {
+                        Some(best) => value.compare(best)? == Some(wanted),
+                        None => true,
+                    };
+                    if better {
+                        best = Some(value);
+                    }
                 }
+
+                best.cloned().ok_or_else(|| {
+                    InterruptKind::Error(
+                        format!("`{name}` kann nicht auf eine leere Liste angewendet werden.")
+                            .into(),
+                    )
+                })
+            }
+            "AlsListe" => {
+                let values = match &args[0] {
+                    Value::String(string) => string
+                        .chars()
+                        .map(|char| Value::String(char.to_string()))
+                        .collect(),
+                    Value::List(list) => return Ok(Value::List(Rc::clone(list))),
+                    Value::Speicherbox(members) => entries(&members.borrow()),
+                    Value::Objekt(members) => entries(&members.borrow()),
+                    other => {
+                        return Err(InterruptKind::Error(
+                            format!(
+                                "`AlsListe` kann keinen Wert vom Typ `{}` in eine Liste umwandeln.",
+                                other.as_type()
+                            )
+                            .into(),
+                        ))
+                    }
+                };
+                Ok(Value::List(Rc::new(RefCell::new(values))))
             }
+            _ => analyzer_guaranteed!("the analyzer prevents calls of unknown functions: {name}"),
+        }
+    }
+
+    /// Generates a random Matrikelnummer which is passed to `Einschreibung`, unless one was
+    /// specified using [`InterpreterBuilder::with_matrikelnummer`].
+    fn matrikelnummer(&mut self) -> i64 {
+        match self.matrikelnummer {
+            Some(matrikelnummer) => matrikelnummer as i64,
+            None => (self.random.next_u64() >> 32) as i64,
+        }
+    }
+
+    /// Resolves a called function by its name, used if the analyzer did not resolve the call.
+    /// Returns `None` if a builtin is called.
+    fn lookup_function(&self, name: &'src str) -> Option<Rc<AnalyzedFunctionDefinition<'src>>> {
+        match self.used_builtins.contains(name) {
+            true => None,
+            false => self.functions.get(name).cloned(),
+        }
+    }
+
+    /// Functions only see the results they remembered themselves, so that unrelated functions
+    /// using the same keys do not interfere.
+    fn memo_key(&self, key: &str) -> (&'src str, String) {
+        let function = self.call_stack.last().copied().unwrap_or_default();
+        (function, key.to_string())
+    }
+
+    /// Raises an error if another call would nest more function calls than allowed.
+    fn check_call_depth(&self) -> Result<(), Error> {
+        if self.call_stack.len() >= self.max_call_depth {
+            return Err(format!(
+                "Maximale Rekursionstiefe überschritten: Es dürfen höchstens {} Funktionsaufrufe verschachtelt werden.",
+                self.max_call_depth
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    fn call_user_func(
+        &mut self,
+        func: Rc<AnalyzedFunctionDefinition<'src>>,
+        args: Vec<Value>,
+    ) -> ExprResult {
+        self.check_call_depth()?;
+        self.call_stack.push(func.name);
+        let mut args = args;
+        let res = loop {
+            self.statistics.count_call();
+
+            let mut scope = HashMap::new();
+            for (param, arg) in func.params.iter().zip(args) {
+                scope.insert(param.name, arg.wrapped());
+            }
+
+            match self.scoped(scope, |self_| self_.visit_block(&func.block, false, true)) {
+                Ok(val) => break Ok(val),
+                Err(InterruptKind::TailCall(next_args)) => args = next_args,
+                Err(interrupt) => break interrupt.into_value(),
+            }
+        };
+        // only the innermost function knows the complete call stack
+        if matches!(res, Err(InterruptKind::Error(_))) && self.error_trace.is_none() {
+            self.error_trace = Some(self.call_stack.clone());
+        }
+        self.call_stack.pop();
+        res
+    }
+
+    /// Calls a function value, such as a builtin member function or a user-defined function.
+    fn call_value(&mut self, func: &Value, args: Vec<Value>) -> ExprResult {
+        match func {
+            Value::BuiltinFunction(base, func) => func(base, args),
+            Value::Function(name, _) => {
+                let func = Rc::clone(&self.functions[name.as_str()]);
+                self.call_user_func(func, args)
+            }
+            _ => analyzer_guaranteed!("analyzer prevents this"),
         }
     }
 
     //////////////////////////////////
 
-    fn visit_block(&mut self, node: &AnalyzedBlock<'src>, new_scope: bool) -> ExprResult {
+    /// Executes a block, `tail` specifies whether its result is the result of the current function.
+    fn visit_block(
+        &mut self,
+        node: &AnalyzedBlock<'src>,
+        new_scope: bool,
+        tail: bool,
+    ) -> ExprResult {
         let callback = |self_: &mut Self| {
-            for stmt in &node.stmts {
-                self_.visit_statement(stmt)?;
+            for (idx, stmt) in node.stmts.iter().enumerate() {
+                if let Err(err) = self_.visit_statement(stmt) {
+                    return Err(self_.locate_stmt(node, idx, err));
+                }
+            }
+            match (&node.expr, tail) {
+                (Some(expr), true) => self_.visit_tail_expression(expr),
+                (Some(expr), false) => self_.visit_expression(expr),
+                (None, _) => Ok(Value::Unit),
             }
-            node.expr
-                .as_ref()
-                .map_or(Ok(Value::Unit), |expr| self_.visit_expression(expr))
         };
 
         match new_scope {
@@ -381,14 +1779,55 @@ where
         }
     }
 
+    /// Remembers that the current runtime error was raised at `span`, unless the error already
+    /// has a more precise location.
+    fn locate<T>(
+        &mut self,
+        span: Span<'src>,
+        res: Result<T, InterruptKind>,
+    ) -> Result<T, InterruptKind> {
+        if matches!(res, Err(InterruptKind::Error(_))) && self.error_span.is_none() {
+            self.error_span = Some(span);
+        }
+        res
+    }
+
+    /// Like [`Interpreter::locate`], but uses the location of the statement at `idx` of `block`.
+    #[inline(never)]
+    fn locate_stmt(
+        &mut self,
+        block: &AnalyzedBlock<'src>,
+        idx: usize,
+        interrupt: InterruptKind,
+    ) -> InterruptKind {
+        if let Some(span) = block.stmt_spans.get(idx) {
+            if matches!(interrupt, InterruptKind::Error(_)) && self.error_span.is_none() {
+                self.error_span = Some(*span);
+            }
+        }
+        interrupt
+    }
+
+    /// Counts a statement or loop iteration against the step limit.
+    fn count_step(&mut self) -> StmtResult {
+        self.steps += 1;
+        match self.step_limit {
+            Some(limit) if self.steps > limit => Err(InterruptKind::Error(
+                format!("Ausführungsbudget erschöpft: Es dürfen höchstens {limit} Schritte ausgeführt werden.").into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     fn visit_statement(&mut self, node: &AnalyzedStatement<'src>) -> StmtResult {
+        self.count_step()?;
         match node {
             AnalyzedStatement::Beantrage(_) => Ok(()),
             AnalyzedStatement::Let(node) => self.visit_let_stmt(node),
             AnalyzedStatement::Aendere(node) => self.visit_aendere_stmt(node),
             AnalyzedStatement::Return(expr) => Err(InterruptKind::Return(
                 expr.as_ref()
-                    .map_or(Ok(Value::Unit), |expr| self.visit_expression(expr))?,
+                    .map_or(Ok(Value::Unit), |expr| self.visit_tail_expression(expr))?,
             )),
             AnalyzedStatement::While(node) => self.visit_while_stmt(node),
             AnalyzedStatement::Break => Err(InterruptKind::Break),
@@ -408,9 +1847,9 @@ where
 
     fn visit_aendere_stmt(&mut self, node: &AnalyzedAendereStmt<'src>) -> StmtResult {
         let rhs = self.visit_expression(&node.expr)?;
-        let mut var = self.get_var(node.assignee);
+        let mut var = self.get_var(node.assignee)?;
         for _ in 0..node.assignee_ptr_count {
-            let new_ptr = var.borrow().clone().unwrap_ptr();
+            let new_ptr = var.borrow().clone().unwrap_ptr()?;
             var = new_ptr;
         }
 
@@ -420,12 +1859,16 @@ where
     }
 
     fn visit_while_stmt(&mut self, node: &AnalyzedWhileStmt<'src>) -> StmtResult {
-        while self.visit_expression(&node.cond)?.unwrap_bool() {
+        while self.visit_expression(&node.cond)?.unwrap_bool()? {
+            self.count_step()?;
+
             // artificially slow down any loops so that
             // the service is not overloaded easily
-            thread::sleep(Duration::from_millis(50));
+            if !self.cooperative {
+                thread::sleep(Duration::from_millis(50));
+            }
 
-            match self.visit_block(&node.block, true) {
+            match self.visit_block(&node.block, true, false) {
                 Err(InterruptKind::Break) => break,
                 Err(InterruptKind::Continue) => continue,
                 res => res?,
@@ -439,15 +1882,19 @@ where
     fn visit_expression(&mut self, node: &AnalyzedExpression<'src>) -> ExprResult {
         match node {
             AnalyzedExpression::Nichts => Ok(Value::Unit),
-            AnalyzedExpression::Block(block) => self.visit_block(block, true),
-            AnalyzedExpression::If(node) => self.visit_if_expr(node),
+            AnalyzedExpression::Block(block) => self.visit_block(block, true, false),
+            AnalyzedExpression::If(node) => self.visit_if_expr(node, false),
             AnalyzedExpression::Int(num) => Ok(num.into()),
             AnalyzedExpression::Float(num) => Ok(num.into()),
             AnalyzedExpression::Bool(bool) => Ok(bool.into()),
             AnalyzedExpression::Char(num) => Ok(num.into()),
             AnalyzedExpression::String(str) => Ok(Value::String((*str).to_string())),
             AnalyzedExpression::List(inner) => self.visit_list_expr(&inner.values),
-            AnalyzedExpression::Ident(node) => Ok(self.get_var(node.ident).borrow().clone()),
+            AnalyzedExpression::Ident(AnalyzedIdentExpr {
+                result_type: result_type @ Type::Function { .. },
+                ident,
+            }) => Ok(Value::Function(ident.to_string(), result_type.clone())),
+            AnalyzedExpression::Ident(node) => Ok(self.get_var(node.ident)?.borrow().clone()),
             AnalyzedExpression::Prefix(node) => self.visit_prefix_expr(node),
             AnalyzedExpression::Infix(node) => self.visit_infix_expr(node),
             AnalyzedExpression::Assign(node) => self.visit_assign_expr(node),
@@ -468,46 +1915,68 @@ where
                 let expr = self.visit_expression(&element.value)?;
                 Ok((element.key.clone(), expr))
             })
-            .collect::<Result<HashMap<String, Value>, InterruptKind>>()?;
+            .collect::<Result<IndexMap<String, Value>, InterruptKind>>()?;
         Ok(Value::Objekt(Rc::new(RefCell::new(members))))
     }
 
-    fn visit_if_expr(&mut self, node: &AnalyzedIfExpr<'src>) -> ExprResult {
-        if self.visit_expression(&node.cond)?.unwrap_bool() {
-            self.visit_block(&node.then_block, true)
+    fn visit_if_expr(&mut self, node: &AnalyzedIfExpr<'src>, tail: bool) -> ExprResult {
+        if self.visit_expression(&node.cond)?.unwrap_bool()? {
+            self.visit_block(&node.then_block, true, tail)
         } else if let Some(else_block) = &node.else_block {
-            self.visit_block(else_block, true)
+            self.visit_block(else_block, true, tail)
         } else {
             Ok(Value::Unit)
         }
     }
 
+    /// Like [`Interpreter::visit_expression`], but for expressions whose value is the result of
+    /// the current function.
+    /// Direct calls of the current function raise [`InterruptKind::TailCall`] instead of nesting
+    /// another call.
+    fn visit_tail_expression(&mut self, node: &AnalyzedExpression<'src>) -> ExprResult {
+        match node {
+            AnalyzedExpression::Block(block) => self.visit_block(block, true, true),
+            AnalyzedExpression::If(node) => self.visit_if_expr(node, true),
+            AnalyzedExpression::Grouped(inner) => self.visit_tail_expression(inner),
+            AnalyzedExpression::Call(node) => match node.func {
+                AnalyzedCallBase::Ident(name)
+                    if node.function_index.is_some() && self.call_stack.last() == Some(&name) =>
+                {
+                    let args = self.visit_list_expr_helper(&node.args)?;
+                    Err(InterruptKind::TailCall(args))
+                }
+                _ => self.visit_call_expr(node),
+            },
+            node => self.visit_expression(node),
+        }
+    }
+
     fn visit_prefix_expr(&mut self, node: &AnalyzedPrefixExpr<'src>) -> ExprResult {
         let val = self.visit_expression(&node.expr)?;
         match node.op {
-            PrefixOp::Not => Ok(!val),
-            PrefixOp::Neg => Ok(-val),
+            PrefixOp::Not => Ok((!val)?),
+            PrefixOp::Neg => Ok((-val)?),
             PrefixOp::Ref => match &node.expr {
                 AnalyzedExpression::Ident(ident_expr) => {
-                    Ok(Value::Ptr(self.get_var(ident_expr.ident)))
+                    Ok(Value::Ptr(self.get_var(ident_expr.ident)?))
                 }
-                _ => unreachable!("the analyzer only allows referencing identifiers"),
+                _ => analyzer_guaranteed!("the analyzer only allows referencing identifiers"),
             },
-            PrefixOp::Deref => Ok(val.unwrap_ptr().borrow().clone()),
+            PrefixOp::Deref => Ok(val.unwrap_ptr()?.borrow().clone()),
         }
     }
 
     fn visit_infix_expr(&mut self, node: &AnalyzedInfixExpr<'src>) -> ExprResult {
         match node.op {
             InfixOp::And => {
-                return if !self.visit_expression(&node.lhs)?.unwrap_bool() {
+                return if !self.visit_expression(&node.lhs)?.unwrap_bool()? {
                     Ok(false.into())
                 } else {
                     self.visit_expression(&node.rhs)
                 };
             }
             InfixOp::Or => {
-                return if self.visit_expression(&node.lhs)?.unwrap_bool() {
+                return if self.visit_expression(&node.lhs)?.unwrap_bool()? {
                     Ok(true.into())
                 } else {
                     self.visit_expression(&node.rhs)
@@ -518,49 +1987,50 @@ where
 
         let lhs = self.visit_expression(&node.lhs)?;
         let rhs = self.visit_expression(&node.rhs)?;
-        match node.op {
-            InfixOp::Plus => Ok(lhs + rhs),
-            InfixOp::Minus => Ok(lhs - rhs),
-            InfixOp::Mul => Ok(lhs * rhs),
-            InfixOp::Div => Ok((lhs / rhs)?),
-            InfixOp::Rem => Ok((lhs % rhs)?),
-            InfixOp::Pow => Ok(lhs.pow(rhs)),
+        let res = match node.op {
+            InfixOp::Plus => (lhs + rhs).map_err(InterruptKind::from),
+            InfixOp::Minus => (lhs - rhs).map_err(InterruptKind::from),
+            InfixOp::Mul => (lhs * rhs).map_err(InterruptKind::from),
+            InfixOp::Div => (lhs / rhs).map_err(InterruptKind::from),
+            InfixOp::Rem => (lhs % rhs).map_err(InterruptKind::from),
+            InfixOp::Pow => lhs.pow(rhs).map_err(InterruptKind::from),
             InfixOp::Eq => Ok((lhs == rhs).into()),
             InfixOp::Neq => Ok((lhs != rhs).into()),
-            InfixOp::Lt => Ok((lhs < rhs).into()),
-            InfixOp::Gt => Ok((lhs > rhs).into()),
-            InfixOp::Lte => Ok((lhs <= rhs).into()),
-            InfixOp::Gte => Ok((lhs >= rhs).into()),
-            InfixOp::Shl => Ok((lhs << rhs)?),
-            InfixOp::Shr => Ok((lhs >> rhs)?),
-            InfixOp::BitOr => Ok(lhs | rhs),
-            InfixOp::BitAnd => Ok(lhs & rhs),
-            InfixOp::BitXor => Ok(lhs ^ rhs),
+            InfixOp::Lt => compare(&lhs, &rhs, |ord| ord.is_lt()),
+            InfixOp::Gt => compare(&lhs, &rhs, |ord| ord.is_gt()),
+            InfixOp::Lte => compare(&lhs, &rhs, |ord| ord.is_le()),
+            InfixOp::Gte => compare(&lhs, &rhs, |ord| ord.is_ge()),
+            InfixOp::Shl => (lhs << rhs).map_err(InterruptKind::from),
+            InfixOp::Shr => (lhs >> rhs).map_err(InterruptKind::from),
+            InfixOp::BitOr => (lhs | rhs).map_err(InterruptKind::from),
+            InfixOp::BitAnd => (lhs & rhs).map_err(InterruptKind::from),
+            InfixOp::BitXor => (lhs ^ rhs).map_err(InterruptKind::from),
             InfixOp::And | InfixOp::Or => unreachable!("logical `and` and `or` are matched above"),
-        }
+        };
+        self.locate(node.span, res)
     }
 
     fn visit_assign_expr(&mut self, node: &AnalyzedAssignExpr<'src>) -> ExprResult {
         let rhs = self.visit_expression(&node.expr)?;
-        let mut var = self.get_var(node.assignee);
+        let mut var = self.get_var(node.assignee)?;
         for _ in 0..node.assignee_ptr_count {
-            let new_ptr = var.borrow().clone().unwrap_ptr();
+            let new_ptr = var.borrow().clone().unwrap_ptr()?;
             var = new_ptr;
         }
 
         let new_val = match node.op {
-            AssignOp::Basic => unreachable!("this operator is never used"),
-            AssignOp::Plus => var.borrow().clone() + rhs,
-            AssignOp::Minus => var.borrow().clone() - rhs,
-            AssignOp::Mul => var.borrow().clone() * rhs,
+            AssignOp::Basic => analyzer_guaranteed!("this operator is never used"),
+            AssignOp::Plus => (var.borrow().clone() + rhs)?,
+            AssignOp::Minus => (var.borrow().clone() - rhs)?,
+            AssignOp::Mul => (var.borrow().clone() * rhs)?,
             AssignOp::Div => (var.borrow().clone() / rhs)?,
             AssignOp::Rem => (var.borrow().clone() % rhs)?,
-            AssignOp::Pow => var.borrow().clone().pow(rhs),
+            AssignOp::Pow => var.borrow().clone().pow(rhs)?,
             AssignOp::Shl => (var.borrow().clone() << rhs)?,
             AssignOp::Shr => (var.borrow().clone() >> rhs)?,
-            AssignOp::BitOr => var.borrow().clone() | rhs,
-            AssignOp::BitAnd => var.borrow().clone() & rhs,
-            AssignOp::BitXor => var.borrow().clone() ^ rhs,
+            AssignOp::BitOr => (var.borrow().clone() | rhs)?,
+            AssignOp::BitAnd => (var.borrow().clone() & rhs)?,
+            AssignOp::BitXor => (var.borrow().clone() ^ rhs)?,
         };
         *var.borrow_mut() = new_val;
 
@@ -573,7 +2043,10 @@ where
             .iter()
             .map(|expr| self.visit_expression(expr))
             .collect::<Result<_, _>>()?;
-        self.call_func(&node.func, args)
+        match node.function_index {
+            Some(idx) => self.call_user_func(Rc::clone(&self.function_table[idx]), args),
+            None => self.call_func(&node.func, args, &node.result_type),
+        }
     }
 
     fn cast_from_any(&self, from: Value, as_type: Type) -> ExprResult {
@@ -587,6 +2060,11 @@ where
 
     fn visit_cast_expr(&mut self, node: &AnalyzedCastExpr<'src>) -> ExprResult {
         let val = self.visit_expression(&node.expr)?;
+        let res = self.cast(val, node);
+        self.locate(node.span, res)
+    }
+
+    fn cast(&mut self, val: Value, node: &AnalyzedCastExpr<'src>) -> ExprResult {
         match (val, node.as_type.clone()) {
             (val @ Value::Int(_), Type::Int(0))
             | (val @ Value::Float(_), Type::Float(0))
@@ -594,10 +2072,15 @@ where
             | (val @ Value::Bool(_), Type::Bool(0)) => Ok(val),
             (Value::Int(int), Type::Float(0)) => Ok((int as f64).into()),
             (Value::Int(int), Type::Bool(0)) => Ok((int != 0).into()),
-            (Value::Int(int), Type::Char(0)) => Ok((int.clamp(0, 127) as u8).into()),
+            // a `Zeichen` is an ASCII character, other values are not silently clamped
+            (Value::Int(int @ 0..=127), Type::Char(0)) => Ok((int as u8).into()),
+            (Value::Int(int), Type::Char(0)) => Err(char_cast_error(Value::Int(int))),
             (Value::Float(float), Type::Int(0)) => Ok((float as i64).into()),
             (Value::Float(float), Type::Bool(0)) => Ok((float != 0.0).into()),
-            (Value::Float(float), Type::Char(0)) => Ok((float.clamp(0.0, 127.0) as u8).into()),
+            (Value::Float(float), Type::Char(0)) if (0.0..128.0).contains(&float) => {
+                Ok((float as u8).into())
+            }
+            (Value::Float(float), Type::Char(0)) => Err(char_cast_error(Value::Float(float))),
             (Value::Bool(bool), Type::Int(0)) => Ok((bool as i64).into()),
             (Value::Bool(bool), Type::Float(0)) => Ok((bool as u8 as f64).into()),
             (Value::Bool(bool), Type::Char(0)) => Ok((bool as u8).into()),
@@ -605,7 +2088,7 @@ where
             (Value::Char(char), Type::Float(0)) => Ok((char as f64).into()),
             (Value::Char(char), Type::Bool(0)) => Ok((char != 0).into()),
             (Value::String(inner), type_ @ Type::Int(0) | type_ @ Type::Float(0)) => {
-                let inner = inner.replace(',', ".");
+                let inner = normalize_number(&inner);
                 match type_ {
                     Type::Int(0) => {
                         let num: i64 = inner.parse().map_err(|err| {
@@ -625,35 +2108,155 @@ where
 
                         Ok(Value::Float(num))
                     }
-                    _ => unreachable!("the analyzer guarantees this"),
+                    _ => analyzer_guaranteed!("the analyzer guarantees this"),
                 }
             }
             (val, to_type) if node.expr.result_type() == Type::Any => {
                 self.cast_from_any(val, to_type)
             }
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 
     fn visit_member_expr(&mut self, node: &AnalyzedMemberExpr<'src>) -> ExprResult {
         let base = self.visit_expression(&node.expr)?;
 
-        Ok(base.member(node.member))
+        base.member(node.member)
     }
 
     fn visit_index_expr(&mut self, node: &AnalyzedIndexExpr<'src>) -> ExprResult {
         let base = self.visit_expression(&node.expr)?;
         let index = self.visit_expression(&node.index)?;
-        match (base, index) {
+        let res = match (base, index) {
+            (Value::List(_), Value::Int(idx)) if idx < 0 => {
+                Err(InterruptKind::Error(RuntimeError::NegativeIndex(idx)))
+            }
             (Value::List(values), Value::Int(idx)) => {
-                if idx < 0 {
-                    return Err(InterruptKind::Error(
-                        format!("Illegale Indizierung mittels Index: `{idx}`").into(),
-                    ));
+                let values = values.borrow();
+                match values.get(idx as usize) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(InterruptKind::Error(RuntimeError::IndexOutOfBounds {
+                        index: idx,
+                        len: values.len(),
+                        target: IndexTarget::List,
+                    })),
                 }
-                Ok(values.borrow()[idx as usize].clone())
             }
-            _ => unreachable!("the analyzer prevents this"),
+            _ => analyzer_guaranteed!("the analyzer prevents this"),
+        };
+        self.locate(node.span, res)
+    }
+}
+
+/// The error of casting a number outside of `0..=127` to `Zeichen`.
+fn char_cast_error(value: Value) -> InterruptKind {
+    InterruptKind::Error(
+        format!("Typumwandlungsfehler: `{value}` liegt außerhalb des ASCII-Bereichs von `0` bis `127` und kann nicht in ein `Zeichen` umgewandelt werden.").into(),
+    )
+}
+
+/// Prepares a string for being parsed as a number, HPI uses a comma as the decimal separator.
+/// Surrounding whitespace is ignored, e.g. for input which was read using `Lies_Zeile`.
+fn normalize_number(input: &str) -> String {
+    input.trim().replace(',', ".")
+}
+
+/// Converts the members of a Speicherbox or object into `Eintrag` objects, sorted by key.
+fn entries(members: &IndexMap<String, Value>) -> Vec<Value> {
+    let mut keys: Vec<&String> = members.keys().collect();
+    keys.sort();
+    keys.into_iter()
+        .map(|key| {
+            let entry = IndexMap::from([
+                ("Schlüssel".to_string(), Value::String(key.clone())),
+                ("Wert".to_string(), members[key].clone()),
+            ]);
+            Value::Objekt(Rc::new(RefCell::new(entry)))
+        })
+        .collect()
+}
+
+fn compile_regex(builtin: &str, pattern: &str) -> Result<Regex, InterruptKind> {
+    Regex::new(pattern).map_err(|err| regex_error(builtin, pattern, err))
+}
+
+fn regex_error(builtin: &str, pattern: &str, err: String) -> InterruptKind {
+    InterruptKind::Error(format!("{builtin} Fehler im Ausdruck `{pattern}`: {err}.").into())
+}
+
+/// Aligns the cells of each column, rows may contain a different number of cells.
+fn table(rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = vec![];
+    for row in rows {
+        for (column, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(column) {
+                Some(max) => *max = (*max).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<String>>()
+                .join(" | ");
+            line.trim_end().to_string() + "\n"
+        })
+        .collect()
+}
+
+/// Reverses a string by Unicode scalar values.
+/// Combining diacritical marks (e.g. in a decomposed `ü`) are kept behind their base character.
+fn reverse(input: &str) -> String {
+    let mut clusters: Vec<String> = vec![];
+    for char in input.chars() {
+        match clusters.last_mut() {
+            Some(cluster) if ('\u{300}'..='\u{36f}').contains(&char) => cluster.push(char),
+            _ => clusters.push(char.to_string()),
+        }
+    }
+    clusters.into_iter().rev().collect()
+}
+
+/// Lists the frames of a call stack, the innermost call last.
+/// Consecutive recursive calls of the same function are combined into a single line.
+fn traceback(trace: &[&str]) -> String {
+    let mut lines = vec!["Aufrufstapel (innerster Aufruf zuletzt):".to_string()];
+    for frames in trace.chunk_by(|left, right| left == right) {
+        match frames.len() {
+            1 => lines.push(format!("  {}", frames[0])),
+            count => lines.push(format!("  {} ({count} mal)", frames[0])),
+        }
+    }
+    lines.join("\n")
+}
+
+/// Compares two values using `<`, `>`, `<=` or `>=`, `NaN` is neither smaller nor larger.
+fn compare(lhs: &Value, rhs: &Value, accept: impl Fn(Ordering) -> bool) -> ExprResult {
+    Ok(lhs.compare(rhs)?.is_some_and(accept).into())
+}
+
+/// Joins the displayed values using spaces, as done by `Drucke`.
+fn display_args(args: &[Value], options: DisplayOptions, separator: &str) -> String {
+    args.iter()
+        .map(|val| val.display(options))
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// Validates the result of the `Bewerbung` function.
+fn check_bewerbung(value: Value) -> Result<(), Error> {
+    if let Value::String(inner) = value {
+        if inner.is_empty() {
+            return Err("Ihre Bewerbung hat das HPI leider nicht überzeugt.\n Ist Ihr Bewerbungsschreiben vielleicht leer?".into());
         }
+    } else {
+        analyzer_guaranteed!("the analyzer prevents this")
     }
+    Ok(())
 }
@@ -0,0 +1,239 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufWriter, Write},
+    rc::Rc,
+    time::Duration,
+};
+
+use super::{
+    json, Channels, Determinismus, DisplayOptions, DruckeCallback, DruckeFormat, ErrorOutput,
+    FloatNotation, HPIHttpClient, Input, Interpreter, PointerNotation, Random, Statistics, Value,
+    DEFAULT_HTTP_TIMEOUT, DEFAULT_MAX_CALL_DEPTH,
+};
+
+/// Configures an [`Interpreter`] using chainable setters.
+///
+/// Only the output and the HTTP client are required, every other option keeps its default unless
+/// it is changed before calling [`InterpreterBuilder::build`].
+#[derive(Debug)]
+pub struct InterpreterBuilder<'src, Output, HttpClient>
+where
+    Output: Write,
+    HttpClient: HPIHttpClient,
+{
+    interpreter: Interpreter<'src, Output, HttpClient>,
+}
+
+impl<'src, Output, HttpClient> InterpreterBuilder<'src, Output, HttpClient>
+where
+    Output: Write,
+    HttpClient: HPIHttpClient,
+{
+    pub fn new(output: Output, http_client: HttpClient) -> Self {
+        Self {
+            interpreter: Interpreter {
+                http_client,
+                http_timeout: DEFAULT_HTTP_TIMEOUT,
+                output: BufWriter::new(output),
+                scopes: vec![],
+                functions: HashMap::new(),
+                used_builtins: HashSet::new(),
+                function_table: vec![],
+                environment_variables: HashMap::new(),
+                cooperative: false,
+                channels: Channels::default(),
+                strict_channels: false,
+                error_output: ErrorOutput::default(),
+                display_options: DisplayOptions::default(),
+                random: Random::Os,
+                matrikelnummer: None,
+                fixed_time: None,
+                statistics: Rc::default(),
+                memo: HashMap::new(),
+                verbosity: 0,
+                json_max_depth: json::DEFAULT_MAX_DEPTH,
+                drucke_callback: DruckeCallback::default(),
+                drucke_format: DruckeFormat::default(),
+                call_stack: vec![],
+                max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+                error_trace: None,
+                error_span: None,
+                step_limit: None,
+                steps: 0,
+                input: Input::default(),
+            },
+        }
+    }
+
+    /// Replaces all environment variables, e.g. with the ones of the host.
+    pub fn with_environment_variables(
+        mut self,
+        environment_variables: HashMap<String, String>,
+    ) -> Self {
+        self.interpreter.environment_variables = environment_variables;
+        self
+    }
+
+    /// Adds a single environment variable, replacing a previous one with the same name.
+    pub fn with_environment_variable(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.interpreter
+            .environment_variables
+            .insert(name.into(), value.into());
+        self
+    }
+
+    /// Specifies the size in bytes of the buffer in front of the output.
+    /// The output is written once the buffer is full, when the program terminates, whenever
+    /// `Lies_Zeile` reads a line or `Schlummere` sleeps, before `DruckeKanal` and `Fehlerdrucke`
+    /// write to other writers, and when the program calls `Leere_Ausgabe`.
+    /// Programs which display output while running for a long time should therefore call
+    /// `Leere_Ausgabe`.
+    /// Defaults to 8 KiB, a capacity of `0` disables the buffering.
+    pub fn with_output_buffer_capacity(mut self, capacity: usize) -> Self {
+        // nothing has been written yet, so the buffer is empty
+        let (output, _) = self.interpreter.output.into_parts();
+        self.interpreter.output = BufWriter::with_capacity(capacity, output);
+        self
+    }
+
+    /// Registers a writer which receives the output of `DruckeKanal` for the given channel.
+    pub fn with_channel(mut self, channel: impl Into<String>, writer: impl Write + 'src) -> Self {
+        self.interpreter
+            .channels
+            .0
+            .insert(channel.into(), Box::new(writer));
+        self
+    }
+
+    /// Specifies how `DruckeKanal` treats channels without a registered writer.
+    /// By default, their output is written to the default output.
+    /// If `strict` is set, a runtime error is raised instead.
+    pub fn with_strict_channels(mut self, strict: bool) -> Self {
+        self.interpreter.strict_channels = strict;
+        self
+    }
+
+    /// Registers a writer which receives the output of `Fehlerdrucke`, e.g. `io::stderr()`.
+    /// By default, it is written to the default output.
+    pub fn with_error_output(mut self, writer: impl Write + 'src) -> Self {
+        self.interpreter.error_output.0 = Some(Box::new(writer));
+        self
+    }
+
+    /// Specifies how floats are rendered by `Drucke`, `Schreibe`, `Fehlerdrucke`,
+    /// `DruckeKanal`, and `Formatiere`.
+    /// Defaults to [`FloatNotation::Decimal`].
+    pub fn with_float_notation(mut self, notation: FloatNotation) -> Self {
+        self.interpreter.display_options.float_notation = notation;
+        self
+    }
+
+    /// Specifies how pointers are rendered by `Drucke`, `Schreibe`, `Fehlerdrucke`,
+    /// `DruckeKanal`, and `Formatiere`.
+    /// Defaults to [`PointerNotation::Pointee`].
+    pub fn with_pointer_notation(mut self, notation: PointerNotation) -> Self {
+        self.interpreter.display_options.pointer_notation = notation;
+        self
+    }
+
+    /// Specifies the string which `Drucke` and `Schreibe` write between their arguments, e.g.
+    /// `","`.
+    /// Defaults to a single space, which `DruckeKanal`, `Fehlerdrucke`, and `DruckeDebug` always
+    /// use.
+    pub fn with_drucke_separator(mut self, separator: impl Into<String>) -> Self {
+        self.interpreter.drucke_format.separator = separator.into();
+        self
+    }
+
+    /// Specifies the string which `Drucke` writes after its last argument.
+    /// Defaults to `"\n"`.
+    pub fn with_drucke_terminator(mut self, terminator: impl Into<String>) -> Self {
+        self.interpreter.drucke_format.terminator = terminator.into();
+        self
+    }
+
+    /// Makes the run reproducible, see [`Determinismus`] for the affected builtins.
+    pub fn with_determinismus(mut self, determinismus: Determinismus) -> Self {
+        self.interpreter.random = Random::Seeded(determinismus.seed);
+        self.interpreter.fixed_time = Some(determinismus.zeit);
+        self
+    }
+
+    /// Passes `matrikelnummer` to `Einschreibung` instead of a random one.
+    /// This takes precedence over [`InterpreterBuilder::with_determinismus`].
+    pub fn with_matrikelnummer(mut self, matrikelnummer: u32) -> Self {
+        self.interpreter.matrikelnummer = Some(matrikelnummer);
+        self
+    }
+
+    /// Registers a handle which is updated while the program runs.
+    pub fn with_statistics(mut self, statistics: Rc<Statistics>) -> Self {
+        self.interpreter.statistics = statistics;
+        self
+    }
+
+    /// Specifies which calls of `DruckeDebug` produce output.
+    /// A call is printed if its level is less than or equal to `verbosity`, which defaults to `0`.
+    pub fn with_verbosity(mut self, verbosity: u8) -> Self {
+        self.interpreter.verbosity = verbosity;
+        self
+    }
+
+    /// Limits how deeply lists and objects may be nested when serialized by `Gliedere_JSON`.
+    /// Exceeding the limit is a runtime error instead of a stack overflow.
+    /// Defaults to `128`.
+    pub fn with_json_max_depth(mut self, max_depth: usize) -> Self {
+        self.interpreter.json_max_depth = max_depth;
+        self
+    }
+
+    /// Passes the evaluated arguments of every `Drucke` call to `callback` instead of writing them
+    /// to the output, e.g. for structured logging.
+    /// Other builtins, such as `Schreibe` and `DruckeKanal`, are not affected.
+    pub fn with_drucke_callback(mut self, callback: impl FnMut(Vec<Value>) + 'src) -> Self {
+        self.interpreter.drucke_callback.0 = Some(Box::new(callback));
+        self
+    }
+
+    /// Limits how many calls of user-defined functions may be nested.
+    /// Exceeding the limit is a runtime error instead of a stack overflow.
+    /// Defaults to `300`, which fits into 8 MiB of stack in debug builds and 2 MiB in optimized
+    /// builds. Hosts running the interpreter on threads with larger stacks may raise the limit.
+    /// A function calling itself from the tail position of its body does not nest another call,
+    /// see [`InterruptKind::TailCall`](crate::value::InterruptKind::TailCall).
+    pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.interpreter.max_call_depth = max_call_depth;
+        self
+    }
+
+    /// Limits how many steps the program may execute, where every executed statement and every
+    /// iteration of a loop counts as one step.
+    /// Exceeding the limit is a runtime error, so that untrusted programs can be bounded
+    /// independently of how fast the host is.
+    /// By default, the number of steps is unlimited.
+    pub fn with_step_limit(mut self, step_limit: u64) -> Self {
+        self.interpreter.step_limit = Some(step_limit);
+        self
+    }
+
+    /// Specifies how long the HTTP client may wait for the response to a request of `Http`.
+    /// Defaults to 30 seconds.
+    pub fn with_http_timeout(mut self, timeout: Duration) -> Self {
+        self.interpreter.http_timeout = timeout;
+        self
+    }
+
+    /// Specifies where `Lies_Zeile` reads from, defaults to the standard input.
+    pub fn with_input(mut self, input: impl BufRead + 'src) -> Self {
+        self.interpreter.input.0 = Box::new(input);
+        self
+    }
+
+    pub fn build(self) -> Interpreter<'src, Output, HttpClient> {
+        self.interpreter
+    }
+}
@@ -0,0 +1,75 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// Makes a whole program run reproducible.
+///
+/// If configured using [`InterpreterBuilder::with_determinismus`](super::InterpreterBuilder::with_determinismus),
+/// the following values no longer depend on the environment:
+/// - the Matrikelnummer passed to `Einschreibung` is derived from `seed`, unless it is set using
+///   [`InterpreterBuilder::with_matrikelnummer`](super::InterpreterBuilder::with_matrikelnummer),
+/// - the numbers returned by `Zufall` are derived from `seed`,
+/// - `Zeit` always returns `zeit`, `Zeit_Millisekunden` returns `zeit` in milliseconds since the
+///   Unix epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Determinismus {
+    /// The seed of the random number generator.
+    pub seed: u64,
+    /// The time which is reported as the current time.
+    pub zeit: NaiveDateTime,
+}
+
+impl Determinismus {
+    /// Uses the given seed and the start of the Unix epoch as the current time.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            zeit: NaiveDate::from_ymd_opt(1970, 1, 1)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .expect("the Unix epoch is a valid date"),
+        }
+    }
+
+    pub fn with_zeit(mut self, zeit: NaiveDateTime) -> Self {
+        self.zeit = zeit;
+        self
+    }
+}
+
+/// The source of all randomness used by the interpreter.
+#[derive(Debug)]
+pub(super) enum Random {
+    /// Uses the random number generator of the operating system.
+    Os,
+    /// A SplitMix64 generator whose state is the contained value.
+    Seeded(u64),
+}
+
+impl Random {
+    pub(super) fn next_u64(&mut self) -> u64 {
+        match self {
+            Random::Os => {
+                let mut rand_buffer = [0; 8];
+                getrandom::getrandom(&mut rand_buffer).unwrap();
+                u64::from_le_bytes(rand_buffer)
+            }
+            Random::Seeded(state) => {
+                *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                let mut res = *state;
+                res = (res ^ (res >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                res = (res ^ (res >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                res ^ (res >> 31)
+            }
+        }
+    }
+
+    /// Returns a uniformly distributed number in `0..bound`.
+    /// Numbers which would favor small results, i.e. the first `2^64 % bound` ones, are rejected.
+    pub(super) fn below(&mut self, bound: u64) -> u64 {
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let res = self.next_u64();
+            if res >= threshold {
+                return res % bound;
+            }
+        }
+    }
+}
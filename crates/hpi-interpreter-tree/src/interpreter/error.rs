@@ -0,0 +1,109 @@
+use std::{borrow::Cow, fmt::Display};
+
+use super::traceback;
+
+/// A runtime error which terminates the program unless it is caught using `Versuche`.
+///
+/// The displayed message is the one shown to the user, so hosts which only report errors may
+/// simply use [`ToString::to_string`].
+/// Hosts which need to react to specific errors can match on the variants instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    /// An integer was divided by zero using `:` or `%`, or by `Modulo`.
+    DivisionByZero,
+    /// The result of an integer operation does not fit into a `Zahl`.
+    Overflow {
+        /// The operator, e.g. `Addition`, or the builtin, e.g. `Summe`.
+        operation: &'static str,
+        /// The exact result, builtins which calculate it using a wider type report it.
+        result: Option<i128>,
+    },
+    /// A list or string was indexed using a negative index.
+    NegativeIndex(i64),
+    /// A list or string was indexed using an index which is not less than its length.
+    IndexOutOfBounds {
+        index: i64,
+        len: usize,
+        target: IndexTarget,
+    },
+    /// An error raised inside of user-defined functions.
+    /// Contains the names of the functions which were being executed, the innermost one last.
+    WithTraceback {
+        error: Box<RuntimeError>,
+        trace: Vec<String>,
+    },
+    /// Any other error, described by its message.
+    Custom(Cow<'static, str>),
+}
+
+/// The kind of value which was indexed, see [`RuntimeError::IndexOutOfBounds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexTarget {
+    List,
+    String,
+}
+
+impl RuntimeError {
+    /// Returns the error without its traceback.
+    pub fn root(&self) -> &RuntimeError {
+        match self {
+            Self::WithTraceback { error, .. } => error.root(),
+            other => other,
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::Overflow {
+                operation,
+                result: None,
+            } => write!(f, "Ganzzahlüberlauf bei {operation}"),
+            Self::Overflow {
+                operation,
+                result: Some(result),
+            } => write!(
+                f,
+                "Ganzzahlüberlauf bei `{operation}`: Das Ergebnis `{result}` passt nicht in eine `Zahl`."
+            ),
+            Self::NegativeIndex(index) => {
+                write!(f, "Illegale Indizierung mittels Index: `{index}`")
+            }
+            Self::IndexOutOfBounds { index, len, target } => {
+                let target = match target {
+                    IndexTarget::List => "Liste",
+                    IndexTarget::String => "Zeichenkette",
+                };
+                write!(
+                    f,
+                    "Illegale Indizierung: Index `{index}` liegt außerhalb der {target} der Länge `{len}`"
+                )
+            }
+            Self::WithTraceback { error, trace } => {
+                let trace: Vec<&str> = trace.iter().map(String::as_str).collect();
+                write!(f, "{error}\n{}", traceback(&trace))
+            }
+            Self::Custom(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl From<String> for RuntimeError {
+    fn from(message: String) -> Self {
+        Self::Custom(message.into())
+    }
+}
+
+impl From<&'static str> for RuntimeError {
+    fn from(message: &'static str) -> Self {
+        Self::Custom(message.into())
+    }
+}
+
+impl From<Cow<'static, str>> for RuntimeError {
+    fn from(message: Cow<'static, str>) -> Self {
+        Self::Custom(message)
+    }
+}
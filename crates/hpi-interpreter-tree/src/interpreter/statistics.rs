@@ -0,0 +1,20 @@
+use std::cell::Cell;
+
+/// Counters which are updated while a program runs.
+/// A shared handle can be registered using
+/// [`InterpreterBuilder::with_statistics`](super::InterpreterBuilder::with_statistics).
+#[derive(Debug, Default)]
+pub struct Statistics {
+    calls: Cell<u64>,
+}
+
+impl Statistics {
+    /// The number of calls to user-defined functions, including the entry points.
+    pub fn calls(&self) -> u64 {
+        self.calls.get()
+    }
+
+    pub(super) fn count_call(&self) {
+        self.calls.set(self.calls.get() + 1);
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+};
+
+use hpi_analyzer::ast::*;
+
+use super::{check_bewerbung, Error, HPIHttpClient, Interpreter, Scope};
+use crate::value::{InterruptKind, Value};
+
+/// Describes whether a [`Stepper`] has more work to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// The step budget was used up, but the program has not terminated yet.
+    Pending,
+    /// The program has terminated with the contained exit code.
+    Finished(i64),
+}
+
+/// Describes why [`Stepper::run_to_breakpoint`] returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointOutcome {
+    /// Execution stopped before a statement on the contained line.
+    Breakpoint(usize),
+    /// The program has terminated with the contained exit code.
+    Finished(i64),
+}
+
+/// Executes a program in slices of statements instead of running it to completion.
+///
+/// Only statements which appear directly in a function body or in the body of a loop are
+/// resumable: `solange` loops and calls to user-defined functions in statement position are
+/// tracked on an explicit frame stack.
+/// Everything else, including calls which are part of a larger expression, is executed in a
+/// single step.
+/// Consequently, breakpoints only apply to resumable statements.
+pub struct Stepper<'tree, 'src, Output, HttpClient>
+where
+    Output: Write,
+    HttpClient: HPIHttpClient,
+{
+    interpreter: Interpreter<'src, Output, HttpClient>,
+    functions: HashMap<&'src str, &'tree AnalyzedFunctionDefinition<'src>>,
+    used_builtins: &'tree HashSet<&'src str>,
+    /// The entry points which have not been started yet, in reverse order.
+    entry_points: Vec<(&'src str, &'tree AnalyzedBlock<'src>)>,
+    frames: Vec<Frame<'tree, 'src>>,
+    matrikelnummer: i64,
+    exit_code: Option<i64>,
+    /// The source lines on which [`Stepper::run_to_breakpoint`] stops.
+    breakpoints: HashSet<usize>,
+    /// Set while stopped at a breakpoint, so that resuming does not stop at the same statement.
+    paused: bool,
+    /// The error which terminated the program, it is reported again by every later step.
+    /// Errors raised while preparing the program are reported by the first step.
+    error: Option<Error>,
+}
+
+enum Frame<'tree, 'src> {
+    /// Marks the boundary of a function call, owns the scope containing the parameters.
+    Call(&'src str),
+    /// A block which is executed statement by statement.
+    Block {
+        block: &'tree AnalyzedBlock<'src>,
+        next_stmt: usize,
+        /// Specifies whether this frame owns a scope.
+        scoped: bool,
+    },
+    /// A loop whose condition is checked each time this frame is on top of the stack.
+    While(&'tree AnalyzedWhileStmt<'src>),
+}
+
+impl<'tree, 'src, Output, HttpClient> Stepper<'tree, 'src, Output, HttpClient>
+where
+    Output: Write,
+    HttpClient: HPIHttpClient,
+{
+    pub(super) fn new(
+        mut interpreter: Interpreter<'src, Output, HttpClient>,
+        tree: &'tree AnalyzedProgram<'src>,
+        init: Result<(), Error>,
+    ) -> Self {
+        let matrikelnummer = interpreter.matrikelnummer();
+        Self {
+            interpreter,
+            functions: tree
+                .functions
+                .iter()
+                .filter(|func| func.used)
+                .map(|func| (func.name, func))
+                .collect(),
+            used_builtins: &tree.used_builtins,
+            entry_points: vec![
+                ("Studium", &tree.studium_fn),
+                ("Einschreibung", &tree.einschreibung_fn),
+                ("Bewerbung", &tree.bewerbung_fn),
+            ],
+            frames: vec![],
+            matrikelnummer,
+            exit_code: None,
+            breakpoints: HashSet::new(),
+            paused: false,
+            error: init.err(),
+        }
+    }
+
+    /// Makes [`Stepper::run_to_breakpoint`] stop before statements starting on `line`.
+    pub fn add_breakpoint(&mut self, line: usize) {
+        self.breakpoints.insert(line);
+    }
+
+    pub fn remove_breakpoint(&mut self, line: usize) {
+        self.breakpoints.remove(&line);
+    }
+
+    /// Executes up to `steps` statements and reports whether the program has terminated.
+    /// Once the program has finished, subsequent calls return the same exit code or error again.
+    /// Breakpoints are ignored.
+    /// The output is flushed before returning.
+    pub fn run_steps(&mut self, steps: usize) -> Result<StepOutcome, Error> {
+        let res = self.advance_steps(steps);
+        self.interpreter.flush_output();
+        res
+    }
+
+    fn advance_steps(&mut self, steps: usize) -> Result<StepOutcome, Error> {
+        for _ in 0..steps {
+            if let Some(code) = self.exit_code {
+                return Ok(StepOutcome::Finished(code));
+            }
+            self.advance()?;
+        }
+
+        Ok(match self.exit_code {
+            Some(code) => StepOutcome::Finished(code),
+            None => StepOutcome::Pending,
+        })
+    }
+
+    /// Executes statements until the program terminates or the next statement starts on a line
+    /// with a breakpoint.
+    /// While stopped, the variables can be inspected using [`Stepper::variables`], calling this
+    /// method again resumes the execution.
+    /// The output is flushed before returning.
+    pub fn run_to_breakpoint(&mut self) -> Result<BreakpointOutcome, Error> {
+        let res = self.until_breakpoint();
+        self.interpreter.flush_output();
+        res
+    }
+
+    fn until_breakpoint(&mut self) -> Result<BreakpointOutcome, Error> {
+        loop {
+            if let Some(code) = self.exit_code {
+                return Ok(BreakpointOutcome::Finished(code));
+            }
+
+            if !self.paused {
+                if let Some(line) = self
+                    .next_line()
+                    .filter(|line| self.breakpoints.contains(line))
+                {
+                    self.paused = true;
+                    return Ok(BreakpointOutcome::Breakpoint(line));
+                }
+            }
+            self.advance()?;
+        }
+    }
+
+    /// Returns the values of all variables which are currently in scope.
+    /// Variables of callers are included unless they are shadowed.
+    pub fn variables(&self) -> HashMap<&'src str, Value> {
+        self.interpreter
+            .scopes
+            .iter()
+            .flatten()
+            .map(|(name, value)| (*name, value.borrow().clone()))
+            .collect()
+    }
+
+    /// The line on which the next statement starts, if the next step executes a statement.
+    fn next_line(&self) -> Option<usize> {
+        match self.frames.last()? {
+            Frame::Block {
+                block, next_stmt, ..
+            } => block.stmt_spans.get(*next_stmt).map(|span| span.start.line),
+            _ => None,
+        }
+    }
+
+    fn advance(&mut self) -> Result<(), Error> {
+        if let Some(err) = &self.error {
+            return Err(err.clone());
+        }
+        self.paused = false;
+        match self.step() {
+            Ok(Some(code)) => {
+                self.frames.clear();
+                self.exit_code = Some(code);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                self.frames.clear();
+                self.entry_points.clear();
+                self.error = Some(err.clone());
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Executes a single step, returns the exit code if the program has terminated.
+    fn step(&mut self) -> Result<Option<i64>, Error> {
+        let Some(frame) = self.frames.last_mut() else {
+            return Ok(match self.entry_points.pop() {
+                Some((name, block)) => {
+                    let mut scope = HashMap::new();
+                    if name == "Einschreibung" {
+                        scope.insert("Matrikelnummer", Value::Int(self.matrikelnummer).wrapped());
+                    }
+                    self.enter(name, block, scope)?;
+                    None
+                }
+                None => Some(0),
+            });
+        };
+
+        match frame {
+            Frame::Block {
+                block, next_stmt, ..
+            } => {
+                let block = *block;
+                match block.stmts.get(*next_stmt) {
+                    Some(stmt) => {
+                        *next_stmt += 1;
+                        let res = self.statement(stmt);
+                        self.handle(res)
+                    }
+                    None => {
+                        let res = block.expr.as_ref().map_or(Ok(Value::Unit), |expr| {
+                            self.interpreter.visit_expression(expr)
+                        });
+                        match res {
+                            Ok(value) => {
+                                self.pop_frame();
+                                match self.frames.last() {
+                                    Some(Frame::Call(_)) => self.leave(value),
+                                    _ => Ok(None),
+                                }
+                            }
+                            Err(interrupt) => self.handle(Err(interrupt)),
+                        }
+                    }
+                }
+            }
+            Frame::While(node) => {
+                let node = *node;
+                match self
+                    .interpreter
+                    .visit_expression(&node.cond)
+                    .and_then(Value::unwrap_bool)
+                    .and_then(|cond| {
+                        if cond {
+                            self.interpreter.count_step()?;
+                        }
+                        Ok(cond)
+                    }) {
+                    Ok(true) => {
+                        self.interpreter.scopes.push(HashMap::new());
+                        self.frames.push(Frame::Block {
+                            block: &node.block,
+                            next_stmt: 0,
+                            scoped: true,
+                        });
+                        Ok(None)
+                    }
+                    Ok(false) => {
+                        self.frames.pop();
+                        Ok(None)
+                    }
+                    Err(interrupt) => self.handle(Err(interrupt)),
+                }
+            }
+            Frame::Call(_) => unreachable!("call frames are always followed by a block frame"),
+        }
+    }
+
+    /// Executes a statement, loops and statement-level calls are pushed onto the frame stack.
+    fn statement(&mut self, node: &'tree AnalyzedStatement<'src>) -> Result<(), InterruptKind> {
+        match node {
+            // statements which are not passed to `visit_statement` count as steps here
+            AnalyzedStatement::While(node) => {
+                self.interpreter.count_step()?;
+                self.frames.push(Frame::While(node));
+                Ok(())
+            }
+            AnalyzedStatement::Expr(AnalyzedExpression::Call(call)) => match call.func {
+                AnalyzedCallBase::Ident(name)
+                    if !self.used_builtins.contains(name) && self.functions.contains_key(name) =>
+                {
+                    self.interpreter.count_step()?;
+                    let func = self.functions[name];
+                    let args = self.interpreter.visit_list_expr_helper(&call.args)?;
+                    let scope = func
+                        .params
+                        .iter()
+                        .zip(args)
+                        .map(|(param, arg)| (param.name, arg.wrapped()))
+                        .collect();
+                    self.enter(name, &func.block, scope)?;
+                    Ok(())
+                }
+                _ => self.interpreter.visit_statement(node),
+            },
+            node => self.interpreter.visit_statement(node),
+        }
+    }
+
+    /// Raises the same error as [`Interpreter::call_user_func`] if the call nests too deeply.
+    fn enter(
+        &mut self,
+        name: &'src str,
+        block: &'tree AnalyzedBlock<'src>,
+        scope: Scope<'src>,
+    ) -> Result<(), Error> {
+        self.interpreter.check_call_depth()?;
+        self.interpreter.statistics.count_call();
+        self.interpreter.call_stack.push(name);
+        self.interpreter.scopes.push(scope);
+        self.frames.push(Frame::Call(name));
+        self.frames.push(Frame::Block {
+            block,
+            next_stmt: 0,
+            scoped: false,
+        });
+        Ok(())
+    }
+
+    /// Pops the call frame on top of the stack, `value` is the result of the call.
+    fn leave(&mut self, value: Value) -> Result<Option<i64>, Error> {
+        let Some(Frame::Call(name)) = self.frames.pop() else {
+            unreachable!("`leave` is only called if a call frame is on top of the stack")
+        };
+        self.interpreter.call_stack.pop();
+        self.interpreter.scopes.pop();
+
+        // the results of entry points are only relevant for `Bewerbung`
+        if self.frames.is_empty() && name == "Bewerbung" {
+            check_bewerbung(value)?;
+        }
+
+        Ok(None)
+    }
+
+    fn pop_frame(&mut self) {
+        if let Some(Frame::Block { scoped: true, .. }) = self.frames.pop() {
+            self.interpreter.scopes.pop();
+        }
+    }
+
+    /// Unwinds the frame stack according to the interruption of a statement.
+    fn handle(&mut self, res: Result<(), InterruptKind>) -> Result<Option<i64>, Error> {
+        let interrupt = match res {
+            Ok(()) => return Ok(None),
+            Err(InterruptKind::Error(msg)) => return Err(msg),
+            Err(InterruptKind::Exit(code)) => return Ok(Some(code)),
+            Err(interrupt) => interrupt,
+        };
+
+        loop {
+            match (self.frames.last(), &interrupt) {
+                (Some(Frame::Call(_)), InterruptKind::Return(value)) => {
+                    return self.leave(value.clone())
+                }
+                // like in `call_user_func`, the call is repeated instead of nesting another one
+                (Some(Frame::Call(name)), InterruptKind::TailCall(args)) => {
+                    let name = *name;
+                    self.frames.pop();
+                    self.interpreter.call_stack.pop();
+                    self.interpreter.scopes.pop();
+
+                    let func = self.functions[name];
+                    let scope = func
+                        .params
+                        .iter()
+                        .zip(args.clone())
+                        .map(|(param, arg)| (param.name, arg.wrapped()))
+                        .collect();
+                    self.enter(name, &func.block, scope)?;
+                    return Ok(None);
+                }
+                // like in `call_func`, other interruptions end the call as well
+                (Some(Frame::Call(_)), _) => return self.leave(Value::Unit),
+                (Some(Frame::While(_)), InterruptKind::Break) => {
+                    self.frames.pop();
+                    return Ok(None);
+                }
+                (Some(Frame::While(_)), InterruptKind::Continue) => return Ok(None),
+                (Some(_), _) => self.pop_frame(),
+                (None, _) => unreachable!("every frame stack is rooted in a call frame"),
+            }
+        }
+    }
+}
@@ -1,31 +1,216 @@
-use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc, str::FromStr};
+use std::{cell::RefCell, fmt::Display, rc::Rc, str::FromStr};
 
 use hpi_analyzer::Type;
-use serde_json::Number;
+use indexmap::IndexMap;
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::{error::Category, Number};
 
 use crate::value::{InterruptKind, Value};
 
-pub fn deserialize(input: &str) -> Result<Value, InterruptKind> {
-    let serde_value: serde_json::Value = serde_json::from_str(input).map_err(|err| {
-        InterruptKind::Error(format!("Zergliedere_JSON Textverarbeitungsfehler: {err}").into())
+/// Parses JSON, objects become Speicherboxen.
+/// `null` becomes `Nichts` ([`Value::Unit`]), which is serialized as `null` again, so HPI
+/// programs can detect it using `Box.Datentyp_Von("Schlüssel") == "Nichts"`.
+pub fn deserialize(input: &str, duplicate_keys: DuplicateKeys) -> Result<Value, InterruptKind> {
+    let builtin = match duplicate_keys {
+        DuplicateKeys::KeepLast => "Zergliedere_JSON",
+        DuplicateKeys::Reject => "Zergliedere_JSON_Streng",
+    };
+    let serde_value = match duplicate_keys {
+        DuplicateKeys::KeepLast => serde_json::from_str(input),
+        DuplicateKeys::Reject => {
+            serde_json::from_str(input).map(|StrictValue(value): StrictValue| value)
+        }
+    }
+    .map_err(|err| {
+        InterruptKind::Error(
+            format!(
+                "{builtin} Textverarbeitungsfehler: {}",
+                describe_syntax_error(input, &err)
+            )
+            .into(),
+        )
     })?;
-    Value::from_json(serde_value).map_err(|err| {
-        InterruptKind::Error(format!("Zergliedere_JSON Umwandlungsfehler: {err}").into())
-    })
+    Value::from_json(serde_value)
+        .map_err(|err| InterruptKind::Error(format!("{builtin} Umwandlungsfehler: {err}").into()))
+}
+
+/// How `deserialize` treats objects which contain the same key more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Keeps the value of the last occurrence, this is used by `Zergliedere_JSON`.
+    #[default]
+    KeepLast,
+    /// Fails with an error naming the key, this is used by `Zergliedere_JSON_Streng`.
+    Reject,
+}
+
+/// A JSON value whose objects do not contain any duplicate keys.
+struct StrictValue(serde_json::Value);
+
+impl<'de> Deserialize<'de> for StrictValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(StrictVisitor).map(Self)
+    }
 }
 
-pub fn serialize(input: Value) -> Result<String, InterruptKind> {
-    let serde_value = input.to_json().map_err(|err| {
+struct StrictVisitor;
+
+impl<'de> Visitor<'de> for StrictVisitor {
+    type Value = serde_json::Value;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(serde_json::Value::Null)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        // the parser never produces `NaN` or infinite numbers
+        Ok(Number::from_f64(value).map_or(serde_json::Value::Null, serde_json::Value::Number))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = vec![];
+        while let Some(StrictValue(value)) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(serde_json::Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut members = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if members.contains_key(&key) {
+                return Err(de::Error::custom(format!(
+                    "der Schlüssel `{key}` ist mehrfach vorhanden"
+                )));
+            }
+            let StrictValue(value) = map.next_value()?;
+            members.insert(key, value);
+        }
+        Ok(serde_json::Value::Object(members))
+    }
+}
+
+/// Describes where and why parsing failed.
+/// The line and the column are counted from `1`, the offset from `0`, all of them count
+/// characters instead of bytes.
+fn describe_syntax_error(input: &str, err: &serde_json::Error) -> String {
+    let reason = match err.classify() {
+        Category::Eof => "unerwartetes Ende der Eingabe",
+        Category::Syntax => "unerwartetes Zeichen",
+        Category::Data | Category::Io => "ungültige Daten",
+    };
+    // the message of serde ends with the position, which is reported separately
+    let message = err.to_string();
+    let detail = message
+        .rsplit_once(" at line ")
+        .map_or(message.as_str(), |(detail, _)| detail);
+
+    // serde reports the column in bytes
+    let mut offset = 0;
+    let mut column = err.column();
+    for (idx, line) in input.split('\n').enumerate() {
+        if idx + 1 == err.line() {
+            column = line
+                .char_indices()
+                .take_while(|(byte, _)| *byte < err.column())
+                .count();
+            offset += column.saturating_sub(1);
+            break;
+        }
+        offset += line.chars().count() + 1;
+    }
+
+    format!(
+        "JSON-Fehler bei Zeile {}, Spalte {column} (Zeichen {offset}): {reason} ({detail})",
+        err.line()
+    )
+}
+
+/// The default for the maximum nesting depth of values serialized by `Gliedere_JSON`.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Serializes `input` as JSON.
+/// If `indent` is set, every member of a non-empty list or object is written on its own line,
+/// indented by `indent` spaces per level.
+pub fn serialize(
+    input: Value,
+    key_order: KeyOrder,
+    max_depth: usize,
+    indent: Option<usize>,
+) -> Result<String, InterruptKind> {
+    let mut serializer = Serializer {
+        key_order,
+        max_depth,
+        indent,
+        depth: 0,
+        output: String::new(),
+    };
+    serializer.write(&input).map_err(|err| {
         InterruptKind::Error(format!("Gliedere_JSON Umwandlungsfehler: {err}").into())
     })?;
 
-    Ok(serde_value.to_string())
+    Ok(serializer.output)
+}
+
+/// The order in which the keys of objects and Speicherboxen are written by `Gliedere_JSON`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Sorts the keys lexicographically, this is used by `Gliedere_JSON`.
+    #[default]
+    Ascending,
+    /// Sorts the keys lexicographically in reverse order.
+    Descending,
+    /// Keeps the order in which the members were created.
+    Insertion,
+}
+
+impl FromStr for KeyOrder {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "aufsteigend" => Ok(Self::Ascending),
+            "absteigend" => Ok(Self::Descending),
+            "eingefügt" => Ok(Self::Insertion),
+            other => Err(format!(
+                "Unbekannte Schlüsselsortierung `{other}`, erlaubt sind `aufsteigend`, `absteigend` und `eingefügt`."
+            )),
+        }
+    }
 }
 
 #[derive(Debug)]
 enum TypeError {
     ListInnerType { expected: Type, found: Type },
     UnsupportedType(Type),
+    TooDeep(usize),
 }
 
 impl Display for TypeError {
@@ -39,64 +224,25 @@ impl Display for TypeError {
                 f,
                 "Datentypfehler: der Datentyp `{other}` wird nicht unterstützt."
             ),
+            TypeError::TooDeep(max_depth) => write!(
+                f,
+                "Schachtelungstiefe: der Wert ist tiefer als {max_depth} Ebenen verschachtelt."
+            ),
         }
     }
 }
 
 impl Value {
-    fn to_json(&self) -> Result<serde_json::Value, TypeError> {
-        Ok(match self {
-            Value::Int(inner) => {
-                serde_json::Value::from_str(&format!("{inner}")).expect("this should not fail")
-            }
-            Value::Float(inner) => {
-                serde_json::Value::Number(Number::from_f64(*inner).expect("this should not fail"))
-            }
-            Value::Char(inner) => serde_json::Value::String(inner.to_string()),
-            Value::String(inner) => serde_json::Value::String(inner.clone()),
-            Value::List(inner) => {
-                let inner = inner
-                    .borrow()
-                    .iter()
-                    .map(|element| element.to_json())
-                    .collect::<Result<Vec<serde_json::Value>, TypeError>>()?;
-                serde_json::Value::Array(inner)
-            }
-            Value::Bool(inner) => serde_json::Value::Bool(*inner),
-            Value::Unit => serde_json::Value::Null,
-            Value::Ptr(_) | Value::BuiltinFunction(_, _) => {
-                return Err(TypeError::UnsupportedType(self.as_type()))
-            }
-            Value::Speicherbox(inner) => {
-                let mut new_inner = serde_json::Map::new();
-                for (key, value) in inner {
-                    new_inner.insert(key.clone(), value.to_json()?);
-                }
-                serde_json::Value::Object(new_inner)
-            }
-            Value::Objekt(inner) => {
-                let mut new_inner = serde_json::Map::new();
-                for (key, value) in inner.borrow().iter() {
-                    new_inner.insert(key.clone(), value.to_json()?);
-                }
-                serde_json::Value::Object(new_inner)
-            }
-        })
-    }
-
     fn from_json(value: serde_json::Value) -> Result<Self, TypeError> {
         Ok(match value {
             serde_json::Value::Null => Self::Unit,
             serde_json::Value::Bool(inner) => Self::Bool(inner),
-            serde_json::Value::Number(inner) => {
-                if inner.is_i64() {
-                    Self::Int(inner.as_i64().expect("this should not fail"))
-                } else if inner.is_f64() {
-                    Self::Float(inner.as_f64().expect("this should not fail"))
-                } else {
-                    unreachable!("this should not happen")
-                }
-            }
+            // Only numbers without a fraction or exponent are parsed as integers.
+            // Integers which do not fit into an `i64` fall back to floats.
+            serde_json::Value::Number(inner) => match inner.as_i64() {
+                Some(inner) => Self::Int(inner),
+                None => Self::Float(inner.as_f64().expect("this should not fail")),
+            },
             serde_json::Value::String(inner) => Self::String(inner),
             serde_json::Value::Array(inner) => {
                 let mut typ_ = Type::Unknown;
@@ -125,18 +271,213 @@ impl Value {
                 let inner_map = inner
                     .into_iter()
                     .map(|(key, value)| Ok((key, Value::from_json(value)?)))
-                    .collect::<Result<HashMap<String, Value>, TypeError>>()?;
+                    .collect::<Result<IndexMap<String, Value>, TypeError>>()?;
 
-                Value::Speicherbox(inner_map)
+                Value::Speicherbox(Rc::new(RefCell::new(inner_map)))
             }
         })
     }
 }
 
+/// Writes values as compact or indented JSON.
+struct Serializer {
+    key_order: KeyOrder,
+    max_depth: usize,
+    /// The number of spaces per level, the output is compact if this is `None`.
+    indent: Option<usize>,
+    /// The number of lists and objects which contain the value currently being written.
+    depth: usize,
+    output: String,
+}
+
+impl Serializer {
+    fn write(&mut self, value: &Value) -> Result<(), TypeError> {
+        match value {
+            Value::Int(inner) => self.output.push_str(&inner.to_string()),
+            Value::Float(inner) => self.output.push_str(
+                &Number::from_f64(*inner)
+                    .expect("this should not fail")
+                    .to_string(),
+            ),
+            Value::Char(inner) => self.write_string(&inner.to_string()),
+            Value::String(inner) => self.write_string(inner),
+            Value::List(inner) => {
+                let inner = inner.borrow();
+                self.enter()?;
+                self.output.push('[');
+                for (idx, element) in inner.iter().enumerate() {
+                    if idx > 0 {
+                        self.output.push(',');
+                    }
+                    self.newline();
+                    self.write(element)?;
+                }
+                self.leave(inner.is_empty());
+                self.output.push(']');
+            }
+            Value::Bool(inner) => self.output.push_str(&inner.to_string()),
+            Value::Unit => self.output.push_str("null"),
+            Value::Ptr(_) | Value::BuiltinFunction(_, _) | Value::Function(_, _) => {
+                return Err(TypeError::UnsupportedType(value.as_type()))
+            }
+            Value::Speicherbox(inner) => self.write_object(&inner.borrow())?,
+            Value::Objekt(inner) => self.write_object(&inner.borrow())?,
+        }
+        Ok(())
+    }
+
+    fn write_string(&mut self, input: &str) {
+        self.output
+            .push_str(&serde_json::Value::String(input.to_string()).to_string());
+    }
+
+    fn write_object(&mut self, members: &IndexMap<String, Value>) -> Result<(), TypeError> {
+        let mut members: Vec<(&String, &Value)> = members.iter().collect();
+        match self.key_order {
+            KeyOrder::Ascending => members.sort_by(|left, right| left.0.cmp(right.0)),
+            KeyOrder::Descending => members.sort_by(|left, right| right.0.cmp(left.0)),
+            KeyOrder::Insertion => {}
+        }
+
+        let is_empty = members.is_empty();
+        self.enter()?;
+        self.output.push('{');
+        for (idx, (key, value)) in members.into_iter().enumerate() {
+            if idx > 0 {
+                self.output.push(',');
+            }
+            self.newline();
+            self.write_string(key);
+            self.output.push(':');
+            if self.indent.is_some() {
+                self.output.push(' ');
+            }
+            self.write(value)?;
+        }
+        self.leave(is_empty);
+        self.output.push('}');
+        Ok(())
+    }
+
+    /// Descends into a list or object, fails if this exceeds the maximum depth.
+    fn enter(&mut self) -> Result<(), TypeError> {
+        if self.depth == self.max_depth {
+            return Err(TypeError::TooDeep(self.max_depth));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Ascends out of a list or object, the closing bracket is put on its own line unless the
+    /// list or object is empty.
+    fn leave(&mut self, is_empty: bool) {
+        self.depth -= 1;
+        if !is_empty {
+            self.newline();
+        }
+    }
+
+    /// Starts a new line indented to the current depth, does nothing for compact output.
+    fn newline(&mut self) {
+        if let Some(indent) = self.indent {
+            self.output.push('\n');
+            self.output.push_str(&" ".repeat(indent * self.depth));
+        }
+    }
+}
+
+#[cfg(test)]
 mod test {
+    use super::{DuplicateKeys, KeyOrder, DEFAULT_MAX_DEPTH};
+    use crate::value::{InterruptKind, Value};
+
     #[test]
     fn test_deserialize() {
-        super::deserialize("{\"foo\": 42}");
-        super::deserialize("[ 42, 1, 3 ]");
+        super::deserialize("{\"foo\": 42}", DuplicateKeys::default());
+        super::deserialize("[ 42, 1, 3 ]", DuplicateKeys::default());
+    }
+
+    #[test]
+    fn test_syntax_errors_report_their_position() {
+        let error = |input| match super::deserialize(input, DuplicateKeys::default()) {
+            Err(InterruptKind::Error(err)) => err.to_string(),
+            other => panic!("expected an error, found {other:?}"),
+        };
+
+        assert_eq!(
+            error("{\n  \"a\": 1,\n  \"b\": x\n}"),
+            "Zergliedere_JSON Textverarbeitungsfehler: JSON-Fehler bei Zeile 3, Spalte 8 (Zeichen 19): unerwartetes Zeichen (expected value)"
+        );
+        assert_eq!(
+            error("[\"äöü\" x]"),
+            "Zergliedere_JSON Textverarbeitungsfehler: JSON-Fehler bei Zeile 1, Spalte 8 (Zeichen 7): unerwartetes Zeichen (expected `,` or `]`)"
+        );
+        assert_eq!(
+            error("[1, 2"),
+            "Zergliedere_JSON Textverarbeitungsfehler: JSON-Fehler bei Zeile 1, Spalte 5 (Zeichen 4): unerwartetes Ende der Eingabe (EOF while parsing a list)"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let input = "{\"a\":1,\"a\":2}";
+
+        let value = super::deserialize(input, DuplicateKeys::KeepLast).unwrap();
+        let Value::Speicherbox(members) = &value else {
+            panic!("expected a Speicherbox, found {value:?}");
+        };
+        assert_eq!(members.borrow()["a"], Value::Int(2));
+
+        match super::deserialize(input, DuplicateKeys::Reject) {
+            Err(InterruptKind::Error(err)) => assert_eq!(
+                err.to_string(),
+                "Zergliedere_JSON_Streng Textverarbeitungsfehler: JSON-Fehler bei Zeile 1, Spalte 10 (Zeichen 9): ungültige Daten (der Schlüssel `a` ist mehrfach vorhanden)"
+            ),
+            other => panic!("expected an error, found {other:?}"),
+        }
+        // nested objects are checked as well, keys in different objects do not collide
+        assert!(super::deserialize("{\"a\":{\"a\":1}}", DuplicateKeys::Reject).is_ok());
+        assert!(super::deserialize("[{\"b\":1,\"b\":1}]", DuplicateKeys::Reject).is_err());
+    }
+
+    #[test]
+    fn test_null_round_trips() {
+        let value =
+            super::deserialize("{\"a\": null, \"b\": [null]}", DuplicateKeys::default()).unwrap();
+        let Value::Speicherbox(members) = &value else {
+            panic!("expected a Speicherbox, found {value:?}");
+        };
+        assert_eq!(members.borrow()["a"], Value::Unit);
+
+        let serialized =
+            super::serialize(value, KeyOrder::default(), DEFAULT_MAX_DEPTH, None).unwrap();
+        assert_eq!(serialized, "{\"a\":null,\"b\":[null]}");
+    }
+
+    #[test]
+    fn test_numbers_keep_their_type() {
+        for (value, json) in [
+            (Value::Int(42), "42"),
+            (Value::Float(42.5), "42.5"),
+            (Value::Float(42.0), "42.0"),
+        ] {
+            let serialized =
+                super::serialize(value.clone(), KeyOrder::default(), DEFAULT_MAX_DEPTH, None)
+                    .unwrap();
+            assert_eq!(serialized, json);
+            assert_eq!(
+                super::deserialize(&serialized, DuplicateKeys::default()).unwrap(),
+                value
+            );
+        }
+
+        assert_eq!(
+            super::deserialize("4.2e1", DuplicateKeys::default()).unwrap(),
+            Value::Float(42.0)
+        );
+        assert_eq!(
+            super::deserialize("18446744073709551615", DuplicateKeys::default()).unwrap(),
+            Value::Float(18446744073709551615.0)
+        );
     }
 }
@@ -1,18 +1,52 @@
+/// Marks code which is only reachable if the analyzer's guarantees do not hold, e.g. because the
+/// analyzed tree was modified.
+/// With the `defensive` feature, a runtime error is returned instead of panicking, so the
+/// enclosing function must return a `Result` whose error can be created from a `String`.
+macro_rules! analyzer_guaranteed {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "defensive")]
+        return Err(
+            format!("Interner Fehler: {} ({}:{})", format_args!($($arg)*), file!(), line!()).into(),
+        );
+        #[cfg(not(feature = "defensive"))]
+        unreachable!($($arg)*)
+    }};
+}
+
 mod format;
+#[cfg(feature = "async")]
+mod http;
 mod interpreter;
 mod json;
+mod math;
 mod ops;
+mod regex;
 mod value;
 
+#[cfg(test)]
+mod tests;
+
 use std::collections::HashMap;
 use std::{fmt::Debug, io::Write};
 
-use hpi_analyzer::Diagnostic;
-pub use interpreter::HPIHttpClient;
-pub use interpreter::Interpreter;
+use hpi_analyzer::{Diagnostic, DiagnosticLevel};
+use hpi_parser::{Location, Span};
+#[cfg(feature = "async")]
+pub use http::{AsyncHPIHttpClient, BlockingHttpClient};
+/// Used by the members of [`Value::Speicherbox`] and [`Value::Objekt`].
+pub use indexmap::IndexMap;
+pub use interpreter::Determinismus;
+pub use interpreter::RunOutcome;
+pub use interpreter::{BreakpointOutcome, Statistics, StepOutcome, Stepper};
+pub use interpreter::{HPIHttpClient, HttpError};
+pub use interpreter::{IndexTarget, RuntimeError};
+pub use interpreter::{Interpreter, InterpreterBuilder};
+pub use value::{FloatNotation, InterruptKind, PointerNotation, Value};
 
 /// Interprets rush source code by walking the analyzed tree.
 /// The `Ok(_)` variant returns the exit code and non-error diagnostics.
+/// The last diagnostic is an info which states whether the program completed normally or
+/// called `Aufgeben`.
 /// The `Err(_)` variant returns a [`RunError`].
 pub fn run<'src, HttpClient>(
     text: &'src str,
@@ -24,14 +58,58 @@ pub fn run<'src, HttpClient>(
 where
     HttpClient: HPIHttpClient,
 {
-    let (tree, diagnostics) = hpi_analyzer::analyze(text, path)?;
-    let code = Interpreter::new(output, http_client, environment_vars).run(tree)?;
-    Ok((code, diagnostics))
+    let (tree, mut diagnostics) = hpi_analyzer::analyze(text, path)?;
+    let outcome = Interpreter::new(output, http_client, environment_vars)
+        .run_located(tree)
+        .map_err(|(err, span)| RunError::Runtime(err, span))?;
+
+    let message = match outcome {
+        RunOutcome::Completed => "Das Programm wurde nach dem Studium regulär beendet.".to_string(),
+        RunOutcome::Exited(code) => {
+            format!("Das Programm wurde mit `Aufgeben({code})` vorzeitig beendet.")
+        }
+    };
+    let location = Location::new(path);
+    diagnostics.push(Diagnostic::new(
+        DiagnosticLevel::Info,
+        message,
+        vec![],
+        Span::new(location, location),
+        text,
+    ));
+
+    Ok((outcome.code(), diagnostics))
+}
+
+/// Like [`run`], but collects the output of the program into a `String`, which is returned
+/// alongside the exit code and the diagnostics.
+/// If the output is not valid UTF-8, a [`RunError::Runtime`] is returned.
+pub fn run_to_string<'src, HttpClient>(
+    text: &'src str,
+    path: &'src str,
+    http_client: HttpClient,
+    environment_vars: HashMap<String, String>,
+) -> Result<(i64, String, Vec<Diagnostic<'src>>), RunError<'src>>
+where
+    HttpClient: HPIHttpClient,
+{
+    let mut output = vec![];
+    let (code, diagnostics) = run(text, path, &mut output, http_client, environment_vars)?;
+    let output = String::from_utf8(output).map_err(|err| {
+        RunError::Runtime(
+            format!("Die Ausgabe des Programms ist kein gültiges UTF-8: {err}").into(),
+            None,
+        )
+    })?;
+
+    Ok((code, output, diagnostics))
 }
 
 pub enum RunError<'src> {
     Analyzer(Vec<Diagnostic<'src>>),
-    Runtime(interpreter::Error),
+    /// A runtime error and the location where it was raised, if known
+    /// (see [`Interpreter::run_located`]).
+    Runtime(RuntimeError, Option<Box<Span<'src>>>),
 }
 
 impl<'src> From<Vec<Diagnostic<'src>>> for RunError<'src> {
@@ -40,9 +118,9 @@ impl<'src> From<Vec<Diagnostic<'src>>> for RunError<'src> {
     }
 }
 
-impl From<interpreter::Error> for RunError<'_> {
-    fn from(err: interpreter::Error) -> Self {
-        Self::Runtime(err)
+impl From<RuntimeError> for RunError<'_> {
+    fn from(err: RuntimeError) -> Self {
+        Self::Runtime(err, None)
     }
 }
 
@@ -50,7 +128,7 @@ impl Debug for RunError<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             RunError::Analyzer(diagnostics) => write!(f, "{diagnostics:?}"),
-            RunError::Runtime(err) => write!(f, "{err:?}"),
+            RunError::Runtime(err, _) => write!(f, "{err:?}"),
         }
     }
 }
@@ -0,0 +1,117 @@
+//! Number theory and summation helpers used by the builtins of the `Mathe` module.
+
+/// Calculates the greatest common divisor using Euclid's algorithm.
+pub fn ggt(mut left: u64, mut right: u64) -> u64 {
+    while right != 0 {
+        (left, right) = (right, left % right);
+    }
+    left
+}
+
+/// Sums floats using Neumaier's compensated summation, so that small values are not lost when they
+/// are added to large ones, e.g. `1e16 + 1 - 1e16` is `1` instead of `0`.
+pub fn sum_floats(values: impl IntoIterator<Item = f64>) -> f64 {
+    let mut sum = 0.0_f64;
+    let mut compensation = 0.0;
+    for value in values {
+        let next = sum + value;
+        compensation += if sum.abs() >= value.abs() {
+            (sum - next) + value
+        } else {
+            (value - next) + sum
+        };
+        sum = next;
+    }
+    sum + compensation
+}
+
+fn mul_mod(left: u64, right: u64, modulus: u64) -> u64 {
+    ((left as u128 * right as u128) % modulus as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut res = 1;
+    base %= modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            res = mul_mod(res, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    res
+}
+
+/// A Miller-Rabin test, the bases are sufficient for a deterministic result for all `u64`.
+pub fn is_prime(num: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+    if num < 2 {
+        return false;
+    }
+    if let Some(base) = BASES.iter().find(|base| num.is_multiple_of(**base)) {
+        return num == *base;
+    }
+
+    let shift = (num - 1).trailing_zeros();
+    let odd = (num - 1) >> shift;
+    BASES.iter().all(|base| {
+        let mut x = pow_mod(*base, odd, num);
+        if x == 1 || x == num - 1 {
+            return true;
+        }
+        (1..shift).any(|_| {
+            x = mul_mod(x, x, num);
+            x == num - 1
+        })
+    })
+}
+
+/// Returns the prime factors of `num` in ascending order, `0` and `1` have none.
+pub fn prime_factors(num: u64) -> Vec<u64> {
+    let mut factors = vec![];
+    let mut rest = num;
+
+    for divisor in [2, 3, 5] {
+        while rest > 1 && rest.is_multiple_of(divisor) {
+            factors.push(divisor);
+            rest /= divisor;
+        }
+    }
+
+    // the remaining number has only large factors, which are found using Pollard's rho algorithm
+    let mut pending = vec![rest];
+    while let Some(composite) = pending.pop() {
+        match composite {
+            0 | 1 => {}
+            prime if is_prime(prime) => factors.push(prime),
+            composite => {
+                let divisor = pollard_rho(composite);
+                pending.push(divisor);
+                pending.push(composite / divisor);
+            }
+        }
+    }
+
+    factors.sort_unstable();
+    factors
+}
+
+/// Finds a non-trivial divisor of an odd composite number.
+fn pollard_rho(num: u64) -> u64 {
+    (1..)
+        .find_map(|increment| {
+            let step = |x| (mul_mod(x, x, num) + increment) % num;
+            let (mut slow, mut fast) = (2, 2);
+            loop {
+                slow = step(slow);
+                fast = step(step(fast));
+                match ggt(slow.abs_diff(fast), num) {
+                    1 => continue,
+                    divisor if divisor == num => break None,
+                    divisor => break Some(divisor),
+                }
+            }
+        })
+        .expect("every composite number has a non-trivial divisor")
+}
@@ -3,113 +3,170 @@ use std::{
     ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub},
 };
 
-use crate::value::Value;
+use crate::{value::Value, RuntimeError};
 
 impl Not for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
 
     fn not(self) -> Self::Output {
         match self {
-            Value::Int(num) => (!num).into(),
-            Value::Bool(bool) => (!bool).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            Value::Int(num) => Ok((!num).into()),
+            Value::Bool(bool) => Ok((!bool).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl Neg for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
 
     fn neg(self) -> Self::Output {
         match self {
-            Value::Int(num) => (-num).into(),
-            Value::Float(num) => (-num).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            Value::Int(num) => num
+                .checked_neg()
+                .map(Value::from)
+                .ok_or(RuntimeError::Overflow {
+                    operation: "Negation",
+                    result: None,
+                }),
+            Value::Float(num) => Ok((-num).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl Add for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
 
     fn add(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Value::Int(left), Value::Int(right)) => left.wrapping_add(right).into(),
-            (Value::Float(left), Value::Float(right)) => (left + right).into(),
-            (Value::Char(left), Value::Char(right)) => (left.wrapping_add(right) & 0x7f).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            (Value::Int(left), Value::Int(right)) => left
+                .checked_add(right)
+                .map(Value::from)
+                .ok_or(RuntimeError::Overflow {
+                    operation: "Addition",
+                    result: None,
+                }),
+            (Value::Float(left), Value::Float(right)) => Ok((left + right).into()),
+            // characters are 7-bit ASCII, so they wrap around instead
+            (Value::Char(left), Value::Char(right)) => Ok((left.wrapping_add(right) & 0x7f).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl Sub for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Value::Int(left), Value::Int(right)) => left.wrapping_sub(right).into(),
-            (Value::Float(left), Value::Float(right)) => (left - right).into(),
-            (Value::Char(left), Value::Char(right)) => (left.wrapping_sub(right) & 0x7f).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            (Value::Int(left), Value::Int(right)) => left
+                .checked_sub(right)
+                .map(Value::from)
+                .ok_or(RuntimeError::Overflow {
+                    operation: "Subtraktion",
+                    result: None,
+                }),
+            (Value::Float(left), Value::Float(right)) => Ok((left - right).into()),
+            (Value::Char(left), Value::Char(right)) => Ok((left.wrapping_sub(right) & 0x7f).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl Mul for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
 
     fn mul(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Value::Int(left), Value::Int(right)) => left.wrapping_mul(right).into(),
-            (Value::Float(left), Value::Float(right)) => (left * right).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            (Value::Int(left), Value::Int(right)) => left
+                .checked_mul(right)
+                .map(Value::from)
+                .ok_or(RuntimeError::Overflow {
+                    operation: "Multiplikation",
+                    result: None,
+                }),
+            (Value::Float(left), Value::Float(right)) => Ok((left * right).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl Div for Value {
-    type Output = Result<Self, &'static str>;
+    type Output = Result<Self, RuntimeError>;
 
     fn div(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (_, Value::Int(0)) => Err("division by zero"),
-            (Value::Int(left), Value::Int(right)) => Ok(left.wrapping_div(right).into()),
+            (_, Value::Int(0)) => Err(RuntimeError::DivisionByZero),
+            (Value::Int(left), Value::Int(right)) => left
+                .checked_div(right)
+                .map(Value::from)
+                .ok_or(RuntimeError::Overflow {
+                    operation: "Division",
+                    result: None,
+                }),
             (Value::Float(left), Value::Float(right)) => Ok((left / right).into()),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl Rem for Value {
-    type Output = Result<Self, &'static str>;
+    type Output = Result<Self, RuntimeError>;
 
     fn rem(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (_, Value::Int(0)) => Err("division by zero"),
+            (_, Value::Int(0)) => Err(RuntimeError::DivisionByZero),
             (Value::Int(left), Value::Int(right)) => Ok(left.wrapping_rem(right).into()),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl Value {
-    pub fn pow(self, exp: Self) -> Self {
+    pub fn pow(self, exp: Self) -> Result<Self, RuntimeError> {
         match (self, exp) {
-            (Value::Int(_), Value::Int(exp)) if exp < 0 => 0_i64.into(),
-            (Value::Int(base), Value::Int(exp)) => base.wrapping_pow(exp as u32).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            (Value::Int(_), Value::Int(exp)) if exp < 0 => Ok(0_i64.into()),
+            (Value::Int(base), Value::Int(exp)) => {
+                let res = match u32::try_from(exp) {
+                    Ok(exp) => base.checked_pow(exp),
+                    // only these bases do not overflow for such large exponents
+                    Err(_) => match base {
+                        0 | 1 => Some(base),
+                        -1 => Some(if exp % 2 == 0 { 1 } else { -1 }),
+                        _ => None,
+                    },
+                };
+                res.map(Value::from).ok_or(RuntimeError::Overflow {
+                    operation: "Potenzierung",
+                    result: None,
+                })
+            }
+            // the `**` operator only accepts integers, but `Potenz` also accepts floats
+            (Value::Float(base), Value::Float(exp)) => Ok(base.powf(exp).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
-impl PartialOrd for Value {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match (self, other) {
+impl Value {
+    /// Compares two values of the same comparable type, `NaN` is not comparable.
+    pub fn compare(&self, other: &Self) -> Result<Option<Ordering>, RuntimeError> {
+        Ok(match (self, other) {
             (Value::Int(left), Value::Int(right)) => left.partial_cmp(right),
             (Value::Float(left), Value::Float(right)) => left.partial_cmp(right),
             (Value::Char(left), Value::Char(right)) => left.partial_cmp(right),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
-        }
+            (Value::String(left), Value::String(right)) => left.partial_cmp(right),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
+        })
+    }
+}
+
+impl PartialOrd for Value {
+    /// Values which violate the guarantees of the analyzer are not comparable in `defensive`
+    /// mode, use [`Value::compare`] to report them instead.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.compare(other).ok().flatten()
     }
 }
 
@@ -122,7 +179,7 @@ impl Shl for Value {
                 "tried to shift left by {right}, which is outside `0..=63`"
             )),
             (Value::Int(left), Value::Int(right)) => Ok(left.shl(right).into()),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
@@ -136,43 +193,43 @@ impl Shr for Value {
                 "tried to shift right by {right}, which is outside `0..=63`"
             )),
             (Value::Int(left), Value::Int(right)) => Ok(left.shr(right).into()),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl BitOr for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
 
     fn bitor(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Value::Int(left), Value::Int(right)) => (left | right).into(),
-            (Value::Bool(left), Value::Bool(right)) => (left | right).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            (Value::Int(left), Value::Int(right)) => Ok((left | right).into()),
+            (Value::Bool(left), Value::Bool(right)) => Ok((left | right).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl BitAnd for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
 
     fn bitand(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Value::Int(left), Value::Int(right)) => (left & right).into(),
-            (Value::Bool(left), Value::Bool(right)) => (left & right).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            (Value::Int(left), Value::Int(right)) => Ok((left & right).into()),
+            (Value::Bool(left), Value::Bool(right)) => Ok((left & right).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
 
 impl BitXor for Value {
-    type Output = Self;
+    type Output = Result<Self, RuntimeError>;
 
     fn bitxor(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Value::Int(left), Value::Int(right)) => (left ^ right).into(),
-            (Value::Bool(left), Value::Bool(right)) => (left ^ right).into(),
-            _ => unreachable!("the analyzer guarantees one of the above to match"),
+            (Value::Int(left), Value::Int(right)) => Ok((left ^ right).into()),
+            (Value::Bool(left), Value::Bool(right)) => Ok((left ^ right).into()),
+            _ => analyzer_guaranteed!("the analyzer guarantees one of the above to match"),
         }
     }
 }
@@ -0,0 +1,728 @@
+//! A small engine for the regular expressions used by the text builtins.
+//!
+//! Patterns are compiled into a program for a Pike VM which simulates all alternatives in
+//! lockstep, so matching takes linear time in the length of the text and never recurses.
+//! Supported are literals, `.`, character classes (`[a-z]`, `[^…]`, `\d`, `\w`, `\s` and their
+//! negations), groups (`(…)`, `(?:…)`), alternations (`|`), anchors (`^`, `$`) and the greedy or
+//! lazy quantifiers `*`, `+`, `?` and `{n,m}`.
+//! Positions are measured in characters instead of bytes.
+
+use std::{iter::Peekable, str::Chars};
+
+/// The maximum number of instructions of a compiled pattern, large counted repetitions like
+/// `(a{1000}){1000}` would otherwise exhaust the memory.
+const MAX_INSTRUCTIONS: usize = 100_000;
+
+/// The maximum count of a counted repetition, so that compiling a pattern always terminates
+/// quickly, even if the repeated part compiles to nothing, like in `(?:){999999999}`.
+const MAX_REPETITIONS: usize = 1000;
+
+#[derive(Debug)]
+pub struct Regex {
+    program: Vec<Inst>,
+    /// The number of capturing groups, excluding the implicit group of the whole match.
+    groups: usize,
+}
+
+/// The start and end positions of each group of a match, group 0 contains the whole match.
+pub type Captures = Vec<Option<(usize, usize)>>;
+
+#[derive(Debug)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit,
+    Word,
+    Space,
+    Not(Box<ClassItem>),
+}
+
+impl ClassItem {
+    fn matches(&self, char: char) -> bool {
+        match self {
+            ClassItem::Range(start, end) => (*start..=*end).contains(&char),
+            ClassItem::Digit => char.is_ascii_digit(),
+            ClassItem::Word => char.is_alphanumeric() || char == '_',
+            ClassItem::Space => char.is_whitespace(),
+            ClassItem::Not(item) => !item.matches(char),
+        }
+    }
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: pattern.chars().peekable(),
+            groups: 0,
+        };
+        let root = parser.alternation()?;
+        match parser.chars.next() {
+            Some(')') => Err("Unerwartete schließende Klammer `)`".to_string()),
+            Some(char) => unreachable!("the parser only stops at `)` or the end, found `{char}`"),
+            None => Ok(Self {
+                program: Compiler::compile(&root)?,
+                groups: parser.groups,
+            }),
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        self.find_at(&text, 0).is_some()
+    }
+
+    /// Returns the groups of the first match, unmatched groups are `None`.
+    pub fn captures(&self, text: &str) -> Option<Vec<Option<String>>> {
+        let text: Vec<char> = text.chars().collect();
+        let captures = self.find_at(&text, 0)?;
+        Some(
+            captures
+                .into_iter()
+                .map(|group| group.map(|(start, end)| text[start..end].iter().collect()))
+                .collect(),
+        )
+    }
+
+    /// Returns all non-overlapping matches in the text.
+    pub fn captures_iter(&self, text: &[char]) -> Vec<Captures> {
+        let mut matches = vec![];
+        let mut pos = 0;
+        while pos <= text.len() {
+            let Some(captures) = self.find_at(text, pos) else {
+                break;
+            };
+            let (start, end) = captures[0].expect("group 0 is always set");
+            // empty matches must not be found at the same position again
+            pos = if end == start { end + 1 } else { end };
+            matches.push(captures);
+        }
+        matches
+    }
+
+    /// Replaces all matches in the text.
+    /// The replacement may reference groups using `$1` or `${1}`, `$$` inserts a literal `$`.
+    pub fn replace_all(&self, text: &str, replacement: &str) -> Result<String, String> {
+        let replacement = self.parse_replacement(replacement)?;
+        let text: Vec<char> = text.chars().collect();
+
+        let mut output = String::new();
+        let mut last_end = 0;
+        for captures in self.captures_iter(&text) {
+            let (start, end) = captures[0].expect("group 0 is always set");
+            output.extend(&text[last_end..start]);
+            for part in &replacement {
+                match part {
+                    ReplacementPart::Literal(literal) => output.push_str(literal),
+                    ReplacementPart::Group(group) => {
+                        if let Some((start, end)) = captures[*group] {
+                            output.extend(&text[start..end]);
+                        }
+                    }
+                }
+            }
+            last_end = end;
+        }
+        output.extend(&text[last_end..]);
+
+        Ok(output)
+    }
+
+    fn parse_replacement(&self, replacement: &str) -> Result<Vec<ReplacementPart>, String> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut chars = replacement.chars().peekable();
+
+        while let Some(char) = chars.next() {
+            if char != '$' {
+                literal.push(char);
+                continue;
+            }
+
+            let braced = chars.next_if_eq(&'{').is_some();
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                digits.push(digit);
+            }
+
+            let group = match (braced, digits.as_str()) {
+                (false, "") if chars.next_if_eq(&'$').is_some() => {
+                    literal.push('$');
+                    continue;
+                }
+                (_, "") => {
+                    return Err("Nach `$` wird eine Gruppennummer oder `$` erwartet".to_string())
+                }
+                (true, _) if chars.next_if_eq(&'}').is_none() => {
+                    return Err(format!("Nicht geschlossene Gruppenreferenz `${{{digits}`"))
+                }
+                (_, digits) => digits.parse::<usize>().unwrap_or(usize::MAX),
+            };
+
+            if group > self.groups {
+                return Err(format!(
+                    "Die Ersetzung verweist auf die Gruppe `{digits}`, der Ausdruck enthält allerdings nur {} Gruppe(n)",
+                    self.groups
+                ));
+            }
+
+            parts.push(ReplacementPart::Literal(std::mem::take(&mut literal)));
+            parts.push(ReplacementPart::Group(group));
+        }
+        parts.push(ReplacementPart::Literal(literal));
+
+        Ok(parts)
+    }
+
+    /// Finds the leftmost match which starts at or after `start`.
+    /// Threads are kept in priority order, so the result equals the one of a backtracking engine.
+    fn find_at(&self, text: &[char], start: usize) -> Option<Captures> {
+        let vm = Vm {
+            program: &self.program,
+            text,
+        };
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut matched = None;
+
+        for pos in start..=text.len() {
+            // a new attempt which starts here has the lowest priority
+            if matched.is_none() {
+                vm.add(&mut current, 0, pos, vec![None; (self.groups + 1) * 2]);
+            }
+            if current.list.is_empty() && matched.is_some() {
+                break;
+            }
+
+            for (pc, slots) in current.list.drain(..) {
+                let accepted = match &vm.program[pc] {
+                    Inst::Char(expected) => text.get(pos) == Some(expected),
+                    Inst::Any => text.get(pos).is_some_and(|char| *char != '\n'),
+                    Inst::Class(items, negated) => text.get(pos).is_some_and(|char| {
+                        items.iter().any(|item| item.matches(*char)) != *negated
+                    }),
+                    Inst::Match => {
+                        // threads with a lower priority than this one are discarded
+                        matched = Some(slots);
+                        break;
+                    }
+                    inst => unreachable!("`add` never queues `{inst:?}`"),
+                };
+                if accepted {
+                    vm.add(&mut next, pc + 1, pos + 1, slots);
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+
+        matched.map(|slots| slots.chunks(2).map(|slot| slot[0].zip(slot[1])).collect())
+    }
+}
+
+enum ReplacementPart {
+    Literal(String),
+    Group(usize),
+}
+
+struct Parser<'pattern> {
+    chars: Peekable<Chars<'pattern>>,
+    groups: usize,
+}
+
+impl Parser<'_> {
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.concat()?];
+        while self.chars.next_if_eq(&'|').is_some() {
+            branches.push(self.concat()?);
+        }
+
+        Ok(match branches.len() {
+            1 => branches.pop().expect("there is exactly one branch"),
+            _ => Node::Alternation(branches),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = vec![];
+        while let Some(char) = self.chars.peek() {
+            if matches!(char, '|' | ')') {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantifier(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn quantifier(&mut self, node: Node) -> Result<Node, String> {
+        let (min, max) = match self.chars.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.chars.next();
+                let (min, max) = self.repetitions()?;
+                return self.finish_quantifier(node, min, max);
+            }
+            _ => return Ok(node),
+        };
+        self.chars.next();
+        self.finish_quantifier(node, min, max)
+    }
+
+    fn finish_quantifier(
+        &mut self,
+        node: Node,
+        min: usize,
+        max: Option<usize>,
+    ) -> Result<Node, String> {
+        if matches!(self.chars.peek(), Some('*' | '+' | '{')) {
+            return Err("Mehrere aufeinanderfolgende Quantoren".to_string());
+        }
+        let greedy = self.chars.next_if_eq(&'?').is_none();
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// Parses the contents of `{n}`, `{n,}` or `{n,m}` after the opening brace.
+    fn repetitions(&mut self) -> Result<(usize, Option<usize>), String> {
+        let invalid =
+            || "Ungültiger Quantor, erwartet wurde `{n}`, `{n,}` oder `{n,m}`".to_string();
+
+        let min = self.number()?;
+        let max = match self.chars.next() {
+            Some('}') => Some(min),
+            Some(',') if self.chars.next_if_eq(&'}').is_some() => None,
+            Some(',') => {
+                let max = self.number()?;
+                if self.chars.next() != Some('}') {
+                    return Err(invalid());
+                }
+                Some(max)
+            }
+            _ => return Err(invalid()),
+        };
+
+        if let Some(max) = max.filter(|max| *max < min) {
+            return Err(format!(
+                "Ungültiger Quantor `{{{min},{max}}}`: das Maximum ist kleiner als das Minimum"
+            ));
+        }
+        if max.unwrap_or(min) > MAX_REPETITIONS {
+            return Err(format!(
+                "Ungültiger Quantor: Es sind höchstens {MAX_REPETITIONS} Wiederholungen erlaubt"
+            ));
+        }
+
+        Ok((min, max))
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        let mut digits = String::new();
+        while let Some(digit) = self.chars.next_if(char::is_ascii_digit) {
+            digits.push(digit);
+        }
+        digits
+            .parse()
+            .map_err(|_| "Ungültiger Quantor, erwartet wurde eine Zahl".to_string())
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        match self
+            .chars
+            .next()
+            .expect("`concat` checks for the end of the input")
+        {
+            '(' => {
+                let index = if self.chars.next_if_eq(&'?').is_some() {
+                    if self.chars.next() != Some(':') {
+                        return Err("Nach `(?` wird `:` erwartet".to_string());
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+
+                let inner = self.alternation()?;
+                if self.chars.next() != Some(')') {
+                    return Err("Nicht geschlossene Gruppe, erwartet wurde `)`".to_string());
+                }
+                Ok(Node::Group(Box::new(inner), index))
+            }
+            '[' => self.class(),
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '\\' => match self.escape()? {
+                Escape::Char(char) => Ok(Node::Char(char)),
+                Escape::Class(item) => Ok(Node::Class(vec![item], false)),
+            },
+            quantifier @ ('*' | '+' | '?' | '{') => Err(format!(
+                "Der Quantor `{quantifier}` folgt auf keinen Ausdruck"
+            )),
+            char => Ok(Node::Char(char)),
+        }
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.chars.next_if_eq(&'^').is_some();
+        let mut items = vec![];
+
+        loop {
+            let start = match self.chars.next() {
+                Some(']') if !items.is_empty() => break,
+                Some('\\') => match self.escape()? {
+                    Escape::Char(char) => char,
+                    Escape::Class(item) => {
+                        items.push(item);
+                        continue;
+                    }
+                },
+                Some(char) => char,
+                None => {
+                    return Err("Nicht geschlossene Zeichenklasse, erwartet wurde `]`".to_string())
+                }
+            };
+
+            let mut lookahead = self.chars.clone();
+            let end = match (lookahead.next(), lookahead.next()) {
+                (Some('-'), Some(end)) if end != ']' => {
+                    self.chars.next();
+                    self.chars.next();
+                    end
+                }
+                _ => start,
+            };
+            if end < start {
+                return Err(format!(
+                    "Ungültiger Bereich `{start}-{end}` in Zeichenklasse"
+                ));
+            }
+            items.push(ClassItem::Range(start, end));
+        }
+
+        Ok(Node::Class(items, negated))
+    }
+
+    fn escape(&mut self) -> Result<Escape, String> {
+        Ok(match self.chars.next() {
+            Some('d') => Escape::Class(ClassItem::Digit),
+            Some('w') => Escape::Class(ClassItem::Word),
+            Some('s') => Escape::Class(ClassItem::Space),
+            Some('D') => Escape::Class(ClassItem::Not(Box::new(ClassItem::Digit))),
+            Some('W') => Escape::Class(ClassItem::Not(Box::new(ClassItem::Word))),
+            Some('S') => Escape::Class(ClassItem::Not(Box::new(ClassItem::Space))),
+            Some('n') => Escape::Char('\n'),
+            Some('t') => Escape::Char('\t'),
+            Some('r') => Escape::Char('\r'),
+            Some(char) if !char.is_alphanumeric() => Escape::Char(char),
+            Some(char) => return Err(format!("Unbekannte Escape-Sequenz `\\{char}`")),
+            None => return Err("Der Ausdruck endet mit einem einzelnen `\\`".to_string()),
+        })
+    }
+}
+
+enum Escape {
+    Char(char),
+    Class(ClassItem),
+}
+
+#[derive(Debug)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    /// Stores the current position in the given slot, group `n` uses the slots `2n` and `2n + 1`.
+    Save(usize),
+    /// Continues at both targets, the first one is preferred.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn compile(root: &Node) -> Result<Vec<Inst>, String> {
+        let mut compiler = Self { program: vec![] };
+        compiler.emit(Inst::Save(0))?;
+        compiler.node(root)?;
+        compiler.emit(Inst::Save(1))?;
+        compiler.emit(Inst::Match)?;
+        Ok(compiler.program)
+    }
+
+    fn emit(&mut self, inst: Inst) -> Result<usize, String> {
+        if self.program.len() >= MAX_INSTRUCTIONS {
+            return Err(format!(
+                "Der Ausdruck ist zu groß, er darf höchstens {MAX_INSTRUCTIONS} Anweisungen ergeben"
+            ));
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn node(&mut self, node: &Node) -> Result<(), String> {
+        match node {
+            Node::Char(char) => _ = self.emit(Inst::Char(*char))?,
+            Node::Any => _ = self.emit(Inst::Any)?,
+            Node::Class(items, negated) => _ = self.emit(Inst::Class(items.clone(), *negated))?,
+            Node::Start => _ = self.emit(Inst::Start)?,
+            Node::End => _ = self.emit(Inst::End)?,
+            Node::Group(inner, None) => self.node(inner)?,
+            Node::Group(inner, Some(index)) => {
+                self.emit(Inst::Save(index * 2))?;
+                self.node(inner)?;
+                self.emit(Inst::Save(index * 2 + 1))?;
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.node(node)?;
+                }
+            }
+            Node::Alternation(branches) => {
+                let (last, rest) = branches
+                    .split_last()
+                    .expect("alternations have at least two branches");
+                let mut jumps = vec![];
+                for branch in rest {
+                    let split = self.emit(Inst::Split(0, 0))?;
+                    self.node(branch)?;
+                    jumps.push(self.emit(Inst::Jump(0))?);
+                    self.program[split] = Inst::Split(split + 1, self.program.len());
+                }
+                self.node(last)?;
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(self.program.len());
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    let len = self.program.len();
+                    self.node(node)?;
+                    // repeating a node which compiles to nothing, e.g. `(?:)`, is pointless
+                    if self.program.len() == len {
+                        break;
+                    }
+                }
+                match max {
+                    None => {
+                        let split = self.emit(Inst::Split(0, 0))?;
+                        self.node(node)?;
+                        self.emit(Inst::Jump(split))?;
+                        self.program[split] = Self::split(*greedy, split + 1, self.program.len());
+                    }
+                    Some(max) => {
+                        let mut splits = vec![];
+                        for _ in *min..*max {
+                            splits.push(self.emit(Inst::Split(0, 0))?);
+                            self.node(node)?;
+                        }
+                        for split in splits {
+                            self.program[split] =
+                                Self::split(*greedy, split + 1, self.program.len());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Greedy quantifiers prefer another iteration over stopping, lazy ones the opposite.
+    fn split(greedy: bool, iterate: usize, stop: usize) -> Inst {
+        match greedy {
+            true => Inst::Split(iterate, stop),
+            false => Inst::Split(stop, iterate),
+        }
+    }
+}
+
+type Slots = Vec<Option<usize>>;
+
+/// The threads of one position in priority order.
+struct Threads {
+    /// Whether a thread already reached an instruction at this position.
+    /// Reaching it again can only yield a match with a lower priority, which also prevents
+    /// empty loop iterations from running forever.
+    visited: Vec<bool>,
+    list: Vec<(usize, Slots)>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Self {
+            visited: vec![false; len],
+            list: vec![],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.visited.fill(false);
+        self.list.clear();
+    }
+}
+
+struct Vm<'prog, 'text> {
+    program: &'prog [Inst],
+    text: &'text [char],
+}
+
+impl Vm<'_, '_> {
+    /// Follows all instructions from `pc` which do not consume a character and queues the
+    /// threads which wait for the next character or reached the end of the program.
+    fn add(&self, threads: &mut Threads, pc: usize, pos: usize, slots: Slots) {
+        let mut stack = vec![(pc, slots)];
+        while let Some((pc, mut slots)) = stack.pop() {
+            if std::mem::replace(&mut threads.visited[pc], true) {
+                continue;
+            }
+            match &self.program[pc] {
+                Inst::Start if pos != 0 => {}
+                Inst::End if pos != self.text.len() => {}
+                Inst::Start | Inst::End => stack.push((pc + 1, slots)),
+                Inst::Save(slot) => {
+                    slots[*slot] = Some(pos);
+                    stack.push((pc + 1, slots));
+                }
+                Inst::Split(first, second) => {
+                    // the first target is popped and therefore explored first
+                    stack.push((*second, slots.clone()));
+                    stack.push((*first, slots));
+                }
+                Inst::Jump(target) => stack.push((*target, slots)),
+                Inst::Char(_) | Inst::Any | Inst::Class(..) | Inst::Match => {
+                    threads.list.push((pc, slots))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Regex;
+
+    #[test]
+    fn matching() {
+        for (pattern, text, expected) in [
+            ("a+b", "xaab", true),
+            ("^a+b$", "xaab", false),
+            ("^(ab|cd)*$", "abcdab", true),
+            ("[a-c]{2,3}x", "abx", true),
+            ("[^0-9]", "123", false),
+            ("\\d+\\.\\d+", "Pi ist 3.14", true),
+            ("colou?r", "color", true),
+            ("(a*)*b", "aaaa", false),
+        ] {
+            let regex = Regex::new(pattern).unwrap();
+            assert_eq!(regex.is_match(text), expected, "`{pattern}` on `{text}`");
+        }
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        for pattern in [
+            "(a",
+            "a)",
+            "[a-",
+            "*a",
+            "a{3,1}",
+            "\\q",
+            "a**",
+            "a{1001}",
+            "a{1001,}",
+            "a{0,1001}",
+            "(?:){999999999999}a",
+            "(?:x{0}){999999999999}",
+        ] {
+            assert!(
+                Regex::new(pattern).is_err(),
+                "`{pattern}` should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn replacing() {
+        let regex = Regex::new("(\\w+)@(\\w+)").unwrap();
+        assert_eq!(
+            regex.replace_all("a@b, c@d", "$2 bei ${1}$$"),
+            Ok("b bei a$, d bei c$".to_string())
+        );
+        assert!(regex.replace_all("a@b", "$3").is_err());
+
+        let lazy = Regex::new("<.+?>").unwrap();
+        assert_eq!(lazy.replace_all("<a><b>", "_"), Ok("__".to_string()));
+
+        let empty = Regex::new("x*").unwrap();
+        assert_eq!(empty.replace_all("abc", "-"), Ok("-a-b-c-".to_string()));
+    }
+
+    #[test]
+    fn long_inputs() {
+        let text = "a".repeat(200_000);
+        assert!(Regex::new("^a*$").unwrap().is_match(&text));
+        assert_eq!(
+            Regex::new("a*").unwrap().replace_all(&text, "b"),
+            Ok("bb".to_string())
+        );
+
+        let start = std::time::Instant::now();
+        assert!(!Regex::new("(a*)*b").unwrap().is_match(&"a".repeat(28)));
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        assert!(Regex::new("((a{1000}){1000}){1000}").is_err());
+
+        let start = std::time::Instant::now();
+        let empty = Regex::new("(?:(?:(?:x{0}){1000}){1000}){1000}a").unwrap();
+        assert!(empty.is_match("a"));
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn captures() {
+        let regex = Regex::new("(a|b(c))*?(d)?$").unwrap();
+        assert_eq!(
+            regex.captures("abcd"),
+            Some(vec![
+                Some("abcd".to_string()),
+                Some("bc".to_string()),
+                Some("c".to_string()),
+                Some("d".to_string()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,3143 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
+
+use hpi_analyzer::{
+    ast::{AnalyzedExpression, AnalyzedStatement},
+    DiagnosticLevel,
+};
+
+use crate::{
+    value::Value, BreakpointOutcome, Determinismus, FloatNotation, HPIHttpClient, HttpError,
+    IndexTarget, Interpreter, InterpreterBuilder, PointerNotation, RunError, RunOutcome,
+    RuntimeError, Statistics, StepOutcome,
+};
+
+struct NoHttpClient;
+
+impl HPIHttpClient for NoHttpClient {
+    fn request(
+        &self,
+        _method: String,
+        _url: &str,
+        _body: String,
+        _headers: HashMap<String, String>,
+        _timeout: Duration,
+    ) -> Result<(u16, String, HashMap<String, String>), HttpError> {
+        Err(HttpError::Other("Kein Netzwerk verfügbar".to_string()))
+    }
+}
+
+/// Wraps the given statements in a minimal program whose `Studium` contains `body`.
+/// `prelude` is inserted before the main functions and may contain imports or functions.
+fn program(prelude: &str, body: &str) -> String {
+    format!(
+        "{prelude}
+funk Bewerbung() ergibt Zeichenkette {{ \"Bitte nehmt mich!\" }}
+funk Einschreibung(Zahl Matrikelnummer) ergibt Nichts {{ setze Zahl _Nummer auf Matrikelnummer; }}
+funk Studium() ergibt Nichts {{
+{body}
+}}
+"
+    )
+}
+
+/// Runs the program and returns its exit code and output.
+fn run(source: &str) -> (Result<i64, String>, String) {
+    let mut output = vec![];
+    let res = crate::run(
+        source,
+        "test.hpi",
+        &mut output,
+        NoHttpClient,
+        HashMap::new(),
+    );
+    let res = match res {
+        Ok((code, _)) => Ok(code),
+        Err(RunError::Runtime(err, _)) => Err(err.to_string()),
+        Err(RunError::Analyzer(diagnostics)) => panic!("analyzer errors: {diagnostics:#?}"),
+    };
+    (
+        res,
+        String::from_utf8(output).expect("output is valid UTF-8"),
+    )
+}
+
+#[test]
+fn run_steps_drives_loop_to_completion() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+
+        funk Zähle_Herunter(Zahl Start) ergibt Nichts {
+            setze Zahl Wert auf Start;
+            solange Wert > 0 {
+                Drucke(Wert);
+                ändere Wert auf Wert - 1;
+            }
+        }",
+        "setze Zahl Zähler auf 0;
+        solange Zähler < 5 {
+            ändere Zähler auf Zähler + 1;
+            Drucke(Zähler);
+        }
+        Zähle_Herunter(2);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let mut stepper =
+        Interpreter::new(&mut output, NoHttpClient, HashMap::new()).into_stepper(&tree);
+
+    let mut slices = 0;
+    let code = loop {
+        slices += 1;
+        match stepper.run_steps(3).expect("program does not fail") {
+            StepOutcome::Pending => continue,
+            StepOutcome::Finished(code) => break code,
+        }
+    };
+    drop(stepper);
+
+    assert_eq!(code, 0);
+    assert!(slices > 5, "the loop should require multiple slices");
+    assert_eq!(String::from_utf8(output).unwrap(), "1\n2\n3\n4\n5\n2\n1\n");
+}
+
+#[test]
+fn run_steps_stops_at_error() {
+    let source = program(
+        "beantrage Drucke von Drucker;",
+        "setze Zahl Null auf 0;
+        Drucke(\"vor\");
+        Drucke(1 : Null);
+        Drucke(\"nach dem Fehler\");",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let mut stepper =
+        Interpreter::new(&mut output, NoHttpClient, HashMap::new()).into_stepper(&tree);
+    assert_eq!(stepper.run_steps(100), Err(RuntimeError::DivisionByZero));
+    assert_eq!(stepper.run_steps(100), Err(RuntimeError::DivisionByZero));
+    assert_eq!(
+        stepper.run_to_breakpoint(),
+        Err(RuntimeError::DivisionByZero)
+    );
+    drop(stepper);
+
+    assert_eq!(String::from_utf8(output).unwrap(), "vor\n");
+}
+
+#[test]
+fn run_to_breakpoint_inspects_variables() {
+    let source = "beantrage Drucke von Drucker;
+funk Bewerbung() ergibt Zeichenkette { \"Bitte nehmt mich!\" }
+funk Einschreibung(Zahl Matrikelnummer) ergibt Nichts {}
+funk Studium() ergibt Nichts {
+    setze Zahl Summe auf 0;
+    setze Zahl Zähler auf 1;
+    solange Zähler <= 3 {
+        ändere Summe auf Summe + Zähler;
+        ändere Zähler auf Zähler + 1;
+    }
+    Drucke(Summe);
+}
+";
+    let (tree, _) = hpi_analyzer::analyze(source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let mut stepper =
+        Interpreter::new(&mut output, NoHttpClient, HashMap::new()).into_stepper(&tree);
+    stepper.add_breakpoint(9);
+
+    let mut sums = vec![];
+    let code = loop {
+        match stepper.run_to_breakpoint().expect("program does not fail") {
+            BreakpointOutcome::Breakpoint(line) => {
+                assert_eq!(line, 9);
+                let variables = stepper.variables();
+                assert_eq!(variables["Zähler"], Value::Int(sums.len() as i64 + 1));
+                sums.push(variables["Summe"].clone());
+                // the last iteration runs without stopping
+                if sums.len() == 2 {
+                    stepper.remove_breakpoint(9);
+                }
+            }
+            BreakpointOutcome::Finished(code) => break code,
+        }
+    };
+    drop(stepper);
+
+    assert_eq!(code, 0);
+    assert_eq!(sums, [Value::Int(1), Value::Int(3)]);
+    assert_eq!(String::from_utf8(output).unwrap(), "6\n");
+}
+
+#[test]
+fn run_steps_repeats_tail_calls() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+
+        funk Zähle(Zahl Wert) ergibt Zahl {
+            Drucke(Wert);
+            falls Wert == 0 {
+                überweise 0;
+            }
+            überweise Zähle(Wert - 1);
+        }",
+        "Zähle(3);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let mut stepper =
+        Interpreter::new(&mut output, NoHttpClient, HashMap::new()).into_stepper(&tree);
+    assert_eq!(stepper.run_steps(100), Ok(StepOutcome::Finished(0)));
+    drop(stepper);
+
+    assert_eq!(String::from_utf8(output).unwrap(), "3\n2\n1\n0\n");
+}
+
+#[test]
+fn run_steps_stops_at_exit() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage Aufgeben von libSAP;",
+        "solange ja {
+            Drucke(\"einmal\");
+            Aufgeben(3);
+        }",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let mut stepper =
+        Interpreter::new(&mut output, NoHttpClient, HashMap::new()).into_stepper(&tree);
+    assert_eq!(stepper.run_steps(100), Ok(StepOutcome::Finished(3)));
+    assert_eq!(stepper.run_steps(1), Ok(StepOutcome::Finished(3)));
+    drop(stepper);
+
+    assert_eq!(String::from_utf8(output).unwrap(), "einmal\n");
+}
+
+#[test]
+fn run_matches_stepper() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Aufgeben von libSAP;
+
+        funk Quadrat(Zahl Wert) ergibt Zahl { Wert * Wert }
+
+        funk Zeige(Zahl Wert) ergibt Nichts {
+            Drucke(Wert / Quadrat(Wert));
+        }",
+        "setze Zahl Zähler auf 0;
+        solange Zähler < 3 {
+            ändere Zähler auf Zähler + 1;
+            Zeige(Zähler);
+        }
+        Aufgeben(Quadrat(Zähler));",
+    );
+    let (res, output) = run(&source);
+    assert_eq!(res, Ok(9));
+    assert_eq!(output, "1 1\n2 4\n3 9\n");
+
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let mut stepped = vec![];
+    let mut stepper =
+        Interpreter::new(&mut stepped, NoHttpClient, HashMap::new()).into_stepper(&tree);
+    let code = loop {
+        match stepper.run_steps(2).expect("program does not fail") {
+            StepOutcome::Pending => continue,
+            StepOutcome::Finished(code) => break code,
+        }
+    };
+    drop(stepper);
+
+    assert_eq!(Ok(code), res);
+    assert_eq!(String::from_utf8(stepped).unwrap(), output);
+}
+
+#[test]
+fn calls_resolve_functions_defined_around_main_functions() {
+    let source = "beantrage Drucke von Drucker;
+funk Eins() ergibt Zahl { 1 }
+funk Bewerbung() ergibt Zeichenkette { \"Bitte nehmt mich!\" }
+funk Zwei() ergibt Zahl { Eins() + 1 }
+funk Einschreibung(Zahl Matrikelnummer) ergibt Nichts {}
+funk Studium() ergibt Nichts { Drucke(Eins() / Zwei() / Drei()); }
+funk Drei() ergibt Zahl { Zwei() + Eins() }
+";
+    let (res, output) = run(source);
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "1 2 3\n");
+}
+
+#[test]
+fn versuche_catches_runtime_errors() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Versuche von libSAP;
+
+        funk Teile() ergibt Zahl {
+            setze Zahl Null auf 0;
+            42 : Null
+        }
+
+        funk Antwort() ergibt Zahl { 42 }",
+        "setze Versuchsergebnis Fehlschlag auf Versuche(Teile);
+        Drucke(Fehlschlag.Erfolg / Fehlschlag.Fehler);
+
+        setze Versuchsergebnis Ergebnis auf Versuche(Antwort);
+        Drucke(Ergebnis.Erfolg / Ergebnis.Wert als Zahl / Ergebnis.Fehler);",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "false division by zero\ntrue 42 \n");
+}
+
+#[test]
+fn versuche_propagates_exit() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Versuche von libSAP;
+        beantrage Aufgeben von libSAP;
+
+        funk Beende() ergibt Nichts { Aufgeben(7); }",
+        "Versuche(Beende);
+        Drucke(\"unerreichbar\");",
+    ));
+    assert_eq!(res, Ok(7));
+    assert_eq!(output, "");
+}
+
+#[test]
+fn drucke_kanal_routes_to_registered_writer() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage DruckeKanal von Drucker;",
+        "Drucke(\"normal\");
+        DruckeKanal(\"protokoll\" / \"Wert:\" / 42);
+        DruckeKanal(\"unbekannt\" / \"zurückgefallen\");",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let mut log = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_channel("protokoll", &mut log)
+        .build()
+        .run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(String::from_utf8(log).unwrap(), "Wert: 42\n");
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "normal\nzurückgefallen\n"
+    );
+}
+
+#[test]
+fn drucke_uses_custom_separator_and_terminator() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage Schreibe von Drucker;",
+        "Drucke(\"Name\" / \"Semester\");
+        Drucke(\"Lena\" / 3 / 1,5);
+        Drucke();
+        Schreibe(\"a\" / \"b\");",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_drucke_separator(";")
+        .with_drucke_terminator("\r\n")
+        .build()
+        .run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "Name;Semester\r\nLena;3;1,5\r\n\r\na;b"
+    );
+}
+
+#[test]
+fn fehlerdrucke_writes_to_error_output() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage Fehlerdrucke von Drucker;",
+        "Drucke(\"Ergebnis\" / 42);
+        Fehlerdrucke(\"Warnung:\" / 1,5);",
+    );
+
+    let (res, output) = run(&source);
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "Ergebnis 42\nWarnung: 1,5\n");
+
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let mut output = vec![];
+    let mut errors = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_error_output(&mut errors)
+        .build()
+        .run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(String::from_utf8(output).unwrap(), "Ergebnis 42\n");
+    assert_eq!(String::from_utf8(errors).unwrap(), "Warnung: 1,5\n");
+}
+
+#[test]
+fn output_is_buffered_until_flushed() {
+    /// Records the data of every call of `write`.
+    #[derive(Default)]
+    struct Writes(Vec<String>);
+
+    impl std::io::Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.push(String::from_utf8_lossy(buf).into_owned());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage Leere_Ausgabe von Drucker;",
+        "Drucke(\"a\");
+        Drucke(\"b\");
+        Leere_Ausgabe();
+        Drucke(\"c\");",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut writes = Writes::default();
+    let code = Interpreter::new(&mut writes, NoHttpClient, HashMap::new()).run(tree.clone());
+    assert_eq!(code, Ok(0));
+    assert_eq!(writes.0, ["a\nb\n", "c\n"]);
+
+    let mut writes = Writes::default();
+    let code = InterpreterBuilder::new(&mut writes, NoHttpClient)
+        .with_output_buffer_capacity(0)
+        .build()
+        .run(tree);
+    assert_eq!(code, Ok(0));
+    assert_eq!(writes.0, ["a\n", "b\n", "c\n"]);
+}
+
+#[test]
+fn output_is_flushed_before_other_writers() {
+    /// Records the data of every call of `write` on any of its clones in the same log.
+    #[derive(Clone, Default)]
+    struct Writes(Rc<RefCell<Vec<String>>>);
+
+    impl std::io::Write for Writes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .borrow_mut()
+                .push(String::from_utf8_lossy(buf).into_owned());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage DruckeKanal von Drucker;
+        beantrage Fehlerdrucke von Drucker;",
+        "Drucke(\"a\");
+        DruckeKanal(\"protokoll\" / \"b\");
+        Drucke(\"c\");
+        Fehlerdrucke(\"d\");
+        Drucke(\"e\");
+        Schlummere(0,0);
+        Drucke(\"f\");",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let writes = Writes::default();
+    let code = InterpreterBuilder::new(writes.clone(), NoHttpClient)
+        .with_channel("protokoll", writes.clone())
+        .with_error_output(writes.clone())
+        .build()
+        .run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(
+        *writes.0.borrow(),
+        ["a\n", "b\n", "c\n", "d\n", "e\n", "f\n"]
+    );
+}
+
+#[test]
+fn drucke_kanal_strict_rejects_unknown_channel() {
+    let source = program(
+        "beantrage DruckeKanal von Drucker;",
+        "DruckeKanal(\"unbekannt\" / 1);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let res = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_strict_channels(true)
+        .build()
+        .run(tree);
+
+    assert_eq!(res, Err("Unbekannter Ausgabekanal `unbekannt`.".into()));
+    assert!(output.is_empty());
+}
+
+#[test]
+fn als_liste_splits_strings() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage AlsListe von libSAP;",
+        "setze Liste von Zeichenkette Zeichen auf AlsListe(\"Hallö\");
+        Drucke(Zeichen.Länge() / Zeichen);",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "5 [H / a / l / l / ö]\n");
+}
+
+#[test]
+fn als_liste_keeps_lists() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage AlsListe von libSAP;",
+        "setze Liste von Zahl Zahlen auf [1 / 2];
+        setze Liste von Zahl Kopie auf AlsListe(Zahlen);
+        Kopie.Hinzufügen(3);
+        Drucke(Zahlen);",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "[1 / 2 / 3]\n");
+}
+
+#[test]
+fn als_liste_lists_object_entries() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage AlsListe von libSAP;",
+        "setze Objekt { Zeichenkette b / Zeichenkette a } Wert auf erstelle { Zeichenkette b auf \"y\" / Zeichenkette a auf \"x\" };
+        setze Liste von Eintrag Einträge auf AlsListe(Wert);
+        setze Zahl Index auf 0;
+        solange Index < Einträge.Länge() {
+            Drucke(Einträge[Index].Schlüssel / Einträge[Index].Wert als Zeichenkette);
+            ändere Index auf Index + 1;
+        }",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "a x\nb y\n");
+}
+
+#[test]
+fn als_liste_lists_speicherbox_entries() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage AlsListe von libSAP;
+        beantrage Umgebungsvariablen von libSAP;",
+        "setze Liste von Eintrag Einträge auf AlsListe(Umgebungsvariablen());
+        Drucke(Einträge.Länge() / Einträge[0].Schlüssel / Einträge[1].Wert als Zeichenkette);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let env = HashMap::from([
+        ("ZWEI".to_string(), "2".to_string()),
+        ("EINS".to_string(), "1".to_string()),
+    ]);
+    let code = Interpreter::new(&mut output, NoHttpClient, env).run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(String::from_utf8(output).unwrap(), "2 EINS 2\n");
+}
+
+#[test]
+fn als_liste_rejects_numbers() {
+    let source = program(
+        "beantrage AlsListe von libSAP;",
+        "setze Liste von Zahl Zahlen auf AlsListe(42);",
+    );
+    assert!(hpi_analyzer::analyze(&source, "test.hpi").is_err());
+}
+
+#[test]
+fn summe_tolerates_intermediate_overflow() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Summe von Mathe;",
+        "setze Zahl Groß auf 9223372036854775807;
+        Drucke(Summe([Groß / Groß / 0 - Groß / 0 - Groß / 5]));
+        Drucke(Summe([]));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "5\n0\n");
+}
+
+#[test]
+fn summe_und_durchschnitt_of_floats() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Summe von Mathe;
+        beantrage Durchschnitt von Mathe;",
+        "setze Liste von Fließkommazahl Werte auf [10000000000000000,0 / 1,0 / 0,0 - 10000000000000000,0];
+        Drucke(Summe(Werte) / Summe([1,5 / 2,25]) / Durchschnitt([1,5 / 2,5]));
+        Drucke(Durchschnitt([1 / 2]) / Durchschnitt([9223372036854775807 / 9223372036854775807]) == 9223372036854775807,0);
+        setze Liste von Fließkommazahl Leer auf [];
+        Drucke(Summe(Leer) + 0,5);
+        setze Liste von Zahl Keine auf [];
+        Drucke(Durchschnitt(Keine));",
+    ));
+    assert_eq!(
+        res,
+        Err("`Durchschnitt` kann nicht auf eine leere Liste angewendet werden.".into())
+    );
+    assert_eq!(output, "1 3,75 2\n1,5 true\n0,5\n");
+}
+
+#[test]
+fn summe_rejects_overflowing_result() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Summe von Mathe;",
+        "setze Zahl Groß auf 9223372036854775807;
+        Drucke(Summe([Groß / 1]));",
+    ));
+    assert_eq!(
+        res,
+        Err("Ganzzahlüberlauf bei `Summe`: Das Ergebnis `9223372036854775808` passt nicht in eine `Zahl`.".into())
+    );
+    assert_eq!(output, "");
+
+    let (res, _) = run(&program(
+        "beantrage Summe von Mathe;",
+        "setze Zahl Klein auf 0 - 9223372036854775807;
+        Summe([Klein / 0 - 2]);",
+    ));
+    assert_eq!(
+        res,
+        Err("Ganzzahlüberlauf bei `Summe`: Das Ergebnis `-9223372036854775809` passt nicht in eine `Zahl`.".into())
+    );
+}
+
+#[test]
+fn floats_are_displayed_without_exponent() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;",
+        "Drucke(1,5 / 10000000000000000000000,0 / 0,0000001);",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "1,5 10000000000000000000000 0,0000001\n");
+}
+
+#[test]
+fn formatiere_pads_and_aligns() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Formatiere von Textverarbeitung;",
+        "Drucke(Formatiere(\"[%5d] [%05d] [%-5d] [%2d]\" / 42 / 0 - 42 / 42 / 12345));
+        Drucke(Formatiere(\"[%6s] [%-6s] [%6v] [%-6t]\" / \"Grüße\" / \"Grüße\" / 1,5 / ja));
+        Drucke(Formatiere(\"[%s] [%0d]\" / \"ohne\" / 7));
+        Drucke(Formatiere(\"%05s\" / \"Text\"));",
+    ));
+    assert_eq!(
+        res,
+        Err("Formatierungsfehler: Die Formatierungsanweisung `s` kann nicht mit Nullen aufgefüllt werden, dies ist nur für Zahlen möglich.".into())
+    );
+    assert_eq!(
+        output,
+        "[00042] [-0042] [42   ] [12345]\n[ Grüße] [Grüße ] [   1,5] [true  ]\n[ohne] [7]\n"
+    );
+
+    let (res, _) = run(&program(
+        "beantrage Formatiere von Textverarbeitung;",
+        "Formatiere(\"%-05d\" / 1);",
+    ));
+    assert_eq!(
+        res,
+        Err("Formatierungsfehler: Linksbündig ausgerichtete Werte können nicht mit Nullen aufgefüllt werden.".into())
+    );
+
+    let (res, _) = run(&program(
+        "beantrage Formatiere von Textverarbeitung;",
+        "Formatiere(\"%99999999999999999999d\" / 1);",
+    ));
+    assert_eq!(
+        res,
+        Err("Formatierungsfehler: Die Breite `99999999999999999999` ist zu groß.".into())
+    );
+
+    for directive in ["%70000d", "%-70000s"] {
+        let (res, _) = run(&program(
+            "beantrage Formatiere von Textverarbeitung;",
+            &format!("Formatiere(\"{directive}\" / 1);"),
+        ));
+        assert_eq!(
+            res,
+            Err("Formatierungsfehler: Die Breite `70000` ist zu groß.".into())
+        );
+    }
+
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Formatiere von Textverarbeitung;",
+        "Drucke(Formatiere(\"%65535d\" / 1) == Formatiere(\"%065535d\" / 1));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true\n");
+}
+
+#[test]
+fn formatiere_writes_integers_in_other_bases() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Formatiere von Textverarbeitung;",
+        "Drucke(Formatiere(\"%x %X %b %o\" / 255 / 255 / 5 / 8));
+        Drucke(Formatiere(\"%x %X %b %o\" / 0 / 0 / 0 / 0));
+        Drucke(Formatiere(\"%x %b %04X %-4o|\" / 0 - 255 / 0 - 2 / 0 - 10 / 0 - 8));
+        Drucke(Formatiere(\"%08b %2x\" / 5 / 3054));
+        Drucke(Formatiere(\"%x\" / 1,5));",
+    ));
+    assert_eq!(
+        res,
+        Err("Formatierungsfehler: Unzulässige Kombination aus Formatierungsanweisung `x`, Pufferung `0` und Eingabewert mit dem Datentyp `Fliesskommazahl`".into())
+    );
+    assert_eq!(
+        output,
+        "ff FF 101 10\n0 0 0 0\n-ff -10 -00A -10 |\n00000101 bee\n"
+    );
+}
+
+#[test]
+fn formatiere_escapes_percent_signs() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Formatiere von Textverarbeitung;",
+        "Drucke(Formatiere(\"%%wert%% = %d\" / 42));
+        Drucke(Formatiere(\"{wert} = %d%%, {{}}\" / 42));
+        Drucke(Formatiere(\"%%%s%%\" / \"x\"));
+        Drucke(Formatiere(\"100%\"));",
+    ));
+    assert_eq!(
+        res,
+        Err("Formatierungsfehler: Erwartete Formatierungsanweisung, allerdings endet hier die Eingabe.".into())
+    );
+    assert_eq!(output, "%wert% = 42\n{wert} = 42%, {{}}\n%x%\n");
+}
+
+#[test]
+fn formatiere_resolves_named_placeholders() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Formatiere von Textverarbeitung;
+        beantrage Setze von libSAP;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "setze Speicherbox Werte auf Zergliedere_JSON(\"{}\") als Speicherbox;
+        Setze(Werte / \"Name\" / \"Lena\");
+        Setze(Werte / \"Punkte\" / 7);
+        Drucke(Formatiere(\"%(Name)s hat %(Punkte)03d Punkte, %(Name)-6s|\" / Werte));
+        setze Objekt { Zeichenkette Name / Zahl Semester } Lena auf erstelle {
+            Zeichenkette Name auf \"Lena\" / Zahl Semester auf 3
+        };
+        Drucke(Formatiere(\"%(Name)s ist im %(Semester)d. Semester\" / Lena));
+        Drucke(Formatiere(\"%(Note)d\" / Werte));",
+    ));
+    assert_eq!(
+        res,
+        Err("Formatierungsfehler: Der benannte Platzhalter `Note` ist nicht im ersten Argument enthalten.".into())
+    );
+    assert_eq!(
+        output,
+        "Lena hat 007 Punkte, Lena  |\nLena ist im 3. Semester\n"
+    );
+
+    let (res, _) = run(&program(
+        "beantrage Formatiere von Textverarbeitung;",
+        "Formatiere(\"%(Name)s\" / \"Lena\");",
+    ));
+    assert_eq!(
+        res,
+        Err("Formatierungsfehler: Der benannte Platzhalter `Name` benötigt eine Speicherbox oder ein Objekt als erstes Argument, allerdings wurde ein Wert mit dem Datentyp `Zeichenkette` übergeben.".into())
+    );
+
+    let (res, _) = run(&program(
+        "beantrage Formatiere von Textverarbeitung;",
+        "Formatiere(\"%(Name\");",
+    ));
+    assert_eq!(
+        res,
+        Err("Formatierungsfehler: Erwartete `)` nach dem Namen des Platzhalters `Name`, allerdings endet hier die Eingabe.".into())
+    );
+}
+
+#[test]
+fn floats_can_use_scientific_notation() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage Formatiere von Textverarbeitung;",
+        "Drucke(1,5 / 10000000000000000000000,0 / 0,0000001);
+        Drucke(Formatiere(\"%v\" / [0,00025]));",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_float_notation(FloatNotation::Scientific)
+        .build()
+        .run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "1,5e0 1e22 1e-7\n[2,5e-4]\n"
+    );
+}
+
+#[test]
+fn groesse_von_and_ist_leer_for_objects() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage GrößeVon von libSAP;
+        beantrage IstLeer von libSAP;",
+        "setze Objekt { Zahl a / Zahl b } Voll auf erstelle { Zahl a auf 1 / Zahl b auf 2 };
+        setze Objekt {} Leer auf erstelle {};
+        Drucke(GrößeVon(Voll) / IstLeer(Voll));
+        Drucke(GrößeVon(Leer) / IstLeer(Leer));
+        Drucke(IstLeer(\"\") / IstLeer([1]));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "2 false\n0 true\ntrue false\n");
+}
+
+#[test]
+fn groesse_von_and_ist_leer_for_speicherboxen() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage GrößeVon von libSAP;
+        beantrage IstLeer von libSAP;
+        beantrage Umgebungsvariablen von libSAP;",
+        "setze Speicherbox Variablen auf Umgebungsvariablen();
+        Drucke(GrößeVon(Variablen) / IstLeer(Variablen));",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    for (env, expected) in [
+        (HashMap::new(), "0 true\n"),
+        (
+            HashMap::from([
+                ("A".to_string(), "1".to_string()),
+                ("B".to_string(), "2".to_string()),
+            ]),
+            "2 false\n",
+        ),
+    ] {
+        let mut output = vec![];
+        let code = Interpreter::new(&mut output, NoHttpClient, env).run(tree.clone());
+        assert_eq!(code, Ok(0));
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
+
+#[test]
+fn ersetze_alle_regex_with_group_references() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage ErsetzeAlleRegex von Textverarbeitung;",
+        "Drucke(ErsetzeAlleRegex(\"31.12.2023 und 1.5.2024\" / \"([0-9]+)[.]([0-9]+)[.]([0-9]{4})\" / \"$3-$2-${1}\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "2023-12-31 und 2024-5-1\n");
+}
+
+#[test]
+fn ersetze_alle_regex_rejects_invalid_patterns() {
+    let (res, _) = run(&program(
+        "beantrage ErsetzeAlleRegex von Textverarbeitung;",
+        "ErsetzeAlleRegex(\"abc\" / \"(a\" / \"x\");",
+    ));
+    assert_eq!(
+        res,
+        Err("ErsetzeAlleRegex Fehler im Ausdruck `(a`: Nicht geschlossene Gruppe, erwartet wurde `)`.".into())
+    );
+
+    let (res, _) = run(&program(
+        "beantrage ErsetzeAlleRegex von Textverarbeitung;",
+        "ErsetzeAlleRegex(\"abc\" / \"(a)\" / \"$2\");",
+    ));
+    assert_eq!(
+        res,
+        Err("ErsetzeAlleRegex Fehler im Ausdruck `(a)`: Die Ersetzung verweist auf die Gruppe `2`, der Ausdruck enthält allerdings nur 1 Gruppe(n).".into())
+    );
+}
+
+#[test]
+fn passt_auf_and_finde_muster_with_groups() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage PasstAuf von Textverarbeitung;
+        beantrage FindeMuster von Textverarbeitung;",
+        "setze Zeichenkette Muster auf \"([a-z]+)@([a-z]+)(:[0-9]+)?\";
+        Drucke(PasstAuf(\"Mail an hasso@hpi bitte\" / Muster));
+        Drucke(FindeMuster(\"Mail an hasso@hpi bitte, nicht an sap@sap:80\" / Muster));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true\n[hasso@hpi / hasso / hpi / ]\n");
+}
+
+#[test]
+fn passt_auf_and_finde_muster_without_match() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage PasstAuf von Textverarbeitung;
+        beantrage FindeMuster von Textverarbeitung;",
+        "Drucke(PasstAuf(\"abc\" / \"^b\"));
+        Drucke(FindeMuster(\"abc\" / \"([0-9])\").Länge());
+        PasstAuf(\"abc\" / \"[a\");",
+    ));
+    assert_eq!(
+        res,
+        Err("PasstAuf Fehler im Ausdruck `[a`: Nicht geschlossene Zeichenklasse, erwartet wurde `]`.".into())
+    );
+    assert_eq!(output, "false\n0\n");
+}
+
+#[test]
+fn matrikelnummer_can_be_specified() {
+    let source = "
+        beantrage Drucke von Drucker;
+        funk Bewerbung() ergibt Zeichenkette { \"Bitte nehmt mich!\" }
+        funk Einschreibung(Zahl Matrikelnummer) ergibt Nichts { Drucke(Matrikelnummer); }
+        funk Studium() ergibt Nichts {}";
+    let (tree, _) = hpi_analyzer::analyze(source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_matrikelnummer(4_200_000)
+        .build()
+        .run(tree.clone());
+    assert_eq!(code, Ok(0));
+    assert_eq!(String::from_utf8(output).unwrap(), "4200000\n");
+
+    let mut output = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_matrikelnummer(7)
+        .with_determinismus(Determinismus::new(1))
+        .build()
+        .run(tree);
+    assert_eq!(code, Ok(0));
+    assert_eq!(String::from_utf8(output).unwrap(), "7\n");
+}
+
+#[test]
+fn determinismus_makes_runs_reproducible() {
+    let source = "
+        beantrage Drucke von Drucker;
+        beantrage Zeit von Uhr;
+        funk Bewerbung() ergibt Zeichenkette { \"Bitte nehmt mich!\" }
+        funk Einschreibung(Zahl Matrikelnummer) ergibt Nichts { Drucke(Matrikelnummer); }
+        funk Studium() ergibt Nichts {
+            setze Zeitstempel Jetzt auf Zeit();
+            Drucke(Jetzt.Jahr / Jetzt.Monat / Jetzt.Kalendar_Tag / Jetzt.Stunde / Jetzt.Minute);
+        }";
+    let (tree, _) = hpi_analyzer::analyze(source, "test.hpi").expect("program is valid");
+    let zeit = chrono::NaiveDate::from_ymd_opt(2023, 10, 3)
+        .and_then(|date| date.and_hms_opt(12, 30, 0))
+        .unwrap();
+
+    let run_with_seed = |seed| {
+        let mut output = vec![];
+        let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+            .with_determinismus(Determinismus::new(seed).with_zeit(zeit))
+            .build()
+            .run(tree.clone());
+        assert_eq!(code, Ok(0));
+        String::from_utf8(output).unwrap()
+    };
+
+    let output = run_with_seed(42);
+    assert_eq!(output, run_with_seed(42));
+    assert_ne!(output, run_with_seed(43));
+    assert!(output.ends_with("\n2023 10 3 12 30\n"), "{output}");
+}
+
+#[test]
+fn zeit_millisekunden_measures_durations() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Zeit_Millisekunden von Uhr;",
+        "setze Zahl Anfang auf Zeit_Millisekunden();
+        Schlummere(0,02);
+        setze Zahl Dauer auf Zeit_Millisekunden() - Anfang;
+        Drucke(Anfang > 1672531200000 / Dauer >= 20 / Dauer < 10000);",
+    );
+    let (res, output) = run(&source);
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true true true\n");
+
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Zeit_Millisekunden von Uhr;",
+        "Drucke(Zeit_Millisekunden());",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let zeit = chrono::NaiveDate::from_ymd_opt(2023, 10, 3)
+        .and_then(|date| date.and_hms_milli_opt(12, 30, 0, 250))
+        .unwrap();
+    let mut output = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_determinismus(Determinismus::new(42).with_zeit(zeit))
+        .build()
+        .run(tree);
+    assert_eq!(code, Ok(0));
+    assert_eq!(String::from_utf8(output).unwrap(), "1696336200250\n");
+}
+
+#[test]
+fn zeit_formatieren_formats_timestamps() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Zeit von Uhr;
+        beantrage Zeit_Millisekunden von Uhr;
+        beantrage Zeit_Formatieren von Uhr;",
+        "Drucke(Zeit_Formatieren(Zeit() / \"%Y-%m-%dT%H:%M:%S\"));
+        Drucke(Zeit_Formatieren(Zeit_Millisekunden() / \"%Y-%m-%dT%H:%M:%S%.3fZ\"));
+        Drucke(Zeit_Formatieren(0 / \"%d.%m.%Y\"));
+        Zeit_Formatieren(Zeit() / \"%Y-%Q\");",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let zeit = chrono::NaiveDate::from_ymd_opt(2023, 10, 3)
+        .and_then(|date| date.and_hms_milli_opt(12, 30, 5, 250))
+        .unwrap();
+
+    let mut output = vec![];
+    let res = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_determinismus(Determinismus::new(42).with_zeit(zeit))
+        .build()
+        .run(tree);
+
+    assert_eq!(
+        res.map_err(|err| err.to_string()),
+        Err("`Zeit_Formatieren`: Das Format `%Y-%Q` ist ungültig.".into())
+    );
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "2023-10-03T12:30:05\n2023-10-03T12:30:05.250Z\n01.01.1970\n"
+    );
+}
+
+#[test]
+fn zufall_stays_in_range() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Zufall von Mathe;
+
+        funk Würfle(Zahl Übrig) ergibt Nichts {
+            setze Zahl Wurf auf Zufall(1 / 7);
+            falls Wurf < 1 || Wurf >= 7 {
+                Drucke(\"außerhalb\" / Wurf);
+            }
+            falls Übrig > 0 {
+                Würfle(Übrig - 1);
+            }
+        }",
+        "Würfle(200);
+        setze Zahl Groß auf 9223372036854775807;
+        Drucke(Zufall(0 - 1 - Groß / 0 - Groß) / Zufall(Groß - 1 / Groß));
+        Zufall(3 / 3);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let run_with_seed = |seed| {
+        let mut output = vec![];
+        let res = InterpreterBuilder::new(&mut output, NoHttpClient)
+            .with_determinismus(Determinismus::new(seed))
+            .build()
+            .run(tree.clone());
+        (res, String::from_utf8(output).unwrap())
+    };
+
+    let (res, output) = run_with_seed(42);
+    assert_eq!(
+        res,
+        Err("`Zufall` erwartet eine untere Grenze, welche kleiner als die obere Grenze ist, allerdings ist die untere Grenze `3` und die obere Grenze `3`.".into())
+    );
+    assert_eq!(output, "-9223372036854775808 9223372036854775806\n");
+    assert_eq!(run_with_seed(7).1, output);
+}
+
+#[test]
+fn als_wahrheitswert_accepted_forms() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage AlsWahrheitswert von Textverarbeitung;",
+        "Drucke(AlsWahrheitswert(\"wahr\") / AlsWahrheitswert(\"falsch\"));
+        Drucke(AlsWahrheitswert(\"true\") / AlsWahrheitswert(\"false\"));
+        Drucke(AlsWahrheitswert(\"1\") / AlsWahrheitswert(\"0\"));
+        Drucke(AlsWahrheitswert(\" Ja \") / AlsWahrheitswert(\"NEIN\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true false\ntrue false\ntrue false\ntrue false\n");
+}
+
+#[test]
+fn als_wahrheitswert_rejects_invalid_input() {
+    let (res, _) = run(&program(
+        "beantrage AlsWahrheitswert von Textverarbeitung;",
+        "AlsWahrheitswert(\"vielleicht\");",
+    ));
+    assert_eq!(
+        res,
+        Err(
+            "Die Zeichenkette `vielleicht` kann nicht in einen Wahrheitswert umgewandelt werden."
+                .into()
+        )
+    );
+}
+
+#[test]
+fn http_rejects_non_pointer_body_destination() {
+    let source = program(
+        "beantrage Http von Netzwerk;",
+        "setze Zeichenkette Körper auf \"\";
+        Http(\"GET\" / \"https://hpi.de\" / \"\" / [] / &Körper);",
+    );
+    let (mut tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let Some(AnalyzedStatement::Expr(AnalyzedExpression::Call(call))) =
+        tree.studium_fn.stmts.get_mut(1)
+    else {
+        panic!("the second statement is the call to `Http`");
+    };
+    call.args[4] = AnalyzedExpression::String("kein Zeiger".to_string());
+
+    let mut output = vec![];
+    let res = Interpreter::new(&mut output, NoHttpClient, HashMap::new()).run(tree);
+    assert_eq!(
+        res,
+        Err("`Http` erwartet als fünftes Argument einen Zeiger, in welchen die Antwort geschrieben wird, allerdings wurde ein Wert vom Typ `Zeichenkette` übergeben.".into())
+    );
+}
+
+#[test]
+fn ggt_and_kgv() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage GgT von Mathe;
+        beantrage KgV von Mathe;",
+        "Drucke(GgT(8 / 15) / KgV(8 / 15));
+        Drucke(GgT(12 / 18) / KgV(12 / 18));
+        Drucke(GgT(0 - 12 / 18) / KgV(12 / 0 - 18));
+        Drucke(GgT(0 / 7) / KgV(0 / 7) / GgT(0 / 0) / KgV(0 / 0));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "1 120\n6 36\n6 36\n7 0 0 0\n");
+}
+
+#[test]
+fn kgv_rejects_overflow() {
+    let (res, _) = run(&program(
+        "beantrage KgV von Mathe;",
+        "KgV(9223372036854775807 / 2);",
+    ));
+    assert_eq!(
+        res,
+        Err("Ganzzahlüberlauf bei `KgV`: Das Ergebnis `18446744073709551614` passt nicht in eine `Zahl`.".into())
+    );
+}
+
+#[test]
+fn ist_prim() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage IstPrim von Mathe;",
+        "Drucke(IstPrim(2) / IstPrim(3) / IstPrim(97) / IstPrim(9223372036854775783));
+        Drucke(IstPrim(4) / IstPrim(91) / IstPrim(3215031751));
+        Drucke(IstPrim(0 - 7) / IstPrim(0) / IstPrim(1));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "true true true true\nfalse false false\nfalse false false\n"
+    );
+}
+
+#[test]
+fn primfaktoren() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Primfaktoren von Mathe;",
+        "Drucke(Primfaktoren(360) / Primfaktoren(97));
+        Drucke(Primfaktoren(9223372036854775807));
+        Drucke(Primfaktoren(1) / Primfaktoren(0) / Primfaktoren(0 - 12));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "[2 / 2 / 2 / 3 / 3 / 5] [97]\n[7 / 7 / 73 / 127 / 337 / 92737 / 649657]\n[] [] []\n"
+    );
+}
+
+#[test]
+fn begrenze() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Begrenze von Mathe;",
+        "Drucke(Begrenze(0 - 5 / 0 / 10) / Begrenze(5 / 0 / 10) / Begrenze(15 / 0 / 10));
+        Drucke(Begrenze(0,5 / 1,0 / 2,0) / Begrenze(1,5 / 1,0 / 2,0) / Begrenze(2,5 / 1,0 / 2,0));
+        Drucke(Begrenze(3 / 3 / 3));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "0 5 10\n1 1,5 2\n3\n");
+}
+
+#[test]
+fn begrenze_rejects_invalid_bounds() {
+    let (res, _) = run(&program(
+        "beantrage Begrenze von Mathe;",
+        "Begrenze(5 / 10 / 0);",
+    ));
+    assert_eq!(
+        res,
+        Err("`Begrenze` erwartet ein Minimum, welches nicht größer als das Maximum ist, allerdings ist das Minimum `10` und das Maximum `0`.".into())
+    );
+
+    let source = program("beantrage Begrenze von Mathe;", "Begrenze(5 / 1,0 / 2);");
+    assert!(hpi_analyzer::analyze(&source, "test.hpi").is_err());
+}
+
+#[test]
+fn drucke_tabelle_aligns_columns() {
+    let (res, output) = run(&program(
+        "beantrage DruckeTabelle von Drucker;",
+        "DruckeTabelle([
+            [\"Name\" / \"Fach\" / \"Note\"] /
+            [\"Hasso\" / \"Mathematik\" / \"1,0\"] /
+            [\"Ada\" / \"PT\"] /
+        ]);
+        DruckeTabelle([[1 / 200] / [30 / 4]]);",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "\
+Name  | Fach       | Note
+Hasso | Mathematik | 1,0
+Ada   | PT
+1  | 200
+30 | 4
+"
+    );
+}
+
+#[test]
+fn kehre_zeichenkette_um() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage KehreZeichenketteUm von Textverarbeitung;",
+        "Drucke(KehreZeichenketteUm(\"Hasso\") / KehreZeichenketteUm(\"\"));
+        Drucke(KehreZeichenketteUm(\"Größe über\"));
+        Drucke(KehreZeichenketteUm(\"zu\u{308}rich\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "ossaH \nrebü eßörG\nhciru\u{308}z\n");
+}
+
+#[test]
+fn drucke_shows_pointees() {
+    let source = program(
+        "beantrage Drucke von Drucker;",
+        "setze Zahl Wert auf 42;
+        setze Liste von Zahl Werte auf [1 / 2];
+        setze Zeiger auf Zahl Adresse auf &Wert;
+        Drucke(Adresse / &Adresse / &Werte);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let (res, output) = run(&source);
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "Zeiger auf 42 Zeiger auf Zeiger auf 42 Zeiger auf [1 / 2]\n"
+    );
+
+    let mut output = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_pointer_notation(PointerNotation::Opaque)
+        .build()
+        .run(tree);
+    assert_eq!(code, Ok(0));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "<Zeiger> <Zeiger> <Zeiger>\n"
+    );
+}
+
+#[test]
+fn display_terminates_for_cycles() {
+    let list = Rc::new(RefCell::new(vec![Value::Int(1)]));
+    let cell = Rc::new(RefCell::new(Value::List(Rc::clone(&list))));
+    list.borrow_mut().push(Value::Ptr(Rc::clone(&cell)));
+
+    assert_eq!(
+        Value::List(Rc::clone(&list)).to_string(),
+        "[1 / Zeiger auf <Zyklus>]"
+    );
+    assert_eq!(Value::Ptr(cell).to_string(), "Zeiger auf [1 / <Zyklus>]");
+    // break the cycle to avoid leaking memory
+    list.borrow_mut().clear();
+}
+
+#[test]
+fn merke_dir_memoizes_recursive_calls() {
+    let fib = |memoized: bool| {
+        let body = if memoized {
+            "setze Zeichenkette Schlüssel auf Formatiere(\"%d\" / N);
+            falls HatSchon(Schlüssel) {
+                überweise HoleGemerkt(Schlüssel) als Zahl;
+            }
+            setze Zahl Ergebnis auf Fib(N - 1) + Fib(N - 2);
+            MerkeDir(Schlüssel / Ergebnis);
+            Ergebnis"
+        } else {
+            "Fib(N - 1) + Fib(N - 2)"
+        };
+        program(
+            &format!(
+                "beantrage Drucke von Drucker;
+                beantrage MerkeDir von libSAP;
+                beantrage HatSchon von libSAP;
+                beantrage HoleGemerkt von libSAP;
+                beantrage Formatiere von Textverarbeitung;
+
+                funk Fib(Zahl N) ergibt Zahl {{
+                    falls N < 2 {{ überweise N; }}
+                    {body}
+                }}"
+            ),
+            "Drucke(Fib(20));",
+        )
+    };
+
+    let calls = |source: &str| {
+        let (tree, _) = hpi_analyzer::analyze(source, "test.hpi").expect("program is valid");
+        let statistics = Rc::new(Statistics::default());
+        let mut output = vec![];
+        let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+            .with_statistics(Rc::clone(&statistics))
+            .build()
+            .run(tree);
+        assert_eq!(code, Ok(0));
+        assert_eq!(String::from_utf8(output).unwrap(), "6765\n");
+        statistics.calls()
+    };
+
+    let naive = calls(&fib(false));
+    let memoized = calls(&fib(true));
+    // 3 calls are the entry points
+    assert_eq!(naive, 21891 + 3);
+    assert_eq!(memoized, 39 + 3);
+}
+
+#[test]
+fn hole_gemerkt_fails_for_unknown_keys() {
+    let (res, _) = run(&program(
+        "beantrage HoleGemerkt von libSAP;",
+        "HoleGemerkt(\"fehlt\") als Zahl;",
+    ));
+    assert_eq!(
+        res,
+        Err("Unter dem Schlüssel `fehlt` wurde nichts gemerkt.".into())
+    );
+}
+
+#[test]
+fn merke_dir_keys_are_namespaced_per_function() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage MerkeDir von libSAP;
+        beantrage HatSchon von libSAP;
+        beantrage HoleGemerkt von libSAP;
+
+        funk Verdopple(Zahl N) ergibt Zahl {
+            falls HatSchon(\"n\") { überweise HoleGemerkt(\"n\") als Zahl; }
+            MerkeDir(\"n\" / N * 2);
+            N * 2
+        }
+
+        funk Verdreifache(Zahl N) ergibt Zahl {
+            falls HatSchon(\"n\") { überweise HoleGemerkt(\"n\") als Zahl; }
+            MerkeDir(\"n\" / N * 3);
+            N * 3
+        }",
+        "Drucke(Verdopple(2));
+        Drucke(Verdreifache(2));
+        Drucke(Verdopple(5));
+        Drucke(HatSchon(\"n\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "4\n6\n4\nfalse\n");
+}
+
+#[test]
+fn rotiere() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Rotiere von libSAP;",
+        "setze Liste von Zahl Zahlen auf [1 / 2 / 3 / 4];
+        Drucke(Rotiere(Zahlen / 1) / Rotiere(Zahlen / 0 - 1));
+        Drucke(Rotiere(Zahlen / 10) / Rotiere(Zahlen / 0 - 9));
+        Drucke(Zahlen / Rotiere([] / 3));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "[4 / 1 / 2 / 3] [2 / 3 / 4 / 1]\n[3 / 4 / 1 / 2] [2 / 3 / 4 / 1]\n[1 / 2 / 3 / 4] []\n"
+    );
+}
+
+#[test]
+fn teilliste_copies_slices() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Teilliste von libSAP;
+        beantrage Hinzufügen von libSAP;",
+        "setze Liste von Zahl Zahlen auf [1 / 2 / 3 / 4];
+        setze Liste von Zahl Mitte auf Teilliste(Zahlen / 1 / 3);
+        Hinzufügen(Mitte / 5);
+        Drucke(Mitte / Zahlen);
+        Drucke(Teilliste(Zahlen / 4 / 4) / Teilliste(Zahlen / 0 / 4));
+        Drucke(Teilliste(Zahlen / 2 / 5));",
+    ));
+    assert_eq!(
+        res,
+        Err("Illegale Indizierung: Index `5` liegt außerhalb der Liste der Länge `4`".into())
+    );
+    assert_eq!(output, "[2 / 3 / 5] [1 / 2 / 3 / 4]\n[] [1 / 2 / 3 / 4]\n");
+
+    let (res, _) = run(&program(
+        "beantrage Teilliste von libSAP;",
+        "Teilliste([1 / 2] / 2 / 1);",
+    ));
+    assert_eq!(
+        res,
+        Err("Illegale Indizierung: Der Anfang `2` liegt hinter dem Ende `1`".into())
+    );
+
+    let (res, _) = run(&program(
+        "beantrage Teilliste von libSAP;",
+        "Teilliste([1 / 2] / 0 - 1 / 1);",
+    ));
+    assert_eq!(res, Err("Illegale Indizierung mittels Index: `-1`".into()));
+}
+
+#[test]
+fn run_reports_normal_completion() {
+    let source = program("", "setze Zahl Wert auf 1;");
+    let Ok((0, diagnostics)) =
+        crate::run(&source, "test.hpi", vec![], NoHttpClient, HashMap::new())
+    else {
+        panic!("program terminates with exit code 0");
+    };
+    let info = diagnostics.last().expect("the outcome is reported");
+    assert_eq!(info.level, DiagnosticLevel::Info);
+    assert_eq!(
+        info.message,
+        "Das Programm wurde nach dem Studium regulär beendet."
+    );
+
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let outcome = Interpreter::new(vec![], NoHttpClient, HashMap::new()).run_to_outcome(tree);
+    assert_eq!(outcome, Ok(RunOutcome::Completed));
+}
+
+#[test]
+fn run_reports_explicit_exit() {
+    let source = program("beantrage Aufgeben von libSAP;", "Aufgeben(0);");
+    let Ok((0, diagnostics)) =
+        crate::run(&source, "test.hpi", vec![], NoHttpClient, HashMap::new())
+    else {
+        panic!("program terminates with exit code 0");
+    };
+    let info = diagnostics.last().expect("the outcome is reported");
+    assert_eq!(info.level, DiagnosticLevel::Info);
+    assert_eq!(
+        info.message,
+        "Das Programm wurde mit `Aufgeben(0)` vorzeitig beendet."
+    );
+
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let outcome = Interpreter::new(vec![], NoHttpClient, HashMap::new()).run_to_outcome(tree);
+    assert_eq!(outcome, Ok(RunOutcome::Exited(0)));
+}
+
+#[test]
+fn betrag() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Betrag von Mathe;",
+        "Drucke(Betrag(0 - 7) / Betrag(7) / Betrag(0));
+        Drucke(Betrag(0,0 - 2,5) / Betrag(2,5));
+        setze Zahl Groß auf 9223372036854775807;
+        Drucke(Betrag(0 - Groß));
+        Drucke(Betrag(0 - 1 - Groß));",
+    ));
+    assert_eq!(
+        res,
+        Err("Ganzzahlüberlauf bei `Betrag`: Das Ergebnis `9223372036854775808` passt nicht in eine `Zahl`.".into())
+    );
+    assert_eq!(output, "7 7 0\n2,5 2,5\n9223372036854775807\n");
+}
+
+#[test]
+fn wurzel_sinus_kosinus() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Wurzel von Mathe;
+        beantrage Sinus von Mathe;
+        beantrage Kosinus von Mathe;",
+        "Drucke(Wurzel(16,0) / Wurzel(2,25) / Wurzel(0,0));
+        Drucke(Sinus(0,0) / Kosinus(0,0));
+        Drucke(Wurzel(0,0 - 4,0));",
+    ));
+    assert_eq!(
+        res,
+        Err("`Wurzel` kann nicht aus der negativen Zahl `-4` gezogen werden.".into())
+    );
+    assert_eq!(output, "4 1,5 0\n0 1\n");
+}
+
+#[test]
+fn runde_abrunden_aufrunden() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Runde von Mathe;
+        beantrage Abrunden von Mathe;
+        beantrage Aufrunden von Mathe;",
+        "Drucke(Runde(2,5) / Runde(0,0 - 2,5) / Runde(2,4) / Runde(0,5));
+        Drucke(Abrunden(2,7) / Abrunden(0,0 - 2,2) / Abrunden(3,0));
+        Drucke(Aufrunden(2,2) / Aufrunden(0,0 - 2,7) / Aufrunden(3,0));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "3 -3 2 1\n2 -3 3\n3 -2 3\n");
+}
+
+#[test]
+fn potenz_und_modulo_match_the_operators() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Potenz von Mathe;
+        beantrage Modulo von Mathe;",
+        "setze Zahl Sieben auf 7;
+        setze Zahl Minus auf 0 - 7;
+        Drucke(Potenz(2 / 10) == 2 ** 10 / Potenz(Minus / 3) == Minus ** 3 / Potenz(2 / 0 - 1) == 2 ** (0 - 1));
+        Drucke(Potenz(2 / 10) / Potenz(Minus / 3) / Potenz(2 / 0 - 1) / Potenz(2,0 / 0,5 - 1,0));
+        Drucke(Modulo(Sieben / 3) == Sieben % 3 / Modulo(Minus / 3) == Minus % 3);
+        Drucke(Modulo(Sieben / 3) / Modulo(Minus / 3) / Modulo(Sieben / 0 - 3));
+        Drucke(Potenz(2 / 63));",
+    ));
+    assert_eq!(res, Err("Ganzzahlüberlauf bei Potenzierung".into()));
+    assert_eq!(
+        output,
+        "true true true\n1024 -343 0 0,7071067811865476\ntrue true\n1 -1 1\n"
+    );
+
+    let (res, _) = run(&program(
+        "beantrage Modulo von Mathe;",
+        "setze Zahl Null auf 0;
+        Modulo(7 / Null);",
+    ));
+    let (operator_res, _) = run(&program(
+        "",
+        "setze Zahl Null auf 0;
+        7 % Null;",
+    ));
+    assert_eq!(res, operator_res);
+    assert_eq!(res, Err("division by zero".into()));
+}
+
+#[test]
+fn integer_overflow_is_an_error() {
+    for (body, expected) in [
+        ("Groß + 1;", "Ganzzahlüberlauf bei Addition"),
+        ("0 - Groß - 2;", "Ganzzahlüberlauf bei Subtraktion"),
+        ("Groß * 2;", "Ganzzahlüberlauf bei Multiplikation"),
+        ("2 ** 63;", "Ganzzahlüberlauf bei Potenzierung"),
+        ("2 ** 4294967296;", "Ganzzahlüberlauf bei Potenzierung"),
+        ("(0 - Groß - 1) : (0 - 1);", "Ganzzahlüberlauf bei Division"),
+        ("-(0 - Groß - 1);", "Ganzzahlüberlauf bei Negation"),
+        ("Groß += 1;", "Ganzzahlüberlauf bei Addition"),
+        ("Groß **= 2;", "Ganzzahlüberlauf bei Potenzierung"),
+    ] {
+        let (res, output) = run(&program(
+            "beantrage Drucke von Drucker;",
+            &format!("setze Zahl Groß auf 9223372036854775807;\n{body}\nDrucke(\"fertig\");"),
+        ));
+        assert_eq!(res, Err(expected.into()), "{body}");
+        assert_eq!(output, "", "{body}");
+    }
+
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;",
+        "setze Zahl Groß auf 9223372036854775807;
+        Drucke(Groß - 1 + 1 / 0 - Groß - 1 / 2 ** 62 / (0 - 1) ** 4294967297 / 1 ** 4294967296);",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "9223372036854775807 -9223372036854775808 4611686018427387904 -1 1\n"
+    );
+}
+
+#[test]
+fn abstand_zwischen() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage AbstandZwischen von Mathe;",
+        "Drucke(AbstandZwischen(3 / 10) / AbstandZwischen(10 / 3) / AbstandZwischen(0 - 4 / 4));
+        Drucke(AbstandZwischen(1,5 / 0,0 - 1,0));
+        setze Zahl Groß auf 9223372036854775807;
+        Drucke(AbstandZwischen(0 - 1 - Groß / 0 - 1) / AbstandZwischen(0 - Groß / 0));
+        Drucke(AbstandZwischen(Groß / 0 - 1 - Groß));",
+    ));
+    assert_eq!(
+        res,
+        Err("Ganzzahlüberlauf bei `AbstandZwischen`: Das Ergebnis `18446744073709551615` passt nicht in eine `Zahl`.".into())
+    );
+    assert_eq!(
+        output,
+        "7 7 8\n2,5\n9223372036854775807 9223372036854775807\n"
+    );
+}
+
+#[cfg(feature = "defensive")]
+#[test]
+fn defensive_mode_reports_invalid_trees() {
+    let source = program(
+        "beantrage Http von Netzwerk;",
+        "setze Zeichenkette Körper auf \"\";
+        Http(\"GET\" / \"https://hpi.de\" / \"\" / [] / &Körper);",
+    );
+    let (mut tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let Some(AnalyzedStatement::Expr(AnalyzedExpression::Call(call))) =
+        tree.studium_fn.stmts.get_mut(1)
+    else {
+        panic!("the second statement is the call to `Http`");
+    };
+    call.args[0] = AnalyzedExpression::Int(42);
+
+    let res = Interpreter::new(vec![], NoHttpClient, HashMap::new()).run(tree);
+    let Err(msg) = res.map_err(|err| err.to_string()) else {
+        panic!("the invalid tree is rejected");
+    };
+    assert!(
+        msg.starts_with("Interner Fehler: the analyzer prevents this (")
+            && msg.contains("interpreter.rs:"),
+        "{msg}"
+    );
+}
+
+#[cfg(feature = "defensive")]
+#[test]
+fn defensive_mode_reports_invalid_operands() {
+    for (operands, message, file) in [
+        (
+            "Zahl C auf B + B",
+            "the analyzer guarantees one of the above to match (",
+            "ops.rs:",
+        ),
+        (
+            "Wahrheitswert C auf A && A",
+            "called unwrap_bool on `String(\"a\")` (",
+            "value.rs:",
+        ),
+    ] {
+        let source = program(
+            "",
+            &format!(
+                "setze Wahrheitswert A auf ja;
+                setze Zahl B auf 1;
+                setze {operands};"
+            ),
+        );
+        let (mut tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+        let Some(AnalyzedStatement::Let(hpi_analyzer::ast::AnalyzedLetStmt {
+            expr: AnalyzedExpression::Infix(infix),
+            ..
+        })) = tree.studium_fn.stmts.get_mut(2)
+        else {
+            panic!("the third statement defines `C` using an infix expression");
+        };
+        infix.lhs = AnalyzedExpression::String("a".to_string());
+
+        let res = Interpreter::new(vec![], NoHttpClient, HashMap::new()).run(tree);
+        let Err(msg) = res.map_err(|err| err.to_string()) else {
+            panic!("the invalid tree is rejected");
+        };
+        assert!(
+            msg.starts_with(&format!("Interner Fehler: {message}")) && msg.contains(file),
+            "{msg}"
+        );
+    }
+}
+
+#[test]
+fn abbilden_and_filtern_apply_functions() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Abbilden von libSAP;
+        beantrage Filtern von libSAP;
+
+        funk Verdopple(Zahl Wert) ergibt Zahl { Wert * 2 }
+        funk Ist_Gerade(Zahl Wert) ergibt Wahrheitswert { Wert % 2 == 0 }
+        funk Ist_Groß(Zahl Wert) ergibt Wahrheitswert { Wert > 2 }",
+        "setze Liste von Zahl Zahlen auf [1 / 2 / 3 / 4];
+        Drucke(Abbilden(Zahlen / Verdopple) / Zahlen);
+        Drucke(Filtern(Zahlen / Ist_Gerade) / Abbilden(Zahlen / Ist_Groß));
+        setze Liste von Zahl Leer auf [];
+        Drucke(Abbilden(Leer / Verdopple) / Filtern(Leer / Ist_Gerade));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "[2 / 4 / 6 / 8] [1 / 2 / 3 / 4]\n[2 / 4] [false / false / true / true]\n[] []\n"
+    );
+}
+
+#[test]
+fn reduziere_folds_lists() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Reduziere von libSAP;
+        beantrage Summe von Mathe;
+        beantrage Formatiere von Textverarbeitung;
+
+        funk Addiere(Zahl Summe / Zahl Wert) ergibt Zahl { Summe + Wert }
+        funk Verkette(Zeichenkette Text / Zahl Wert) ergibt Zeichenkette { Formatiere(\"%s%d\" / Text / Wert) }",
+        "setze Liste von Zahl Zahlen auf [1 / 2 / 3 / 4];
+        Drucke(Reduziere(Zahlen / 0 / Addiere) == Summe(Zahlen));
+        Drucke(Reduziere(Zahlen / \">\" / Verkette));
+        setze Liste von Zahl Leer auf [];
+        Drucke(Reduziere(Leer / 42 / Addiere));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true\n>1234\n42\n");
+}
+
+#[test]
+fn liste_als_text_formats_elements() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage ListeAlsText von Textverarbeitung;
+        beantrage Formatiere von Textverarbeitung;
+
+        funk Nummer(Zahl Wert) ergibt Zeichenkette { Formatiere(\"Nr. %d\" / Wert) }
+        funk Teile(Zahl Wert) ergibt Zeichenkette { Formatiere(\"%d\" / 10 : Wert) }",
+        "Drucke(ListeAlsText([1 / 2 / 3] / \", \" / Nummer));
+        Drucke(ListeAlsText([] / \", \" / Nummer));
+        Drucke(ListeAlsText([5 / 0] / \", \" / Teile));",
+    ));
+    assert_eq!(
+        res,
+        Err(
+            "division by zero\nAufrufstapel (innerster Aufruf zuletzt):\n  Studium\n  Teile".into()
+        )
+    );
+    assert_eq!(output, "Nr. 1, Nr. 2, Nr. 3\n\n");
+}
+
+#[test]
+fn gliedere_json_key_orders() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Gliedere_JSON von Textverarbeitung;
+        beantrage Gliedere_JSON_Sortiert von Textverarbeitung;",
+        "setze Objekt { Zahl Mitte / Zeichenkette Anfang / Liste von Zahl Ende } Daten auf erstelle {
+            Zahl Mitte auf 2 /
+            Zeichenkette Anfang auf \"a\" /
+            Liste von Zahl Ende auf [1 / 2]
+        };
+        Drucke(Gliedere_JSON(Daten));
+        Drucke(Gliedere_JSON_Sortiert(Daten / \"aufsteigend\"));
+        Drucke(Gliedere_JSON_Sortiert(Daten / \"absteigend\"));
+        Drucke(Gliedere_JSON_Sortiert(Daten / \"eingefügt\"));
+        Drucke(Gliedere_JSON_Sortiert(Daten / \"zufällig\"));",
+    ));
+    assert_eq!(
+        res,
+        Err("Unbekannte Schlüsselsortierung `zufällig`, erlaubt sind `aufsteigend`, `absteigend` und `eingefügt`.".into())
+    );
+    assert_eq!(
+        output,
+        "{\"Anfang\":\"a\",\"Ende\":[1,2],\"Mitte\":2}
+{\"Anfang\":\"a\",\"Ende\":[1,2],\"Mitte\":2}
+{\"Mitte\":2,\"Ende\":[1,2],\"Anfang\":\"a\"}
+{\"Mitte\":2,\"Anfang\":\"a\",\"Ende\":[1,2]}
+"
+    );
+}
+
+#[test]
+fn drucke_debug_respects_verbosity() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage DruckeDebug von Drucker;",
+        "Drucke(\"Start\");
+        DruckeDebug(1 / \"Zwischenstand:\" / 42);
+        DruckeDebug(2 / \"Details\");
+        Drucke(\"Ende\");",
+    );
+
+    for (verbosity, expected) in [
+        (0, "Start\nEnde\n"),
+        (1, "Start\nZwischenstand: 42\nEnde\n"),
+        (2, "Start\nZwischenstand: 42\nDetails\nEnde\n"),
+    ] {
+        let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+        let mut output = vec![];
+        let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+            .with_verbosity(verbosity)
+            .build()
+            .run(tree);
+
+        assert_eq!(code, Ok(0));
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
+
+#[test]
+fn zerlege_zeilen_handles_line_endings() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage ZerlegeZeilen von Textverarbeitung;",
+        "Drucke(ZerlegeZeilen(\"eins\\r\\nzwei\\ndrei\\r\\n\"));
+        Drucke(ZerlegeZeilen(\"eins\\n\\nzwei\\n\").Länge());
+        Drucke(ZerlegeZeilen(\"\").Länge());",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "[eins / zwei / drei]\n3\n0\n");
+}
+
+#[test]
+fn gliedere_json_limits_depth() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage Gliedere_JSON von Textverarbeitung;",
+        "setze Liste von Liste von Liste von Zahl Tief auf [[[1] / [2]]];
+        Drucke(Gliedere_JSON(Tief));",
+    );
+
+    for (max_depth, expected_res, expected_output) in [
+        (3, Ok(0), "[[[1],[2]]]\n"),
+        (
+            2,
+            Err("Gliedere_JSON Umwandlungsfehler: Schachtelungstiefe: der Wert ist tiefer als 2 Ebenen verschachtelt.".into()),
+            "",
+        ),
+    ] {
+        let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+        let mut output = vec![];
+        let res = InterpreterBuilder::new(&mut output, NoHttpClient)
+            .with_json_max_depth(max_depth)
+            .build()
+            .run(tree);
+
+        assert_eq!(res, expected_res);
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+}
+
+#[test]
+fn builder_configures_environment_variables() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage UmgebungsvariableEgalGroß von libSAP;",
+        "Drucke(UmgebungsvariableEgalGroß(\"PFAD\") / UmgebungsvariableEgalGroß(\"WERT\"));",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_environment_variables(HashMap::from([("WERT".to_string(), "alt".to_string())]))
+        .with_environment_variable("PFAD", "/bin")
+        .with_environment_variable("WERT", "neu")
+        .with_step_limit(100)
+        .build()
+        .run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(String::from_utf8(output).unwrap(), "/bin neu\n");
+}
+
+#[test]
+fn umgebungsvariable_egal_gross_ignores_case() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage UmgebungsvariableEgalGroß von libSAP;",
+        "Drucke(UmgebungsvariableEgalGroß(\"PFAD\"));
+        Drucke(UmgebungsvariableEgalGroß(\"wert\"));
+        Drucke(UmgebungsvariableEgalGroß(\"Wert\"));
+        Drucke(UmgebungsvariableEgalGroß(\"FEHLT\") == \"\");",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let env = HashMap::from([
+        ("Pfad".to_string(), "/bin".to_string()),
+        ("WERT".to_string(), "groß".to_string()),
+        ("wert".to_string(), "klein".to_string()),
+    ]);
+    let code = Interpreter::new(&mut output, NoHttpClient, env).run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "/bin\nklein\ngroß\ntrue\n"
+    );
+}
+
+#[test]
+fn drucke_callback_receives_values() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage DruckeKanal von Drucker;",
+        "Drucke(\"Antwort\" / 42 / [ja]);
+        Drucke();
+        DruckeKanal(\"protokoll\" / \"Text\");",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let mut calls = vec![];
+    let code = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_drucke_callback(|values| calls.push(values))
+        .build()
+        .run(tree);
+
+    assert_eq!(code, Ok(0));
+    assert_eq!(
+        calls,
+        [
+            vec![
+                Value::String("Antwort".into()),
+                Value::Int(42),
+                Value::List(Rc::new(RefCell::new(vec![Value::Bool(true)]))),
+            ],
+            vec![],
+        ]
+    );
+    assert_eq!(String::from_utf8(output).unwrap(), "Text\n");
+}
+
+#[test]
+fn schreibe_omits_the_newline() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Schreibe von Drucker;",
+        "Schreibe(\"Fortschritt:\");
+        Schreibe();
+        Schreibe(\"\" / 1);
+        Schreibe(\"\" / 2 / 3);
+        Drucke(\"\");
+        Schreibe(\"fertig\" / ja);",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "Fortschritt: 1 2 3\nfertig true");
+}
+
+#[test]
+fn maximum_und_minimum() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Maximum von libSAP;
+        beantrage Minimum von libSAP;",
+        "setze Liste von Zahl Zahlen auf [3 / 0 - 7 / 12 / 5];
+        Drucke(Maximum(Zahlen) / Minimum(Zahlen) / Maximum([42]));
+        Drucke(Maximum([\"Birne\" / \"Apfel\" / \"Zitrone\"]) / Minimum([\"Birne\" / \"Apfel\" / \"Zitrone\"]));
+        Drucke(Maximum([1,5 / 2,5 / 0,5]) / Minimum([1,5 / 2,5 / 0,5]));
+        setze Liste von Zahl Leer auf [];
+        Drucke(Maximum(Leer));",
+    ));
+    assert_eq!(
+        res,
+        Err("`Maximum` kann nicht auf eine leere Liste angewendet werden.".into())
+    );
+    assert_eq!(output, "12 -7 42\nZitrone Apfel\n2,5 0,5\n");
+
+    let (res, _) = run(&program(
+        "beantrage Minimum von libSAP;",
+        "setze Fließkommazahl Null auf 0,0;
+        Minimum([1,0 / Null : Null]);",
+    ));
+    assert_eq!(
+        res,
+        Err("`Minimum` kann nicht auf eine Liste angewendet werden, welche `NaN` enthält.".into())
+    );
+}
+
+#[test]
+fn maximum_und_minimum_nach_select_by_key() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage MaximumNach von libSAP;
+        beantrage MinimumNach von libSAP;
+
+        funk Punkte(Objekt { Zeichenkette Name / Zahl Punkte } Student) ergibt Zahl { Student.Punkte }
+        funk Name(Objekt { Zeichenkette Name / Zahl Punkte } Student) ergibt Zeichenkette { Student.Name }",
+        "setze Liste von Objekt { Zeichenkette Name / Zahl Punkte } Studenten auf [
+            erstelle { Zeichenkette Name auf \"Bert\" / Zahl Punkte auf 7 } /
+            erstelle { Zeichenkette Name auf \"Anna\" / Zahl Punkte auf 9 } /
+            erstelle { Zeichenkette Name auf \"Carl\" / Zahl Punkte auf 9 } /
+            erstelle { Zeichenkette Name auf \"Dora\" / Zahl Punkte auf 3 }
+        ];
+        Drucke(MaximumNach(Studenten / Punkte).Name);
+        Drucke(MinimumNach(Studenten / Punkte).Name);
+        Drucke(MaximumNach(Studenten / Name).Name);
+        Drucke(MinimumNach(Studenten / Name).Name);
+        setze Liste von Objekt { Zeichenkette Name / Zahl Punkte } Leer auf [];
+        Drucke(MaximumNach(Leer / Punkte).Name);",
+    ));
+    assert_eq!(
+        res,
+        Err("`MaximumNach` kann nicht auf eine leere Liste angewendet werden.".into())
+    );
+    assert_eq!(output, "Anna\nDora\nDora\nAnna\n");
+}
+
+#[test]
+fn index_out_of_bounds_is_an_error() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;",
+        "setze Liste von Zahl Zahlen auf [1 / 2 / 3];
+        Drucke(Zahlen[2]);
+        Drucke(Zahlen[3]);",
+    ));
+    assert_eq!(
+        res,
+        Err("Illegale Indizierung: Index `3` liegt außerhalb der Liste der Länge `3`".into())
+    );
+    assert_eq!(output, "3\n");
+}
+
+#[test]
+fn laenge_counts_characters_and_elements() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Länge von libSAP;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "Drucke(Länge(\"Größe\") / Länge(\"\") / Länge([1 / 2 / 3]));
+        Drucke(Länge(Zergliedere_JSON(\"{\\\"a\\\": 1, \\\"b\\\": 2}\") als Speicherbox));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "5 0 3\n2\n");
+}
+
+#[test]
+fn run_to_string_collects_output() {
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage Aufgeben von libSAP;",
+        "Drucke(\"Grüße\" / 42);
+        Aufgeben(2);",
+    );
+
+    let (code, output, diagnostics) =
+        crate::run_to_string(&source, "test.hpi", NoHttpClient, HashMap::new())
+            .expect("program does not fail");
+
+    assert_eq!(code, 2);
+    assert_eq!(output, "Grüße 42\n");
+    assert_eq!(
+        diagnostics
+            .last()
+            .map(|diagnostic| diagnostic.message.as_ref()),
+        Some("Das Programm wurde mit `Aufgeben(2)` vorzeitig beendet.")
+    );
+}
+
+#[test]
+fn runtime_errors_are_typed() {
+    let run_typed = |prelude: &str, body: &str| {
+        let source = program(prelude, body);
+        let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+        Interpreter::new(vec![], NoHttpClient, HashMap::new())
+            .run(tree)
+            .expect_err("the program fails")
+    };
+
+    let err = run_typed("", "setze Zahl Null auf 0; 1 : Null;");
+    assert_eq!(err, RuntimeError::DivisionByZero);
+    assert_eq!(err.to_string(), "division by zero");
+
+    let err = run_typed("", "setze Liste von Zahl Zahlen auf [1 / 2]; Zahlen[2];");
+    assert_eq!(
+        err,
+        RuntimeError::IndexOutOfBounds {
+            index: 2,
+            len: 2,
+            target: IndexTarget::List
+        }
+    );
+
+    let err = run_typed(
+        "funk Verdopple(Zahl N) ergibt Zahl { N * 2 }",
+        "Verdopple(9223372036854775807);",
+    );
+    assert_eq!(
+        err.root(),
+        &RuntimeError::Overflow {
+            operation: "Multiplikation",
+            result: None
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "Ganzzahlüberlauf bei Multiplikation\nAufrufstapel (innerster Aufruf zuletzt):\n  Studium\n  Verdopple"
+    );
+}
+
+#[test]
+fn runtime_errors_report_their_location() {
+    let location = |prelude: &str, body: &str| {
+        let source = program(prelude, body);
+        match crate::run(&source, "test.hpi", vec![], NoHttpClient, HashMap::new()) {
+            Err(RunError::Runtime(_, span)) => span.map(|span| format!("{span:#?}")),
+            other => panic!("expected a runtime error, found {other:?}"),
+        }
+    };
+
+    // the first line of `body` is line 5 of the program
+    assert_eq!(
+        location("", "setze Zahl Null auf 0;\nsetze Zahl X auf 1 + 2 : Null;").as_deref(),
+        Some("6:22..6:30")
+    );
+    assert_eq!(
+        location("", "setze Liste von Zahl L auf [];\n  L[0];").as_deref(),
+        Some("6:3..6:7")
+    );
+    assert_eq!(
+        location("", "setze Zeichenkette S auf \"x\";\nS als Zahl;").as_deref(),
+        Some("6:1..6:11")
+    );
+    // errors of builtins are reported at the innermost statement
+    assert_eq!(
+        location(
+            "beantrage Zufall von Mathe;\nfunk F() ergibt Nichts {\n    Zufall(3 / 3);\n}",
+            "F();"
+        )
+        .as_deref(),
+        Some("3:5..3:19")
+    );
+}
+
+#[test]
+fn recursion_depth_is_limited() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+
+        funk Tiefe(Zahl N) ergibt Zahl {
+            falls N == 0 { 0 } sonst { Tiefe(N - 1) + 1 }
+        }",
+        "Drucke(Tiefe(48));
+        Drucke(Tiefe(49));",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    // `Studium` counts as a call as well
+    let mut output = vec![];
+    let res = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_max_call_depth(50)
+        .build()
+        .run(tree);
+
+    assert_eq!(
+        res.map_err(|err| err.to_string()),
+        Err("Maximale Rekursionstiefe überschritten: Es dürfen höchstens 50 Funktionsaufrufe verschachtelt werden.
+Aufrufstapel (innerster Aufruf zuletzt):
+  Studium
+  Tiefe (49 mal)".into())
+    );
+    assert_eq!(String::from_utf8(output).unwrap(), "48\n");
+}
+
+#[test]
+fn default_call_depth_fits_into_the_stack() {
+    // the size of the main thread's stack on most platforms
+    let res = std::thread::Builder::new()
+        .stack_size(8 << 20)
+        .spawn(|| {
+            run(&program(
+                "funk Tiefe(Zahl N) ergibt Zahl {
+                    falls N == 0 { 0 } sonst { Tiefe(N - 1) + 1 }
+                }",
+                "Tiefe(1000);",
+            ))
+            .0
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let msg = res.unwrap_err();
+    assert!(
+        msg.starts_with("Maximale Rekursionstiefe überschritten: Es dürfen höchstens 300 "),
+        "{msg}"
+    );
+}
+
+#[test]
+fn stepper_limits_call_depth() {
+    let source = program(
+        "funk Rek(Zahl N) ergibt Nichts {
+            setze Zahl Rest auf N;
+            solange Rest > 0 {
+                Rek(Rest - 1);
+                ändere Rest auf 0;
+            }
+        }",
+        "Rek(100);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut stepper = InterpreterBuilder::new(vec![], NoHttpClient)
+        .with_max_call_depth(50)
+        .build()
+        .into_stepper(&tree);
+    let err = stepper.run_steps(10_000).unwrap_err().to_string();
+    assert!(
+        err.starts_with("Maximale Rekursionstiefe überschritten: Es dürfen höchstens 50 "),
+        "{err}"
+    );
+}
+
+#[test]
+fn tail_calls_do_not_nest() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+
+        funk Countdown(Zahl N / Zahl Summe) ergibt Zahl {
+            falls N == 0 { Summe } sonst { Countdown(N - 1 / Summe + N) }
+        }
+
+        funk Zähle(Zahl N) ergibt Zahl {
+            falls N > 0 {
+                falls N % 2 == 0 {
+                    überweise Zähle(N - 1);
+                }
+                überweise (Zähle(N - 1));
+            }
+            überweise N;
+        }",
+        "Drucke(Countdown(100000 / 0));
+        Drucke(Zähle(100000));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "5000050000\n0\n");
+}
+
+#[test]
+fn lies_zeile_reads_lines() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Lies_Zeile von libSAP;",
+        "setze Zeichenkette Name auf Lies_Zeile();
+        Drucke(\"Hallo\" / Name);
+        Drucke(Lies_Zeile() == \"\");
+        Drucke(Lies_Zeile());
+        Drucke(Lies_Zeile());",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let res = InterpreterBuilder::new(&mut output, NoHttpClient)
+        .with_input("Welt\r\n\nohne Zeilenumbruch".as_bytes())
+        .build()
+        .run(tree);
+
+    assert_eq!(
+        res,
+        Err("`Lies_Zeile` hat das Ende der Eingabe erreicht.".into())
+    );
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "Hallo Welt\ntrue\nohne Zeilenumbruch\n"
+    );
+}
+
+#[test]
+fn gross_und_kleinbuchstaben() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Grossbuchstaben von Textverarbeitung;
+        beantrage Kleinbuchstaben von Textverarbeitung;",
+        "Drucke(Grossbuchstaben(\"Straße über Äcker\"));
+        Drucke(Kleinbuchstaben(\"ÖLFÄSSER Übermaß\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "STRASSE ÜBER ÄCKER\nölfässer übermaß\n");
+}
+
+#[test]
+fn trennen_splits_on_separator() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Trennen von Textverarbeitung;",
+        "Drucke(Trennen(\"a, b,, c\" / \", \"));
+        Drucke(Trennen(\"a,,b,\" / \",\").Länge());
+        Drucke(Trennen(\"abc\" / \"\"));",
+    ));
+    assert_eq!(
+        res,
+        Err("`Trennen` benötigt ein nicht-leeres Trennzeichen.".into())
+    );
+    assert_eq!(output, "[a / b, / c]\n4\n");
+}
+
+#[test]
+fn ersetzen_replaces_all_occurrences() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Ersetzen von Textverarbeitung;",
+        "Drucke(Ersetzen(\"Hallo Welt, hallo Welt\" / \"Welt\" / \"HPI\"));
+        Drucke(Ersetzen(\"aaa\" / \"aa\" / \"b\") / Ersetzen(\"aaaa\" / \"aa\" / \"a\"));
+        Drucke(Ersetzen(\"abc\" / \"x\" / \"y\") / Ersetzen(\"a-b-c\" / \"-\" / \"\"));
+        Drucke(Ersetzen(\"abc\" / \"\" / \"-\"));",
+    ));
+    assert_eq!(
+        res,
+        Err("`Ersetzen` benötigt eine nicht-leere zu ersetzende Zeichenkette.".into())
+    );
+    assert_eq!(output, "Hallo HPI, hallo HPI\nba aa\nabc abc\n");
+}
+
+#[test]
+fn zuschneiden_removes_whitespace() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Zuschneiden von Textverarbeitung;
+        beantrage Zuschneiden_Links von Textverarbeitung;
+        beantrage Zuschneiden_Rechts von Textverarbeitung;",
+        "setze Zeichenkette Text auf \"\\t\u{a0} Hallo Welt \\r\\n\";
+        Drucke(Zuschneiden(Text) == \"Hallo Welt\");
+        Drucke(Zuschneiden_Links(Text) == \"Hallo Welt \\r\\n\");
+        Drucke(Zuschneiden_Rechts(Text) == \"\\t\u{a0} Hallo Welt\");
+        Drucke(Zuschneiden(\" \\t\\n\u{a0}\") == \"\" / Zuschneiden_Links(\"\\t\") == \"\");",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true\ntrue\ntrue\ntrue true\n");
+}
+
+#[test]
+fn zeichen_bei_indexes_characters() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Zeichen_Bei von Textverarbeitung;",
+        "setze Zeichenkette Text auf \"Grüße\";
+        Drucke(Zeichen_Bei(Text / 0) / Zeichen_Bei(Text / 1) / Zeichen_Bei(Text / 4));
+        Drucke(Zeichen_Bei(Text / 5));",
+    ));
+    assert_eq!(
+        res,
+        Err(
+            "Illegale Indizierung: Index `5` liegt außerhalb der Zeichenkette der Länge `5`".into()
+        )
+    );
+    assert_eq!(output, "G r e\n");
+
+    let (res, _) = run(&program(
+        "beantrage Zeichen_Bei von Textverarbeitung;",
+        "Zeichen_Bei(\"Grüße\" / 2);",
+    ));
+    assert_eq!(
+        res,
+        Err("`Zeichen_Bei`: Das Zeichen `ü` an Index `2` ist kein ASCII-Zeichen und kann nicht als `Zeichen` dargestellt werden.".into())
+    );
+
+    let (res, _) = run(&program(
+        "beantrage Zeichen_Bei von Textverarbeitung;",
+        "setze Zahl Index auf 0 - 1;
+        Zeichen_Bei(\"abc\" / Index);",
+    ));
+    assert_eq!(res, Err("Illegale Indizierung mittels Index: `-1`".into()));
+}
+
+#[test]
+fn ist_zahl_checks_whether_casts_succeed() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Ist_Zahl von Textverarbeitung;
+        beantrage Ist_Fließkommazahl von Textverarbeitung;
+
+        funk Prüfe(Zeichenkette Eingabe) ergibt Nichts {
+            Drucke(Ist_Zahl(Eingabe) / Ist_Fließkommazahl(Eingabe));
+        }",
+        "Prüfe(\"12\");
+        Prüfe(\"0 - 12\");
+        Prüfe(\"-12\");
+        Prüfe(\"1,5\");
+        Prüfe(\"abc\");
+        Prüfe(\"\");
+        setze Zeichenkette Eingabe auf \"1,5\";
+        falls Ist_Fließkommazahl(Eingabe) {
+            Drucke(Eingabe als Fließkommazahl * 2,0);
+        }",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "true true\nfalse false\ntrue true\nfalse true\nfalse false\nfalse false\n3\n"
+    );
+}
+
+#[test]
+fn casts_ignore_surrounding_whitespace() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Ist_Zahl von Textverarbeitung;",
+        "Drucke(\" 42 \" als Zahl + 1 / \"\\t1,5\\n\" als Fließkommazahl / Ist_Zahl(\" 7\"));
+        setze Zeichenkette Leer auf \" \\t \";
+        Drucke(Ist_Zahl(Leer));
+        Drucke(Leer als Zahl);",
+    ));
+    assert_eq!(
+        res,
+        Err("Zeichenkettenverarbeitungsfehler in Zeichenkette ``: cannot parse integer from empty string".into())
+    );
+    assert_eq!(output, "43 1,5 true\nfalse\n");
+}
+
+#[test]
+fn index_von_counts_characters() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Index_Von von Textverarbeitung;",
+        "Drucke(Index_Von(\"Grüße\" / \"Gr\"));
+        Drucke(Index_Von(\"Grüße, Welt\" / \"Welt\"));
+        Drucke(Index_Von(\"Grüße\" / \"Welt\"));
+        Drucke(Index_Von(\"Grüße\" / \"\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "0\n7\n-1\n0\n");
+}
+
+#[test]
+fn beginnt_mit_and_endet_mit_check_affixes() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Beginnt_Mit von Textverarbeitung;
+        beantrage Endet_Mit von Textverarbeitung;",
+        "Drucke(Beginnt_Mit(\"Grüße\" / \"Grü\") / Beginnt_Mit(\"Grüße\" / \"ße\") / Beginnt_Mit(\"Grüße\" / \"\"));
+        Drucke(Endet_Mit(\"Grüße\" / \"ße\") / Endet_Mit(\"Grüße\" / \"Grü\") / Endet_Mit(\"Grüße\" / \"\"));
+        Drucke(Beginnt_Mit(\"\" / \"a\") / Endet_Mit(\"\" / \"\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true false true\ntrue false true\nfalse true\n");
+}
+
+#[test]
+fn wiederhole_repeats_strings() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Wiederhole von Textverarbeitung;",
+        "Drucke(Wiederhole(\"-ß\" / 3));
+        Drucke(Wiederhole(\"abc\" / 0) == \"\");
+        Drucke(Wiederhole(\"\" / 1000000000000));
+        setze Zahl Anzahl auf 0 - 1;
+        Wiederhole(\"abc\" / Anzahl);",
+    ));
+    assert_eq!(
+        res,
+        Err("`Wiederhole`: Die Anzahl `-1` darf nicht negativ sein.".into())
+    );
+    assert_eq!(output, "-ß-ß-ß\ntrue\n\n");
+
+    let (res, _) = run(&program(
+        "beantrage Wiederhole von Textverarbeitung;",
+        "Wiederhole(\"abc\" / 1000000000000);",
+    ));
+    assert_eq!(
+        res,
+        Err("`Wiederhole`: Das Ergebnis wäre länger als die erlaubten `67108864` Bytes.".into())
+    );
+}
+
+#[test]
+fn char_casts_reject_values_outside_of_ascii() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Zeichen_Code von Textverarbeitung;",
+        "setze Zahl A auf 65;
+        setze Zahl Grenze auf 127;
+        Drucke(A als Zeichen == 'A' / 65 als Zeichen == 'A' / 65,9 als Zeichen == 'A');
+        Drucke(Zeichen_Code(Grenze als Zeichen) / Zeichen_Code(127 als Zeichen));
+        Drucke((Grenze + 1) als Zeichen);",
+    ));
+    assert_eq!(
+        res,
+        Err("Typumwandlungsfehler: `128` liegt außerhalb des ASCII-Bereichs von `0` bis `127` und kann nicht in ein `Zeichen` umgewandelt werden.".into())
+    );
+    assert_eq!(output, "true true true\n127 127\n");
+
+    for (value, displayed) in [("(0 - 1)", "-1"), ("128", "128"), ("(0,0 - 0,5)", "-0,5")] {
+        let (res, _) = run(&program("", &format!("{value} als Zeichen;")));
+        assert_eq!(
+            res,
+            Err(format!("Typumwandlungsfehler: `{displayed}` liegt außerhalb des ASCII-Bereichs von `0` bis `127` und kann nicht in ein `Zeichen` umgewandelt werden."))
+        );
+    }
+}
+
+#[test]
+fn zeichen_code_and_von_code_convert_ascii() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Zeichen_Code von Textverarbeitung;
+        beantrage Von_Code von Textverarbeitung;",
+        "Drucke(Zeichen_Code('A') / Zeichen_Code(Von_Code(0)) / Zeichen_Code(Von_Code(127)));
+        Drucke(Von_Code(65) == 'A');
+        Von_Code(128);",
+    ));
+    assert_eq!(
+        res,
+        Err(
+            "`Von_Code`: Der Code `128` liegt außerhalb des ASCII-Bereichs von `0` bis `127`."
+                .into()
+        )
+    );
+    assert_eq!(output, "65 0 127\ntrue\n");
+
+    let (res, _) = run(&program(
+        "beantrage Von_Code von Textverarbeitung;",
+        "setze Zahl Code auf 0 - 1;
+        Von_Code(Code);",
+    ));
+    assert_eq!(
+        res,
+        Err(
+            "`Von_Code`: Der Code `-1` liegt außerhalb des ASCII-Bereichs von `0` bis `127`."
+                .into()
+        )
+    );
+}
+
+#[test]
+fn teilzeichenkette_extracts_substrings() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Teilzeichenkette von Textverarbeitung;",
+        "setze Zeichenkette Zeile auf \"0042Grüße  Ende\";
+        Drucke(Teilzeichenkette(Zeile / 0 / 4) als Zahl + 1);
+        Drucke(Teilzeichenkette(Zeile / 4 / 11) == \"Grüße  \");
+        Drucke(Teilzeichenkette(Zeile / 11 / 15));
+        Drucke(Teilzeichenkette(Zeile / 15 / 15) == \"\");
+        Drucke(Teilzeichenkette(Zeile / 11 / 16));",
+    ));
+    assert_eq!(
+        res,
+        Err("`Teilzeichenkette`: Der Bereich von `11` bis `16` liegt außerhalb der Zeichenkette der Länge `15`.".into())
+    );
+    assert_eq!(output, "43\ntrue\nEnde\ntrue\n");
+
+    let (res, _) = run(&program(
+        "beantrage Teilzeichenkette von Textverarbeitung;",
+        "Teilzeichenkette(\"abc\" / 2 / 1);",
+    ));
+    assert_eq!(
+        res,
+        Err("`Teilzeichenkette`: Der Anfang `2` liegt hinter dem Ende `1`.".into())
+    );
+
+    let (res, _) = run(&program(
+        "beantrage Teilzeichenkette von Textverarbeitung;",
+        "setze Zahl Anfang auf 0 - 1;
+        Teilzeichenkette(\"abc\" / Anfang / 1);",
+    ));
+    assert_eq!(
+        res,
+        Err("`Teilzeichenkette`: Der Bereich von `-1` bis `1` liegt außerhalb der Zeichenkette der Länge `3`.".into())
+    );
+}
+
+#[test]
+fn verbinden_joins_strings() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Verbinden von Textverarbeitung;",
+        "setze Liste von Zeichenkette Leer auf [];
+        Drucke(Verbinden([\"a\" / \"b\" / \"c\"] / \", \"));
+        Drucke(Verbinden([\"allein\"] / \", \"));
+        Drucke(Verbinden(Leer / \", \") == \"\");",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "a, b, c\nallein\ntrue\n");
+}
+
+#[test]
+fn verbinden_rejects_other_lists() {
+    let source = program(
+        "beantrage Verbinden von Textverarbeitung;",
+        "setze Zeichenkette Text auf Verbinden([1 / 2] / \", \");",
+    );
+    assert!(hpi_analyzer::analyze(&source, "test.hpi").is_err());
+}
+
+#[test]
+fn hole_und_setze_modify_speicherboxen() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Hole von libSAP;
+        beantrage Setze von libSAP;
+        beantrage Umgebungsvariablen von libSAP;
+
+        funk Ergänze(Speicherbox Box) ergibt Nichts {
+            Setze(Box / \"Neu\" / 42);
+        }",
+        "setze Speicherbox Box auf Umgebungsvariablen();
+        Drucke(Hole(Box / \"NAME\") als Zeichenkette);
+        Setze(Box / \"NAME\" / \"anders\");
+        Ergänze(Box);
+        Drucke(Hole(Box / \"NAME\") als Zeichenkette / Hole(Box / \"Neu\") als Zahl);
+        Drucke(Hole(Box / \"Fehlt\") als Zahl);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let env = HashMap::from([("NAME".to_string(), "Wert".to_string())]);
+    let res = Interpreter::new(&mut output, NoHttpClient, env).run(tree);
+
+    assert_eq!(
+        res,
+        Err("`Hole`: Die Speicherbox enthält keinen Schlüssel `Fehlt`.".into())
+    );
+    assert_eq!(String::from_utf8(output).unwrap(), "Wert\nanders 42\n");
+}
+
+#[test]
+fn schluessel_returns_sorted_keys() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Schlüssel von libSAP;
+        beantrage Umgebungsvariablen von libSAP;",
+        "setze Speicherbox Box auf Umgebungsvariablen();
+        Drucke(Schlüssel(Box));",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let env = HashMap::from([
+        ("PFAD".to_string(), "/bin".to_string()),
+        ("BENUTZER".to_string(), "hpi".to_string()),
+        ("ZUHAUSE".to_string(), "/home/hpi".to_string()),
+    ]);
+    let res = Interpreter::new(&mut output, NoHttpClient, env).run(tree);
+
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "[BENUTZER / PFAD / ZUHAUSE]\n"
+    );
+}
+
+#[test]
+fn json_null_is_nichts() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Zergliedere_JSON von Textverarbeitung;
+        beantrage Gliedere_JSON von Textverarbeitung;",
+        "setze Speicherbox Antwort auf Zergliedere_JSON(\"{\\\"a\\\": null, \\\"b\\\": 1}\") als Speicherbox;
+        Drucke(Antwort.Datentyp_Von(\"a\") == \"Nichts\" / Antwort.Datentyp_Von(\"b\") == \"Nichts\");
+        Drucke(Gliedere_JSON(Antwort));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true false\n{\"a\":null,\"b\":1}\n");
+}
+
+#[test]
+fn zergliedere_json_streng_rejects_duplicate_keys() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Zergliedere_JSON von Textverarbeitung;
+        beantrage Zergliedere_JSON_Streng von Textverarbeitung;",
+        "setze Speicherbox Verschachtelt auf Zergliedere_JSON_Streng(\"{\\\"a\\\":{\\\"a\\\":1}}\") als Speicherbox;
+        Drucke(Verschachtelt);
+        setze Speicherbox Letzter auf Zergliedere_JSON(\"{\\\"a\\\":1,\\\"a\\\":2}\") als Speicherbox;
+        Drucke(Letzter);
+        Zergliedere_JSON_Streng(\"{\\\"a\\\":1,\\\"a\\\":2}\") als Speicherbox;",
+    ));
+    assert_eq!(
+        res,
+        Err("Zergliedere_JSON_Streng Textverarbeitungsfehler: JSON-Fehler bei Zeile 1, Spalte 10 (Zeichen 9): ungültige Daten (der Schlüssel `a` ist mehrfach vorhanden)".into())
+    );
+    assert_eq!(
+        output,
+        "Speicherbox {\n    a: Speicherbox {\n        a: 1\n    }\n}\nSpeicherbox {\n    a: 2\n}\n"
+    );
+}
+
+#[test]
+fn typ_names_the_runtime_type() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Typ von libSAP;
+        beantrage Zergliedere_JSON von Textverarbeitung;
+
+        funk Verdopple(Zahl Wert) ergibt Zahl { Wert * 2 }",
+        "Drucke(Typ(1) / Typ(1,5) / Typ('a') / Typ(ja) / Typ(\"a\"));
+        setze Speicherbox Antwort auf Zergliedere_JSON(\"{\\\"a\\\": [1, 2], \\\"b\\\": null}\") als Speicherbox;
+        Drucke(Typ(Antwort) / Typ(Antwort.Nehmen(\"a\")) / Typ(Antwort.Nehmen(\"b\")));
+        setze Objekt { Zeichenkette Name } Student auf erstelle { Zeichenkette Name auf \"Lena\" };
+        setze Liste von Zahl Leer auf [];
+        Drucke(Typ(Student) / Typ(Verdopple) / Typ(Leer));
+        setze Fließkommazahl Wert auf 1,5;
+        setze Zeiger auf Fließkommazahl Verweis auf &Wert;
+        Drucke(Typ(Verweis) / Typ(&Verweis));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "Zahl Fließkommazahl Zeichen Wahrheitswert Zeichenkette\nSpeicherbox Liste Nichts\nObjekt Funktion Liste\nZeiger auf Fließkommazahl Zeiger auf Zeiger auf Fließkommazahl\n"
+    );
+}
+
+#[test]
+fn feld_looks_up_members_by_name() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Feld von libSAP;
+        beantrage Objekt_Schlüssel von libSAP;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "setze Speicherbox Preise auf Zergliedere_JSON(\"{\\\"Apfel\\\": 2, \\\"Birne\\\": 3}\") als Speicherbox;
+        setze Liste von Zeichenkette Sorten auf Objekt_Schlüssel(Preise);
+        Drucke(Sorten[0] / Feld(Preise / Sorten[0]) als Zahl + Feld(Preise / Sorten[1]) als Zahl);
+        setze Objekt { Zeichenkette Name } Student auf erstelle { Zeichenkette Name auf \"Lena\" };
+        Drucke(Feld(Student / \"Name\") als Zeichenkette);
+        Feld(Student / \"Semester\") als Zahl;",
+    ));
+    assert_eq!(
+        res,
+        Err("`Feld`: Das Objekt enthält kein Feld `Semester`.".into())
+    );
+    assert_eq!(output, "Apfel 5\nLena\n");
+}
+
+#[test]
+fn objekt_schluessel_lists_members() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Objekt_Schlüssel von libSAP;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "setze Speicherbox Antwort auf Zergliedere_JSON(\"{\\\"zeit\\\": 1, \\\"daten\\\": {\\\"b\\\": 2, \\\"a\\\": 3}}\") als Speicherbox;
+        Drucke(Objekt_Schlüssel(Antwort));
+        Drucke(Objekt_Schlüssel(Zergliedere_JSON(\"{}\") als Speicherbox));
+        setze Objekt { Zahl Semester / Zeichenkette Name } Student auf erstelle { Zahl Semester auf 3 / Zeichenkette Name auf \"Lena\" };
+        Drucke(Objekt_Schlüssel(Student));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "[daten / zeit]\n[]\n[Name / Semester]\n");
+}
+
+#[test]
+fn gliedere_json_schoen_indents() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Gliedere_JSON_Schön von Textverarbeitung;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "setze Speicherbox Daten auf Zergliedere_JSON(\"{\\\"b\\\": [{\\\"c\\\": []}, {\\\"d\\\": 1}], \\\"a\\\": {}}\") als Speicherbox;
+        Drucke(Gliedere_JSON_Schön(Daten));
+        setze Liste von Zahl Leer auf [];
+        Drucke(Gliedere_JSON_Schön(Leer));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "{
+  \"a\": {},
+  \"b\": [
+    {
+      \"c\": []
+    },
+    {
+      \"d\": 1
+    }
+  ]
+}
+[]
+"
+    );
+}
+
+#[test]
+fn enthaelt_strings_and_lists() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Enthält von libSAP;",
+        "Drucke(Enthält(\"Hallo Welt\" / \"lo W\") / Enthält(\"Hallo\" / \"hallo\"));
+        Drucke(Enthält(\"Hallo\" / \"\") / Enthält(\"\" / \"\") / Enthält(\"\" / \"a\"));
+        Drucke(Enthält([1 / 2 / 3] / 2) / Enthält([1 / 2 / 3] / 4));
+        setze Liste von Zeichenkette Leer auf [];
+        Drucke(Enthält(Leer / \"\") / Enthält([\"a\" / \"\"] / \"\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "true false\ntrue true false\ntrue false\nfalse true\n"
+    );
+}
+
+#[test]
+fn lists_and_objects_are_compared_by_contents() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "setze Liste von Zahl Links auf [1 / 2 / 3];
+        setze Liste von Zahl Rechts auf [1];
+        Rechts.Hinzufügen(2);
+        Rechts.Hinzufügen(3);
+        Drucke(Links == Rechts / Links != Rechts / Links == [3 / 2 / 1] / Links == [1 / 2]);
+        Drucke([[1] / [2 / 3]] == [[1] / [2 / 3]] / [[1] / [2 / 3]] == [[2 / 3] / [1]]);
+        setze Objekt { Zahl a / Zeichenkette b } Erstes auf erstelle { Zahl a auf 1 / Zeichenkette b auf \"x\" };
+        setze Objekt { Zahl a / Zeichenkette b } Zweites auf erstelle { Zahl a auf 1 / Zeichenkette b auf \"x\" };
+        setze Objekt { Zahl a / Zeichenkette b } Drittes auf erstelle { Zahl a auf 2 / Zeichenkette b auf \"x\" };
+        Drucke(Erstes == Zweites / Erstes == Drittes);
+        Drucke(
+            Zergliedere_JSON(\"{\\\"a\\\": 1, \\\"b\\\": [2]}\") als Speicherbox
+                == Zergliedere_JSON(\"{\\\"b\\\": [2], \\\"a\\\": 1}\") als Speicherbox
+        );",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "true false false false\ntrue false\ntrue false\ntrue\n"
+    );
+}
+
+#[test]
+fn comparing_cyclic_values_terminates() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Setze von libSAP;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "setze Speicherbox Box auf Zergliedere_JSON(\"{\\\"a\\\": 1}\") als Speicherbox;
+        Setze(Box / \"selbst\" / Box);
+        setze Speicherbox Andere auf Zergliedere_JSON(\"{\\\"a\\\": 1}\") als Speicherbox;
+        Setze(Andere / \"selbst\" / Andere);
+        Drucke(Box == Andere / Box == Box / Box != Andere);
+        Setze(Andere / \"a\" / 2);
+        Drucke(Box == Andere);",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "true true false\nfalse\n");
+}
+
+#[test]
+fn kopiere_copies_deeply() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Kopiere von libSAP;
+        beantrage Hinzufügen von libSAP;
+        beantrage Setze von libSAP;
+        beantrage Hole von libSAP;
+        beantrage Schlüssel von libSAP;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "setze Liste von Liste von Zahl Tabelle auf [[1] / [2]];
+        setze Liste von Liste von Zahl Kopie auf Kopiere(Tabelle);
+        Hinzufügen(Kopie[0] / 9);
+        Hinzufügen(Kopie / [3]);
+        Drucke(Tabelle / Kopie);
+
+        setze Speicherbox Box auf Zergliedere_JSON(\"{\\\"a\\\": [1]}\") als Speicherbox;
+        Setze(Box / \"selbst\" / Box);
+        setze Speicherbox Kopierte auf Kopiere(Box);
+        Setze(Kopierte / \"b\" / 2);
+        Drucke(Schlüssel(Box) / Schlüssel(Kopierte));
+        Drucke(Schlüssel(Hole(Kopierte / \"selbst\") als Speicherbox));
+        Drucke(Kopiere(42) / Kopiere(\"Text\"));",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        output,
+        "[[1] / [2]] [[1 / 9] / [2] / [3]]\n[a / selbst] [a / b / selbst]\n[a / b / selbst]\n42 Text\n"
+    );
+}
+
+#[test]
+fn hinzufuegen_und_entferne_letztes_modify_lists() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;
+        beantrage Hinzufügen von libSAP;
+        beantrage Entferne_Letztes von libSAP;",
+        "setze Liste von Zahl Zahlen auf [1];
+        setze Liste von Zahl Alias auf Zahlen;
+        Hinzufügen(Zahlen / 2);
+        Hinzufügen(Alias / 3);
+        Drucke(Zahlen / Alias);
+        Drucke(Entferne_Letztes(Alias) / Zahlen);
+        Drucke(Entferne_Letztes(Zahlen) / Entferne_Letztes(Zahlen) / Alias);
+        Entferne_Letztes(Alias);",
+    ));
+    assert_eq!(
+        res,
+        Err("`Entferne_Letztes` kann nicht aus einer leeren Liste entfernen.".into())
+    );
+    assert_eq!(output, "[1 / 2 / 3] [1 / 2 / 3]\n3 [1 / 2]\n2 1 []\n");
+}
+
+#[test]
+fn umkehren_strings_and_lists() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Umkehren von libSAP;",
+        "setze Liste von Zahl Zahlen auf [1 / 2 / 3];
+        Drucke(Umkehren(Zahlen) / Zahlen);
+        Drucke(Umkehren(\"Mühle\") / Umkehren(\"größer\") / Umkehren(\"\") == \"\");",
+    ));
+    assert_eq!(res, Ok(0));
+    assert_eq!(output, "[3 / 2 / 1] [1 / 2 / 3]\nelhüM reßörg true\n");
+}
+
+#[test]
+fn sortiere_sorts_ascending() {
+    let (res, output) = run(&program(
+        "beantrage Drucke von Drucker;\nbeantrage Sortiere von libSAP;",
+        "setze Liste von Zahl Zahlen auf [3 / 0 - 1 / 2 / 3 / 0];
+        Drucke(Sortiere(Zahlen) / Zahlen);
+        Drucke(Sortiere([\"Birne\" / \"Apfel\" / \"apfel\" / \"Banane\"]));
+        Drucke(Sortiere([2,5 / 0,0 - 1,5 / 1,0]) / Sortiere(['c' / 'a' / 'b']));
+        setze Liste von Zahl Leer auf [];
+        Drucke(Sortiere(Leer));
+        Drucke(Sortiere([1,0 / 0,0 : 0,0]));",
+    ));
+    assert_eq!(
+        res,
+        Err("`Sortiere` kann keine Liste sortieren, welche `NaN` enthält.".into())
+    );
+    assert_eq!(
+        output,
+        "[-1 / 0 / 2 / 3 / 3] [3 / -1 / 2 / 3 / 0]\n[Apfel / Banane / Birne / apfel]\n[-1,5 / 1 / 2,5] [a / b / c]\n[]\n"
+    );
+}
+
+#[test]
+fn runtime_errors_list_the_call_stack() {
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Versuche von libSAP;
+
+        funk Teile(Zahl Wert) ergibt Zahl { 10 : Wert }
+        funk Berechne(Zahl Wert) ergibt Zahl { Teile(Wert) + 1 }
+        funk Fehlschlag() ergibt Zahl { Berechne(0) }",
+        "Drucke(Versuche(Fehlschlag).Fehler);
+        setze Zahl Null auf 0;
+        Drucke(10 : Null);",
+    );
+    let (res, output) = run(&source);
+    // errors caught by `Versuche` do not leave a stale call stack behind
+    assert_eq!(res, Err("division by zero".into()));
+    assert_eq!(output, "division by zero\n");
+
+    let (res, _) = run(&source.replace("Drucke(10 : Null);", "Berechne(Null);"));
+    assert_eq!(
+        res,
+        Err("division by zero
+Aufrufstapel (innerster Aufruf zuletzt):
+  Studium
+  Berechne
+  Teile"
+            .into())
+    );
+}
+
+#[test]
+fn step_limit_bounds_execution() {
+    // `Einschreibung` executes one statement, `Studium` two statements and three iterations
+    // containing one statement each
+    let source = program(
+        "",
+        "setze Zahl Zähler auf 0;
+        solange Zähler < 3 {
+            ändere Zähler auf Zähler + 1;
+        }",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    for (step_limit, expected) in [
+        (9, Ok(0)),
+        (
+            8,
+            Err(
+                "Ausführungsbudget erschöpft: Es dürfen höchstens 8 Schritte ausgeführt werden."
+                    .into(),
+            ),
+        ),
+    ] {
+        let res = InterpreterBuilder::new(vec![], NoHttpClient)
+            .with_step_limit(step_limit)
+            .build()
+            .run(tree.clone());
+        assert_eq!(res, expected, "{step_limit}");
+    }
+
+    let source = program("", "solange ja {}");
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let mut stepper = InterpreterBuilder::new(vec![], NoHttpClient)
+        .with_step_limit(100)
+        .build()
+        .into_stepper(&tree);
+
+    let res = (0..1000)
+        .find_map(|_| stepper.run_steps(10).err())
+        .expect("the loop is stopped by the step limit");
+    assert_eq!(
+        res.to_string(),
+        "Ausführungsbudget erschöpft: Es dürfen höchstens 100 Schritte ausgeführt werden."
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn async_http_client_is_driven_to_completion() {
+    use std::{
+        future::{poll_fn, Future},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::Poll,
+    };
+
+    use crate::{AsyncHPIHttpClient, BlockingHttpClient};
+
+    /// Completes each request after being polled three times.
+    struct SlowClient(Arc<AtomicUsize>);
+
+    impl AsyncHPIHttpClient for SlowClient {
+        fn request(
+            &self,
+            method: String,
+            url: &str,
+            _body: String,
+            _headers: HashMap<String, String>,
+        ) -> impl Future<Output = Result<(u16, String, HashMap<String, String>), HttpError>>
+        {
+            let response = format!("{method} {url}");
+            let polls = Arc::clone(&self.0);
+            poll_fn(move |context| {
+                if polls.fetch_add(1, Ordering::SeqCst) % 3 < 2 {
+                    context.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                Poll::Ready(Ok((200, response.clone(), HashMap::new())))
+            })
+        }
+    }
+
+    let source = program(
+        "beantrage Drucke von Drucker;\nbeantrage Http von Netzwerk;",
+        "setze Zeichenkette Körper auf \"\";
+        setze Zahl Status auf Http(\"GET\" / \"https://example.com\" / \"\" / [] / &Körper);
+        Drucke(Status / Körper);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let polls = Arc::new(AtomicUsize::new(0));
+    let mut output = vec![];
+    let client = BlockingHttpClient(SlowClient(Arc::clone(&polls)));
+    let res = Interpreter::new(&mut output, client, HashMap::new()).run(tree.clone());
+
+    assert_eq!(res, Ok(0));
+    assert_eq!(polls.load(Ordering::SeqCst), 3);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "200 GET https://example.com\n"
+    );
+
+    // such a timeout cannot be represented as a deadline, so there is none
+    let mut output = vec![];
+    let client = BlockingHttpClient(SlowClient(Arc::clone(&polls)));
+    let res = InterpreterBuilder::new(&mut output, client)
+        .with_http_timeout(Duration::MAX)
+        .build()
+        .run(tree.clone());
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "200 GET https://example.com\n"
+    );
+
+    /// Never receives a response.
+    struct HangingClient;
+
+    impl AsyncHPIHttpClient for HangingClient {
+        fn request(
+            &self,
+            _method: String,
+            _url: &str,
+            _body: String,
+            _headers: HashMap<String, String>,
+        ) -> impl Future<Output = Result<(u16, String, HashMap<String, String>), HttpError>>
+        {
+            std::future::pending()
+        }
+    }
+
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let res = InterpreterBuilder::new(vec![], BlockingHttpClient(HangingClient))
+        .with_http_timeout(Duration::from_millis(20))
+        .build()
+        .run(tree);
+    assert_eq!(
+        res,
+        Err("Zeitüberschreitung bei HTTP-Anfrage: Die Antwort auf `https://example.com` ist nicht innerhalb von 20 Millisekunden eingetroffen.".into())
+    );
+}
+
+#[test]
+fn http_exposes_response_headers() {
+    struct HeaderClient;
+
+    impl HPIHttpClient for HeaderClient {
+        fn request(
+            &self,
+            _method: String,
+            _url: &str,
+            _body: String,
+            _headers: HashMap<String, String>,
+            _timeout: Duration,
+        ) -> Result<(u16, String, HashMap<String, String>), HttpError> {
+            let headers = HashMap::from([
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("location".to_string(), "/neu".to_string()),
+            ]);
+            Ok((301, "{}".to_string(), headers))
+        }
+    }
+
+    let source = program(
+        "beantrage Drucke von Drucker;
+        beantrage Http von Netzwerk;
+        beantrage Zergliedere_JSON von Textverarbeitung;",
+        "setze Zeichenkette Körper auf \"\";
+        setze Speicherbox Kopfzeilen auf Zergliedere_JSON(\"{}\") als Speicherbox;
+        Drucke(Http(\"GET\" / \"https://example.com\" / \"\" / [] / &Körper));
+        Drucke(Http(\"GET\" / \"https://example.com\" / \"\" / [] / &Körper / &Kopfzeilen) / Körper);
+        Drucke(Kopfzeilen.Schlüssel());
+        Drucke(Kopfzeilen.Nehmen(\"content-type\") als Zeichenkette);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+
+    let mut output = vec![];
+    let res = Interpreter::new(&mut output, HeaderClient, HashMap::new()).run(tree);
+
+    assert_eq!(res, Ok(0));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "301\n301 {}\n[content-type / location]\napplication/json\n"
+    );
+}
+
+#[test]
+fn http_reports_timeouts() {
+    struct SlowClient;
+
+    impl HPIHttpClient for SlowClient {
+        fn request(
+            &self,
+            _method: String,
+            _url: &str,
+            _body: String,
+            _headers: HashMap<String, String>,
+            timeout: Duration,
+        ) -> Result<(u16, String, HashMap<String, String>), HttpError> {
+            assert_eq!(timeout, Duration::from_secs(30));
+            Err(HttpError::Timeout)
+        }
+    }
+
+    let source = program(
+        "beantrage Http von Netzwerk;",
+        "setze Zeichenkette Körper auf \"\";
+        Http(\"GET\" / \"https://example.com\" / \"\" / [] / &Körper);",
+    );
+    let (tree, _) = hpi_analyzer::analyze(&source, "test.hpi").expect("program is valid");
+    let res = Interpreter::new(vec![], SlowClient, HashMap::new()).run(tree);
+
+    assert_eq!(
+        res,
+        Err("Zeitüberschreitung bei HTTP-Anfrage: Die Antwort auf `https://example.com` ist nicht innerhalb von 30000 Millisekunden eingetroffen.".into())
+    );
+}
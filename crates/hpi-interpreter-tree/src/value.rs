@@ -1,23 +1,88 @@
-use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    rc::Rc,
+};
 
 use hpi_analyzer::Type;
 use hpi_parser::ast::ObjectTypeField;
+use indexmap::IndexMap;
 
 use crate::interpreter;
 
-#[derive(Clone, Debug, PartialEq)]
+/// Specifies how floats are rendered when values are displayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatNotation {
+    /// Always uses the decimal representation, e.g. `0,000001`, even for huge or tiny numbers.
+    #[default]
+    Decimal,
+    /// Uses scientific notation, e.g. `1e-6`.
+    Scientific,
+}
+
+/// Specifies how pointers are rendered when values are displayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PointerNotation {
+    /// Shows the value behind the pointer, e.g. `Zeiger auf 42`.
+    /// Values which contain themselves are shown as `<Zyklus>` when they are reached again.
+    #[default]
+    Pointee,
+    /// Only shows that the value is a pointer: `<Zeiger>`.
+    Opaque,
+}
+
+/// Configures how values are rendered by `Drucke` and similar builtins.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct DisplayOptions {
+    pub(crate) float_notation: FloatNotation,
+    pub(crate) pointer_notation: PointerNotation,
+}
+
+/// A value of an HPI program.
+///
+/// Values which are constructed by a host are only valid if they match the types the analyzer
+/// expects at that place, e.g. the inner values of a list must all have the same type.
+/// Lists, objects, and pointers share their contents when cloned, which is how HPI implements
+/// references, [`Value::deep_clone`] creates an independent copy instead.
+/// Values are compared by their contents, not by their identity: lists element-wise in order,
+/// objects and Speicherboxen by their members regardless of the order of their keys, and pointers
+/// by the values they point to.
+/// Comparing containers which contain themselves terminates.
+#[derive(Clone, Debug)]
 pub enum Value {
     Int(i64),
     Float(f64),
+    /// An ASCII character.
     Char(u8),
     String(String),
     List(Rc<RefCell<Vec<Value>>>),
     Bool(bool),
+    /// The value of type `Nichts`.
     Unit,
+    /// A pointer created using `&`, modifying the inner value is visible through every clone.
+    /// It is safe to construct as long as the pointee is valid.
     Ptr(Rc<RefCell<Value>>),
-    Speicherbox(HashMap<String, Value>),
-    Objekt(Rc<RefCell<HashMap<String, Value>>>),
-    BuiltinFunction(Box<Value>, fn(&Value, Vec<Value>) -> Value),
+    /// A dynamically typed object, e.g. the result of `Zergliedere_JSON`.
+    /// Like objects, Speicherboxen have reference semantics, so changes made using `Setze` are
+    /// visible through every copy.
+    Speicherbox(Rc<RefCell<IndexMap<String, Value>>>),
+    /// An object whose members were declared as the fields of its type.
+    /// The members must match the fields of the expected type exactly.
+    Objekt(Rc<RefCell<IndexMap<String, Value>>>),
+    /// A method bound to its receiver, e.g. the result of `Liste.Länge`.
+    /// The function pointer is one of the interpreter's internal method implementations, so this
+    /// variant should only be passed back to the interpreter, not constructed by hosts.
+    BuiltinFunction(
+        Box<Value>,
+        fn(&Value, Vec<Value>) -> Result<Value, InterruptKind>,
+    ),
+    /// A user-defined function which is referenced by its name.
+    /// Created when a function is used as a value, e.g. `Versuche(Funktion)` or
+    /// `Abbilden(Liste / Funktion)`.
+    /// The type is the `funk(..) ergibt ..` type of the function, calling the value looks the
+    /// function up by its name.
+    Function(String, Type),
 }
 
 impl From<Value> for Type {
@@ -68,125 +133,264 @@ impl Value {
                 Type::Object(inner, 0)
             }
             Value::BuiltinFunction(_, _) => unreachable!("this does not work!"),
+            Value::Function(_, type_) => type_.clone(),
         }
     }
 }
 
+impl Value {
+    /// Copies the value including all nested lists, objects, and Speicherboxen, so that changes
+    /// made to the copy are not visible through the original (`Kopiere`).
+    /// Pointers are copied shallowly, so the copy points to the same variable as the original.
+    /// Containers which are shared or contain themselves are copied once, so the copy is shared
+    /// in the same way.
+    pub fn deep_clone(&self) -> Self {
+        self.deep_clone_inner(&mut HashMap::new())
+    }
+
+    /// `copies` maps the address of every container which was copied to its copy.
+    fn deep_clone_inner(&self, copies: &mut HashMap<*const (), Value>) -> Self {
+        let address = match self {
+            Value::List(inner) => Rc::as_ptr(inner) as *const (),
+            Value::Speicherbox(inner) | Value::Objekt(inner) => Rc::as_ptr(inner) as *const (),
+            other => return other.clone(),
+        };
+        if let Some(copy) = copies.get(&address) {
+            return copy.clone();
+        }
+
+        // the empty copy is registered first, so that a container which contains itself refers
+        // to its copy
+        match self {
+            Value::List(inner) => {
+                let copy = Rc::new(RefCell::new(vec![]));
+                copies.insert(address, Value::List(Rc::clone(&copy)));
+                let values = inner
+                    .borrow()
+                    .iter()
+                    .map(|value| value.deep_clone_inner(copies))
+                    .collect();
+                *copy.borrow_mut() = values;
+                Value::List(copy)
+            }
+            Value::Speicherbox(inner) | Value::Objekt(inner) => {
+                let copy = Rc::new(RefCell::new(IndexMap::new()));
+                let wrapped = match self {
+                    Value::Speicherbox(_) => Value::Speicherbox(Rc::clone(&copy)),
+                    _ => Value::Objekt(Rc::clone(&copy)),
+                };
+                copies.insert(address, wrapped.clone());
+                let members = inner
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.deep_clone_inner(copies)))
+                    .collect();
+                *copy.borrow_mut() = members;
+                wrapped
+            }
+            _ => unreachable!("only containers have an address"),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    /// Containers are equal to themselves, even if they contain `NaN`.
+    /// Containers which are reached again while they are compared are assumed to be equal, so
+    /// that comparing cyclic values terminates.
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_inner(other, &mut HashSet::new())
+    }
+}
+
+/// The addresses of two containers which are compared.
+type ContainerPair = (*const (), *const ());
+
+impl Value {
+    /// `assumed` contains the pairs of containers which were already compared or are currently
+    /// compared.
+    /// As every inequality makes the whole comparison unequal, the pairs are never removed.
+    fn eq_inner(&self, other: &Self, assumed: &mut HashSet<ContainerPair>) -> bool {
+        match (self, other) {
+            (Value::Int(left), Value::Int(right)) => left == right,
+            (Value::Float(left), Value::Float(right)) => left == right,
+            (Value::Char(left), Value::Char(right)) => left == right,
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::Bool(left), Value::Bool(right)) => left == right,
+            (Value::Unit, Value::Unit) => true,
+            (Value::List(left), Value::List(right)) => {
+                eq_containers(left, right, assumed, |left, right, assumed| {
+                    left.len() == right.len()
+                        && left
+                            .iter()
+                            .zip(right)
+                            .all(|(left, right)| left.eq_inner(right, assumed))
+                })
+            }
+            (Value::Ptr(left), Value::Ptr(right)) => {
+                eq_containers(left, right, assumed, |left, right, assumed| {
+                    left.eq_inner(right, assumed)
+                })
+            }
+            (Value::Speicherbox(left), Value::Speicherbox(right))
+            | (Value::Objekt(left), Value::Objekt(right)) => {
+                eq_containers(left, right, assumed, |left, right, assumed| {
+                    left.len() == right.len()
+                        && left.iter().all(|(key, left)| {
+                            right
+                                .get(key)
+                                .is_some_and(|right| left.eq_inner(right, assumed))
+                        })
+                })
+            }
+            (
+                Value::BuiltinFunction(left, left_func),
+                Value::BuiltinFunction(right, right_func),
+            ) => std::ptr::fn_addr_eq(*left_func, *right_func) && left.eq_inner(right, assumed),
+            (Value::Function(left, left_type), Value::Function(right, right_type)) => {
+                left == right && left_type == right_type
+            }
+            _ => false,
+        }
+    }
+}
+
+fn eq_containers<T>(
+    left: &Rc<RefCell<T>>,
+    right: &Rc<RefCell<T>>,
+    assumed: &mut HashSet<ContainerPair>,
+    compare: impl FnOnce(&T, &T, &mut HashSet<ContainerPair>) -> bool,
+) -> bool {
+    if Rc::ptr_eq(left, right) {
+        return true;
+    }
+    let pair = (
+        Rc::as_ptr(left) as *const (),
+        Rc::as_ptr(right) as *const (),
+    );
+    if !assumed.insert(pair) {
+        return true;
+    }
+    compare(&left.borrow(), &right.borrow(), assumed)
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.display())
+        write!(f, "{}", self.display(DisplayOptions::default()))
     }
 }
 
-fn list_push(val: &Value, args: Vec<Value>) -> Value {
+fn list_push(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     match val {
         Value::List(values) => values.borrow_mut().push(args[0].clone()),
-        _ => unreachable!("the analyzer prevents this: {val:?}"),
+        _ => analyzer_guaranteed!("the analyzer prevents this: {val:?}"),
     }
-    Value::Unit
+    Ok(Value::Unit)
 }
 
-fn list_update(val: &Value, args: Vec<Value>) -> Value {
+fn list_update(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     match (val, &args[0]) {
         (Value::List(values), &Value::Int(idx)) => {
             values.borrow_mut()[idx as usize] = args[1].clone()
         }
-        (_, _) => unreachable!("the analyzer prevents this: {val:?}"),
+        (_, _) => analyzer_guaranteed!("the analyzer prevents this: {val:?}"),
     };
-    Value::Unit
+    Ok(Value::Unit)
 }
 
-fn list_length(val: &Value, _args: Vec<Value>) -> Value {
+fn list_length(val: &Value, _args: Vec<Value>) -> Result<Value, InterruptKind> {
     match val {
-        Value::List(values) => Value::Int(values.borrow().len() as i64),
-        _ => unreachable!("the analyzer prevents this: {val:?}"),
+        Value::List(values) => Ok(Value::Int(values.borrow().len() as i64)),
+        _ => analyzer_guaranteed!("the analyzer prevents this: {val:?}"),
     }
 }
 
-fn list_contains(val: &Value, args: Vec<Value>) -> Value {
+fn list_contains(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     match val {
-        Value::List(values) => Value::Bool(values.borrow().contains(&args[0])),
-        _ => unreachable!("the analyzer prevents this: {val:?}"),
+        Value::List(values) => Ok(Value::Bool(values.borrow().contains(&args[0]))),
+        _ => analyzer_guaranteed!("the analyzer prevents this: {val:?}"),
     }
 }
 
-fn string_split(val: &Value, args: Vec<Value>) -> Value {
+fn string_split(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     match (val, &args[0]) {
         (Value::String(string), Value::String(split)) => {
             let raw = string
                 .split(split)
                 .map(|element| Value::String(element.to_string()))
                 .collect();
-            Value::List(Rc::new(RefCell::new(raw)))
+            Ok(Value::List(Rc::new(RefCell::new(raw))))
         }
-        _ => unreachable!("the analyzer prevents this: {val:?}"),
+        _ => analyzer_guaranteed!("the analyzer prevents this: {val:?}"),
     }
 }
 
-fn string_startswith(val: &Value, args: Vec<Value>) -> Value {
+fn string_startswith(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     match (val, &args[0]) {
         (Value::String(string), Value::String(startswith)) => {
-            Value::Bool(string.starts_with(startswith))
+            Ok(Value::Bool(string.starts_with(startswith)))
         }
-        _ => unreachable!("the analyzer prevents this: {val:?}"),
+        _ => analyzer_guaranteed!("the analyzer prevents this: {val:?}"),
     }
 }
 
-fn string_contains(val: &Value, args: Vec<Value>) -> Value {
+fn string_contains(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     match (val, &args[0]) {
-        (Value::String(string), Value::String(contains)) => Value::Bool(string.contains(contains)),
-        _ => unreachable!("the analyzer prevents this: {val:?}"),
+        (Value::String(string), Value::String(contains)) => {
+            Ok(Value::Bool(string.contains(contains)))
+        }
+        _ => analyzer_guaranteed!("the analyzer prevents this: {val:?}"),
     }
 }
 
-fn string_replace(val: &Value, args: Vec<Value>) -> Value {
+fn string_replace(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     match (val, &args[0], &args[1]) {
-        (Value::String(string), Value::String(replacewhat), Value::String(replacewith)) => {
-            Value::String(string.replace(replacewhat, replacewith).to_string())
-        }
-        _ => unreachable!("the analyzer prevents this: {val:?}"),
+        (Value::String(string), Value::String(replacewhat), Value::String(replacewith)) => Ok(
+            Value::String(string.replace(replacewhat, replacewith).to_string()),
+        ),
+        _ => analyzer_guaranteed!("the analyzer prevents this: {val:?}"),
     }
 }
 
-fn speicherbox_nehme(val: &Value, args: Vec<Value>) -> Value {
+fn speicherbox_nehme(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     match (val, &args[0]) {
-        (Value::Speicherbox(inner), Value::String(key)) => match inner.get(key) {
-            Some(res) => res.clone(),
-            None => Value::Unit,
+        (Value::Speicherbox(inner), Value::String(key)) => match inner.borrow().get(key) {
+            Some(res) => Ok(res.clone()),
+            None => Ok(Value::Unit),
         },
-        (_, _) => unreachable!("the analyzer prevents this: {val}"),
+        (_, _) => analyzer_guaranteed!("the analyzer prevents this: {val}"),
     }
 }
 
-fn speicherbox_datentyp_von(val: &Value, args: Vec<Value>) -> Value {
+fn speicherbox_datentyp_von(val: &Value, args: Vec<Value>) -> Result<Value, InterruptKind> {
     let type_ = match (val, &args[0]) {
-        (Value::Speicherbox(inner), Value::String(key)) => match inner.get(key) {
+        (Value::Speicherbox(inner), Value::String(key)) => match inner.borrow().get(key) {
             Some(res) => res.clone(),
             None => Value::Unit,
         },
-        (_, _) => unreachable!("the analyzer prevents this: {val}"),
+        (_, _) => analyzer_guaranteed!("the analyzer prevents this: {val}"),
     }
     .as_type();
 
-    Value::String(type_.to_string())
+    Ok(Value::String(type_.to_string()))
 }
 
-fn speicherbox_keys(val: &Value, _args: Vec<Value>) -> Value {
+fn speicherbox_keys(val: &Value, _args: Vec<Value>) -> Result<Value, InterruptKind> {
     match val {
         Value::Speicherbox(inner) => {
             let list_inner = inner
+                .borrow()
                 .iter()
                 .map(|(key, _)| Value::String(key.clone()))
                 .collect();
 
-            Value::List(Rc::new(RefCell::new(list_inner)))
+            Ok(Value::List(Rc::new(RefCell::new(list_inner))))
         }
-        _ => unreachable!("the analyzer prevents this: {val}"),
+        _ => analyzer_guaranteed!("the analyzer prevents this: {val}"),
     }
 }
 
-fn value_type(val: &Value, _args: Vec<Value>) -> Value {
-    Value::String(val.as_type().to_string())
+fn value_type(val: &Value, _args: Vec<Value>) -> Result<Value, InterruptKind> {
+    Ok(Value::String(val.as_type().to_string()))
 }
 
 impl Value {
@@ -194,8 +398,8 @@ impl Value {
         Rc::new(RefCell::new(self))
     }
 
-    pub fn member(&self, member: &str) -> Value {
-        match (self, member) {
+    pub fn member(&self, member: &str) -> Result<Value, InterruptKind> {
+        let func = match (self, member) {
             (Value::List(_), "Hinzufügen") => {
                 Value::BuiltinFunction(Box::new(self.clone()), list_push)
             }
@@ -230,26 +434,51 @@ impl Value {
                 Value::BuiltinFunction(Box::new(self.clone()), speicherbox_datentyp_von)
             }
             (_, "Datentyp") => Value::BuiltinFunction(Box::new(self.clone()), value_type),
-            (Value::Objekt(members), member) => members
-                .borrow()
-                .get(member)
-                .expect("the analyzer guanrantees valid member accesses")
-                .clone(),
-            (_, _) => unreachable!("the analyzer prevents this"),
-        }
+            (Value::Objekt(members), member) => match members.borrow().get(member) {
+                Some(value) => value.clone(),
+                None => analyzer_guaranteed!("the analyzer guarantees valid member accesses"),
+            },
+            (_, _) => analyzer_guaranteed!("the analyzer prevents this"),
+        };
+        Ok(func)
     }
 
-    fn display(&self) -> String {
-        match self {
+    pub(crate) fn display(&self, options: DisplayOptions) -> String {
+        self.display_inner(options, &mut vec![])
+    }
+
+    /// `path` contains the addresses of the containers which are currently displayed.
+    fn display_inner(&self, options: DisplayOptions, path: &mut Vec<*const ()>) -> String {
+        let address = match self {
+            Value::List(inner) => Some(Rc::as_ptr(inner) as *const ()),
+            Value::Speicherbox(inner) => Some(Rc::as_ptr(inner) as *const ()),
+            Value::Objekt(inner) => Some(Rc::as_ptr(inner) as *const ()),
+            Value::Ptr(inner) => Some(Rc::as_ptr(inner) as *const ()),
+            _ => None,
+        };
+        if let Some(address) = address {
+            if path.contains(&address) {
+                return "<Zyklus>".to_string();
+            }
+            path.push(address);
+        }
+
+        let res = match self {
             Value::Int(inner) => inner.to_string(),
-            Value::Float(inner) => inner.to_string().replace('.', ","),
+            Value::Float(inner) => match options.float_notation {
+                FloatNotation::Decimal => inner.to_string(),
+                FloatNotation::Scientific => format!("{inner:e}"),
+            }
+            .replace('.', ","),
             Value::Char(inner) => (*inner as char).to_string(),
             Value::String(inner) => inner.clone(),
             Value::Speicherbox(inner) => {
                 let inner_str = inner
+                    .borrow()
                     .iter()
                     .map(|(key, value)| {
-                        format!("{key}: {}", value.to_string().replace('\n', "\n    "))
+                        let value = value.display_inner(options, path);
+                        format!("{key}: {}", value.replace('\n', "\n    "))
                     })
                     .collect::<Vec<String>>()
                     .join(",\n    ");
@@ -260,7 +489,8 @@ impl Value {
                     .borrow()
                     .iter()
                     .map(|(key, value)| {
-                        format!("{key}: {}", value.to_string().replace('\n', "\n    "))
+                        let value = value.display_inner(options, path);
+                        format!("{key}: {}", value.replace('\n', "\n    "))
                     })
                     .collect::<Vec<String>>()
                     .join(",\n    ");
@@ -271,33 +501,58 @@ impl Value {
                 inner
                     .borrow()
                     .iter()
-                    .map(|val| val.display())
+                    .map(|val| val.display_inner(options, path))
                     .collect::<Vec<String>>()
                     .join(" / ")
             ),
             Value::Bool(inner) => format!("{inner}"),
             Value::Unit => "Nichts".to_string(),
             Value::BuiltinFunction(_, _) => "<Eingebaute-Funktion>".to_string(),
-            Value::Ptr(inner) => format!("Zeiger auf {}", inner.borrow()),
+            Value::Function(name, _) => format!("<Funktion {name}>"),
+            Value::Ptr(inner) => match options.pointer_notation {
+                PointerNotation::Pointee => {
+                    format!("Zeiger auf {}", inner.borrow().display_inner(options, path))
+                }
+                PointerNotation::Opaque => "<Zeiger>".to_string(),
+            },
+        };
+
+        if address.is_some() {
+            path.pop();
         }
+        res
     }
 }
 
+/// Interrupts the regular control flow of a program.
 #[derive(Clone, Debug)]
 pub enum InterruptKind {
+    /// Returns the contained value from the current function.
     Return(Value),
     Break,
     Continue,
+    /// A runtime error, which terminates the program unless it is caught using `Versuche`.
     Error(interpreter::Error),
+    /// Terminates the program with the contained exit code (`Aufgeben`).
     Exit(i64),
+    /// Calls the currently executed function again using the contained arguments.
+    /// Raised by calls of a function from the tail position of its own body, which are executed
+    /// in a loop instead of nesting another call.
+    TailCall(Vec<Value>),
 }
 
 impl InterruptKind {
+    /// Converts an interruption which ended a function call into the result of the call.
+    /// Tail calls must be executed by the caller instead, as their arguments belong to the call
+    /// being repeated.
     pub fn into_value(self) -> Result<Value, InterruptKind> {
         match self {
             Self::Return(val) => Ok(val),
+            Self::Break | Self::Continue => Ok(Value::Unit),
             kind @ (Self::Error(_) | Self::Exit(_)) => Err(kind),
-            _ => Ok(Value::Unit),
+            Self::TailCall(_) => {
+                unreachable!("tail calls are repeated by the caller before they become a result")
+            }
         }
     }
 }
@@ -344,10 +599,12 @@ from_impl!(Bool, bool);
 
 macro_rules! unwrap_impl {
     ($variant:ident, $res:ty, $name:ident) => {
-        pub fn $name(self) -> $res {
+        pub fn $name(self) -> Result<$res, InterruptKind> {
             match self {
-                Self::$variant(val) => val,
-                other => panic!(concat!("called ", stringify!($name), " on `{:?}`"), other),
+                Self::$variant(val) => Ok(val),
+                other => {
+                    analyzer_guaranteed!(concat!("called ", stringify!($name), " on `{:?}`"), other)
+                }
             }
         }
     };
@@ -355,6 +612,8 @@ macro_rules! unwrap_impl {
 
 impl Value {
     unwrap_impl!(Int, i64, unwrap_int);
+    unwrap_impl!(Float, f64, unwrap_float);
     unwrap_impl!(Bool, bool, unwrap_bool);
     unwrap_impl!(Ptr, Rc<RefCell<Value>>, unwrap_ptr);
+    unwrap_impl!(List, Rc<RefCell<Vec<Value>>>, unwrap_list);
 }
@@ -4,7 +4,7 @@ use std::{
     mem,
 };
 
-use hpi_analyzer::{ast::*, AssignOp, InfixOp, Type};
+use hpi_analyzer::{ast::*, AssignOp, InfixOp, Span, Type};
 
 use crate::c_ast::*;
 
@@ -154,16 +154,19 @@ impl<'src> Transpiler<'src> {
                     result_type: Type::String(0),
                     func: AnalyzedCallBase::Ident("type_descriptor_setup"),
                     args: vec![],
+                    function_index: None,
                 }))),
                 AnalyzedStatement::Expr(AnalyzedExpression::Call(Box::new(AnalyzedCallExpr {
                     result_type: Type::String(0),
                     func: AnalyzedCallBase::Ident("global_variable_setup"),
                     args: vec![],
+                    function_index: None,
                 }))),
                 AnalyzedStatement::Expr(AnalyzedExpression::Call(Box::new(AnalyzedCallExpr {
                     result_type: Type::String(0),
                     func: AnalyzedCallBase::Ident("bewerbung"),
                     args: vec![],
+                    function_index: None,
                 }))),
                 AnalyzedStatement::Expr(AnalyzedExpression::Call(Box::new(AnalyzedCallExpr {
                     result_type: Type::Nichts,
@@ -172,14 +175,19 @@ impl<'src> Transpiler<'src> {
                         result_type: Type::Int(0),
                         func: AnalyzedCallBase::Ident("__hpi_internal_generate_matrikelnummer"),
                         args: vec![],
+                        function_index: None,
                     }))],
+                    function_index: None,
                 }))),
                 AnalyzedStatement::Expr(AnalyzedExpression::Call(Box::new(AnalyzedCallExpr {
                     result_type: Type::String(0),
                     func: AnalyzedCallBase::Ident("studium"),
                     args: vec![],
+                    function_index: None,
                 }))),
             ],
+            // the setup calls do not appear in the source code
+            stmt_spans: vec![Span::dummy(); 5],
             expr: Some(AnalyzedExpression::Int(0)),
         };
 